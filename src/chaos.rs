@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{PoisonError, RwLock};
+use std::time::Duration;
+
+// Chaos mode.
+// --------------------------------------------------
+
+// Test-only failure injection for config loading, so services can test their
+// degraded-startup behavior against realistic config failures:
+//
+// let _guard = ChaosScope::new()
+//     .fail_key("DB_PASSWORD")
+//     .delay_fetches(Duration::from_secs(2))
+//     .apply();
+//
+// While the guard is alive, loading any env variable or secret key named in
+// fail_key returns an InjectedConfigFailure error, and every secrets fetch is
+// delayed by the given duration (the longest delay wins if several scopes are
+// active). Available with the "testing" feature.
+#[derive(Debug, Clone, Default)]
+pub struct ChaosScope {
+    failing_keys: HashSet<String>,
+    fetch_delay: Option<Duration>,
+}
+
+#[must_use = "chaos mode is disabled as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct ChaosGuard {
+    id: u64,
+}
+
+static ACTIVE_SCOPES: RwLock<Vec<(u64, ChaosScope)>> = RwLock::new(Vec::new());
+static NEXT_SCOPE_ID: AtomicU64 = AtomicU64::new(0);
+
+impl ChaosScope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fail_key(mut self, key: impl Into<String>) -> Self {
+        self.failing_keys.insert(key.into());
+        self
+    }
+
+    pub fn delay_fetches(mut self, delay: Duration) -> Self {
+        self.fetch_delay = Some(delay);
+        self
+    }
+
+    pub fn apply(self) -> ChaosGuard {
+        let id = NEXT_SCOPE_ID.fetch_add(1, Ordering::Relaxed);
+        ACTIVE_SCOPES
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((id, self));
+        ChaosGuard { id }
+    }
+}
+
+impl Drop for ChaosGuard {
+    fn drop(&mut self) {
+        ACTIVE_SCOPES
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|(id, _)| *id != self.id);
+    }
+}
+
+pub(crate) fn is_key_failing(key: &str) -> bool {
+    ACTIVE_SCOPES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .any(|(_, scope)| scope.failing_keys.contains(key))
+}
+
+pub(crate) fn fetch_delay() -> Option<Duration> {
+    ACTIVE_SCOPES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .filter_map(|(_, scope)| scope.fetch_delay)
+        .max()
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        define_env_config, define_env_variable, load_env, EnvConfigEnum, EnvOverrideScope,
+    };
+
+    use super::{fetch_delay, is_key_failing, ChaosScope};
+
+    define_env_variable!(CHAOS_TEST_REGION);
+
+    define_env_config!(
+        ChaosConfig,
+        Region => CHAOS_TEST_REGION,
+    );
+
+    #[test]
+    fn test_chaos_fail_key() {
+        let _overrides = EnvOverrideScope::new()
+            .set("CHAOS_TEST_REGION", "us-west-2")
+            .apply();
+        assert!(load_env::<ChaosConfig>().is_ok());
+
+        let guard = ChaosScope::new().fail_key("CHAOS_TEST_REGION").apply();
+        assert!(is_key_failing("CHAOS_TEST_REGION"));
+        assert!(load_env::<ChaosConfig>().is_err());
+
+        drop(guard);
+        assert!(!is_key_failing("CHAOS_TEST_REGION"));
+        assert!(load_env::<ChaosConfig>().is_ok());
+    }
+
+    #[test]
+    fn test_chaos_fetch_delay_longest_wins() {
+        let _short = ChaosScope::new()
+            .delay_fetches(Duration::from_millis(1))
+            .apply();
+        let _long = ChaosScope::new()
+            .delay_fetches(Duration::from_millis(5))
+            .apply();
+        assert!(fetch_delay().unwrap() >= Duration::from_millis(5));
+    }
+}
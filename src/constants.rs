@@ -2,3 +2,4 @@ use crate::define_env_variable;
 
 define_env_variable!(SECRETS_REGION);
 define_env_variable!(SECRETS_ID);
+define_env_variable!(STAGE);
@@ -0,0 +1,260 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use fractic_server_error::{define_internal_error, ServerError};
+
+use crate::error_chain::ErrorChain;
+
+// Config errors.
+// --------------------------------------------------
+
+// All fallible functions of this crate return a ConfigError. The underlying
+// cause (ex. an AWS SDK, IO or parse error) is kept as its source(), so the
+// errors compose with anyhow/eyre, and error reporters show the full chain:
+//
+// let env = load_env::<EnvConfig>().context("loading the service config")?;
+//
+// Errors defined by this crate carry their stable code (see error_code.rs) and
+// the values they were built from as fields, next to the English message:
+//
+// error.error_code() == Some("ENV002")
+// error.message() == "Missing environment variable 'REGION'."
+// error.field("missing_var") == Some("REGION")
+//
+// Display renders both, ex. "[ENV002] Missing environment variable 'REGION'.".
+//
+// ConfigErrors are cheap to clone (the cause is shared between clones), and
+// convert into a fractic ServerError with ?, for services built on
+// fractic-server-error (the chain is then rendered into its debug string).
+#[derive(Clone)]
+pub struct ConfigError {
+    code: Option<&'static str>,
+    message: String,
+    fields: Vec<(&'static str, String)>,
+    details: Option<String>,
+    source: Option<Arc<dyn Error + Send + Sync>>,
+    critical: bool,
+}
+
+impl ConfigError {
+    pub(crate) fn new(message: String) -> Self {
+        Self {
+            code: None,
+            message,
+            fields: Vec::new(),
+            details: None,
+            source: None,
+            critical: false,
+        }
+    }
+
+    // Errors that indicate a bug in this crate (or a broken invariant), rather
+    // than a problem with the config.
+    pub(crate) fn critical(message: &str) -> Self {
+        Self {
+            critical: true,
+            ..Self::new(message.to_string())
+        }
+    }
+
+    pub(crate) fn with_code(
+        mut self,
+        code: &'static str,
+        fields: Vec<(&'static str, String)>,
+    ) -> Self {
+        self.code = Some(code);
+        self.fields = fields;
+        self
+    }
+
+    pub(crate) fn with_details(mut self, details: String) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub(crate) fn with_source(mut self, source: impl Error + Send + Sync + 'static) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    pub fn code(&self) -> Option<&'static str> {
+        self.code
+    }
+
+    // The English message, without the code.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    // The values the message was built from, by placeholder name (ex.
+    // "missing_var"), for rendering the error in another form (see
+    // localize.rs).
+    pub fn fields(&self) -> &[(&'static str, String)] {
+        &self.fields
+    }
+
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Debug information on causes that aren't errors themselves (ex. the list
+    // of attempted keys).
+    pub fn details(&self) -> Option<&str> {
+        self.details.as_deref()
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "[{code}] {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl fmt::Debug for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigError")
+            .field("code", &self.code)
+            .field("message", &self.message)
+            .field("fields", &self.fields)
+            .field("details", &self.details)
+            .field("source", &self.source)
+            .field("critical", &self.critical)
+            .finish()
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn Error + 'static))
+    }
+}
+
+define_internal_error!(FailedToLoadConfig, "{message}", { message: &str });
+
+impl From<ConfigError> for ServerError {
+    fn from(error: ConfigError) -> Self {
+        let chain = match &error.details {
+            Some(details) => format!("{:?} ({details})", ErrorChain(&error)),
+            None => format!("{:?}", ErrorChain(&error)),
+        };
+        FailedToLoadConfig::with_debug(&error.to_string(), &chain)
+    }
+}
+
+// Errors of a ServerError-based callback (ex. a validator shared with other
+// fractic code), kept as message and debug text.
+impl From<ServerError> for ConfigError {
+    fn from(error: ServerError) -> Self {
+        ConfigError::new(error.to_string()).with_details(format!("{error:?}"))
+    }
+}
+
+// Declares an error of this crate, with its code, its message template, and
+// constructors returning a ConfigError:
+//
+// define_config_error!(
+//     FailedToReadConfigFile,
+//     "ENV027",
+//     "Failed to read config file '{path}'.",
+//     { path: &str }
+// );
+//
+// FailedToReadConfigFile::new(path)
+// FailedToReadConfigFile::with_source(path, io_error)
+// FailedToReadConfigFile::with_debug(path, &attempts)
+macro_rules! define_config_error {
+    ($name:ident, $code:literal, $msg:literal) => {
+        $crate::error::define_config_error!($name, $code, $msg, {});
+    };
+    ($name:ident, $code:literal, $msg:literal, { $($arg:ident: $t:ty),* $(,)? }) => {
+        pub struct $name;
+
+        #[allow(dead_code, clippy::new_ret_no_self, clippy::too_many_arguments)]
+        impl $name {
+            pub const CODE: &'static str = $code;
+            pub const TEMPLATE: &'static str = $msg;
+
+            pub fn new($($arg: $t),*) -> $crate::ConfigError {
+                $crate::ConfigError::new(format!($msg)).with_code(
+                    $code,
+                    vec![$((stringify!($arg), $arg.to_string())),*],
+                )
+            }
+
+            pub fn with_source(
+                $($arg: $t,)*
+                source: impl ::std::error::Error + Send + Sync + 'static,
+            ) -> $crate::ConfigError {
+                Self::new($($arg),*).with_source(source)
+            }
+
+            pub fn with_debug(
+                $($arg: $t,)*
+                debug: &impl ::std::fmt::Debug,
+            ) -> $crate::ConfigError {
+                Self::new($($arg),*).with_details(format!("{debug:?}"))
+            }
+        }
+    };
+}
+pub(crate) use define_config_error;
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::fmt;
+
+    define_config_error!(FailedToOpen, "TST001", "Failed to open '{path}'.", { path: &str });
+
+    #[derive(Debug)]
+    struct Outer(std::io::Error);
+
+    impl fmt::Display for Outer {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "failed to connect")
+        }
+    }
+
+    impl Error for Outer {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn test_source_chain() {
+        let error = FailedToOpen::with_source(
+            "config.json",
+            Outer(std::io::Error::other("connection refused")),
+        );
+        assert_eq!(error.to_string(), "[TST001] Failed to open 'config.json'.");
+        assert_eq!(error.code(), Some("TST001"));
+        assert_eq!(error.message(), "Failed to open 'config.json'.");
+        assert_eq!(error.field("path"), Some("config.json"));
+
+        let outer = error.source().unwrap();
+        assert_eq!(outer.to_string(), "failed to connect");
+        let inner = outer.source().unwrap();
+        assert_eq!(inner.to_string(), "connection refused");
+        assert!(inner.source().is_none());
+
+        let clone = error.clone();
+        assert_eq!(clone.source().unwrap().to_string(), "failed to connect");
+    }
+}
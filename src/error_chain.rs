@@ -0,0 +1,67 @@
+use std::error::Error;
+use std::fmt;
+
+// Error chains.
+// --------------------------------------------------
+
+// Where an error has to be flattened into a string (ex. when converting into a
+// fractic ServerError, or when listing the reasons of several failed attempts),
+// it is rendered together with its full source() chain, ex.:
+//
+// "dispatch failure: io error: Connection refused (os error 111)"
+//
+// Usage: format!("{:?}", ErrorChain(&e))
+pub(crate) struct ErrorChain<'a>(pub(crate) &'a (dyn Error + 'static));
+
+impl fmt::Debug for ErrorChain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        let mut source = self.0.source();
+        while let Some(cause) = source {
+            write!(f, ": {cause}")?;
+            source = cause.source();
+        }
+        Ok(())
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::fmt;
+
+    use super::ErrorChain;
+
+    #[derive(Debug)]
+    struct Outer(std::io::Error);
+
+    impl fmt::Display for Outer {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "failed to fetch")
+        }
+    }
+
+    impl Error for Outer {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn test_error_chain_includes_sources() {
+        let error = Outer(std::io::Error::other("connection refused"));
+        assert_eq!(
+            format!("{:?}", ErrorChain(&error)),
+            "failed to fetch: connection refused"
+        );
+    }
+
+    #[test]
+    fn test_error_chain_single_error() {
+        let error = std::io::Error::other("not found");
+        assert_eq!(format!("{:?}", ErrorChain(&error)), "not found");
+    }
+}
@@ -0,0 +1,559 @@
+use crate::ConfigError;
+
+// Error codes.
+// --------------------------------------------------
+
+// Every error defined by this crate has a stable code, carried on the error
+// next to its message (and shown in front of it when displayed, ex. "[ENV002]
+// Missing environment variable 'REGION'."), so that alerting and runbooks can
+// match on the code instead of the message text:
+//
+// match error.error_code() {
+//     Some("ENV002") => page_oncall("config missing"),
+//     Some(code) if code.starts_with("SEC") => retry_later(),
+//     _ => {}
+// }
+//
+// Codes are prefixed by module (ENV for environment variables and config
+// sources, SEC for secrets, K8S for Kubernetes), and never reused or
+// renumbered. New errors take the next free number of their module, and must be
+// added to ERROR_CODES (and to its test). Errors from other crates (and critical
+// errors) have no code.
+pub trait ErrorCode {
+    fn error_code(&self) -> Option<&'static str>;
+}
+
+impl ErrorCode for ConfigError {
+    fn error_code(&self) -> Option<&'static str> {
+        self.code()
+    }
+}
+
+// All error codes, with the name of the error type and its message template
+// (see localize.rs).
+pub static ERROR_CODES: &[(&str, &str, &str)] = &[
+    (
+        "ENV001",
+        "InvalidEnvCloneInto",
+        "Invalid clone_into(...). Parent config missing key '{missing_var}'.",
+    ),
+    (
+        "ENV002",
+        "MissingEnvVariableError",
+        "Missing environment variable '{missing_var}'.{hint}",
+    ),
+    (
+        "ENV003",
+        "MissingEnvVariables",
+        "Missing environment variables: {missing_vars}.",
+    ),
+    (
+        "ENV004",
+        "NotPrewarmed",
+        "No prewarmed value of type '{type_name}'. Call prewarm() during init first.",
+    ),
+    (
+        "ENV005",
+        "FailedToServeConfig",
+        "Failed to serve config on socket '{path}'.",
+    ),
+    (
+        "ENV006",
+        "FailedToReceiveSharedConfig",
+        "Failed to receive config from socket '{path}'.",
+    ),
+    (
+        "ENV007",
+        "InvalidSharedConfig",
+        "Config received from socket '{path}' is invalid: {reason}.",
+    ),
+    (
+        "ENV008",
+        "StartupBudgetExceeded",
+        "Config loading exceeded its {budget} budget, still waiting for: {pending}.",
+    ),
+    (
+        "ENV009",
+        "InvalidConfigWindow",
+        "Invalid window into '{child}' at {location}.",
+    ),
+    (
+        "ENV010",
+        "UndeclaredDynamicEnvVariable",
+        "Environment variable '{var}' is not declared in the dynamic schema.",
+    ),
+    (
+        "ENV011",
+        "InvalidUtf8EnvVariable",
+        "Environment variable '{var}' is set, but its value is not valid UTF-8.",
+    ),
+    (
+        "ENV012",
+        "FailedToReadEnvVariableFile",
+        "Failed to read environment variable '{var}' from file '{path}'.",
+    ),
+    (
+        "ENV013",
+        "ConflictingEnvVariableFile",
+        "Both '{var}' and '{file_var}' are set. Only one of them should be provided.",
+    ),
+    (
+        "ENV014",
+        "InvalidEnvVariableType",
+        "Environment variable '{var}' could not be parsed as '{expected_type}'.",
+    ),
+    (
+        "ENV015",
+        "InvalidBase64Value",
+        "Value of '{key}' is not valid base64 (invalid {position}).",
+    ),
+    (
+        "ENV016",
+        "InvalidPemValue",
+        "Environment variable '{var}' is not a valid PEM {kind}: {reason}.",
+    ),
+    (
+        "ENV017",
+        "InvalidEnvMapEntry",
+        "Environment variable '{var}' has a malformed key-value entry '{entry}'.",
+    ),
+    (
+        "ENV018",
+        "InvalidEnvTableRow",
+        "Environment variable '{var}' has a malformed table row '{row}': {reason}.",
+    ),
+    (
+        "ENV019",
+        "EnvVariableFailedConstraint",
+        "Environment variable '{var}' does not satisfy constraint: {constraint}.",
+    ),
+    (
+        "ENV020",
+        "RemovedEnvVariableSet",
+        "Environment variable '{var}' was removed after {removed_after}, but is still set. Delete it from the environment.",
+    ),
+    (
+        "ENV021",
+        "InvalidEnvConstraint",
+        "Invalid constraint declared for environment variable '{var}': {constraint}.",
+    ),
+    (
+        "ENV022",
+        "FailedEnvMigration",
+        "Failed to migrate environment variable '{var}' to schema version {version}: {reason}.",
+    ),
+    (
+        "ENV023",
+        "EnvConfigValidationFailed",
+        "Environment config failed validation: {reason}.",
+    ),
+    (
+        "ENV024",
+        "EcsMetadataUnavailable",
+        "ECS task metadata is unavailable: '{var}' is not set (not running on Amazon ECS?).",
+    ),
+    (
+        "ENV025",
+        "FailedToFetchMetadata",
+        "Failed to fetch metadata from '{endpoint}'.",
+    ),
+    (
+        "ENV026",
+        "InvalidMetadataResponse",
+        "Invalid metadata response from '{endpoint}': {reason}.",
+    ),
+    (
+        "ENV027",
+        "FailedToReadConfigFile",
+        "Failed to read config file '{path}'.",
+    ),
+    (
+        "ENV028",
+        "InvalidConfigCrateValue",
+        "Value '{key}' of the config::Config source could not be read as a string.",
+    ),
+    (
+        "ENV029",
+        "InvalidFigmentValue",
+        "Value '{key}' of the figment source is a dict, expected a scalar or array.",
+    ),
+    (
+        "ENV030",
+        "InvalidConfigFile",
+        "Config file '{path}' is not a valid {format} object.",
+    ),
+    (
+        "ENV031",
+        "InvalidConfigSignature",
+        "Config file '{path}' failed signature verification: {reason}.",
+    ),
+    (
+        "ENV032",
+        "FailedToOpenRegistryKey",
+        "Failed to open registry key '{path}'.",
+    ),
+    (
+        "ENV033",
+        "FailedToReadRegistryValue",
+        "Failed to read value '{name}' from registry key '{path}'.",
+    ),
+    (
+        "ENV034",
+        "InjectedConfigFailure",
+        "Injected failure for config key '{key}' (chaos mode).",
+    ),
+    (
+        "ENV035",
+        "FailedToWriteConfigSnapshot",
+        "Failed to write config snapshot to '{path}'.",
+    ),
+    (
+        "ENV036",
+        "InvalidFeatureFlagValue",
+        "Feature flag '{var}' has invalid value '{value}' (expected on/off or a percentage like 25%).",
+    ),
+    (
+        "ENV037",
+        "PolicyViolated",
+        "Config violates {count} policy rule(s): {violations}.",
+    ),
+    (
+        "ENV038",
+        "EnvLoadFailed",
+        "Failed to load {count} environment variable(s): {errors}",
+    ),
+    (
+        "ENV039",
+        "EnvKeyGroupNotSatisfied",
+        "Invalid combination of environment variables ({problem}). Expected {expected}.",
+    ),
+    (
+        "ENV040",
+        "InvalidSchemaCommand",
+        "Invalid schema command: {reason}. Usage: print-schema [--format json|markdown|dotenv].",
+    ),
+    (
+        "ENV041",
+        "EnvConfigDiverged",
+        "Config '{config}' was loaded again with different values for: {keys}.",
+    ),
+    (
+        "ENV042",
+        "SchemaManifestOutdated",
+        "Schema manifest '{path}' does not match the config ({changes}). Regenerate it with print-schema --format json.",
+    ),
+    (
+        "ENV043",
+        "SocketPathInUse",
+        "Can't serve the config at '{path}', since a file that isn't a socket already exists there.",
+    ),
+    (
+        "SEC001",
+        "FailedToFetchSecretsJson",
+        "Failed to fetch secret '{secret_id}' (region '{region}') from Amazon Secrets Manager.",
+    ),
+    (
+        "SEC002",
+        "SecretNotFound",
+        "Secret '{secret_id}' (region '{region}') does not exist in Amazon Secrets Manager.",
+    ),
+    (
+        "SEC003",
+        "SecretsAccessDenied",
+        "Access denied to secret '{secret_id}' (region '{region}') in Amazon Secrets Manager.",
+    ),
+    (
+        "SEC004",
+        "SecretsThrottled",
+        "Request for secret '{secret_id}' (region '{region}') was throttled by Amazon Secrets Manager.",
+    ),
+    (
+        "SEC005",
+        "SecretsNetworkError",
+        "Failed to reach Amazon Secrets Manager for secret '{secret_id}' (region '{region}').",
+    ),
+    (
+        "SEC006",
+        "MissingSecretKey",
+        "Secret '{secret_id}' (region '{region}') missing key '{missing_key}'.",
+    ),
+    (
+        "SEC007",
+        "MissingSecretValue",
+        "Missing secret '{missing_key}'.",
+    ),
+    (
+        "SEC008",
+        "FailedToReadKeychain",
+        "Failed to read secret '{key}' (service '{service}') from the OS keychain.",
+    ),
+    (
+        "SEC009",
+        "InvalidSopsFile",
+        "SOPS file '{path}' is invalid: {reason}.",
+    ),
+    (
+        "SEC010",
+        "FailedToDecryptSopsDataKey",
+        "Failed to decrypt the data key of SOPS file '{path}' with any of its age or KMS keys.",
+    ),
+    (
+        "SEC011",
+        "FailedToDecryptSopsValue",
+        "Failed to decrypt value '{key}' of SOPS file '{path}'.",
+    ),
+    (
+        "SEC012",
+        "CoalescedSecretsFetchFailed",
+        "Failed to fetch secret '{secret_id}' (shared with a concurrent fetch): {reason}",
+    ),
+    (
+        "SEC013",
+        "FailedToReadSecretsCache",
+        "Failed to read secrets cache file '{path}'.",
+    ),
+    (
+        "SEC014",
+        "FailedToWriteSecretsCache",
+        "Failed to write secrets cache file '{path}'.",
+    ),
+    (
+        "SEC015",
+        "UnresolvedSecretsIdPlaceholder",
+        "Could not resolve placeholder '{placeholder}' in secret ID '{secret_id}'.",
+    ),
+    (
+        "SEC016",
+        "InvalidTenantId",
+        "Invalid tenant ID '{tenant_id}'. Only alphanumeric characters, '-' and '_' are allowed.",
+    ),
+    (
+        "SEC017",
+        "SecretsInvalidJson",
+        "Secret '{secret_id}' (region '{region}')'s value is not valid JSON.",
+    ),
+    (
+        "SEC018",
+        "InvalidSecretValueType",
+        "Secret '{secret_id}' (region '{region}') key '{key}' is {kind}, expected a string, number or boolean (or declare the key with json = true).",
+    ),
+    (
+        "SEC019",
+        "SecretBinaryNotSupported",
+        "Secret '{secret_id}' was requested as binary, which its SecretsFetcher does not support.",
+    ),
+    (
+        "SEC020",
+        "InvalidSecretBundle",
+        "Secret '{secret_id}' (region '{region}') is not a valid bundle: {reason}.",
+    ),
+    (
+        "SEC021",
+        "InvalidSecretFormat",
+        "Secret '{secret_id}' (region '{region}')'s value is not valid {format}: {reason}.",
+    ),
+    (
+        "SEC022",
+        "InvalidRotationNotification",
+        "Invalid secret rotation notification: {reason}.",
+    ),
+    (
+        "SEC023",
+        "InvalidSecretsCloneInto",
+        "Invalid clone_into(...). Parent config missing secret '{missing_secret}'.",
+    ),
+    (
+        "SEC024",
+        "SecretNotInRotationGroup",
+        "Secret '{key}' is not part of a rotation group.",
+    ),
+    (
+        "SEC025",
+        "FailedToGenerateRdsAuthToken",
+        "Failed to generate an RDS IAM auth token for '{host}': {reason}.",
+    ),
+    (
+        "SEC026",
+        "FailedToAssumeRole",
+        "Failed to assume role '{role_arn}': {reason}.",
+    ),
+    (
+        "SEC027",
+        "SecretsConfigNotRegistered",
+        "Secrets config '{config}' is not registered in the SecretsManagerRegistry.",
+    ),
+    (
+        "SEC028",
+        "InvalidTenantTemplate",
+        "Secret ID template '{template}' doesn't contain the tenant placeholder.",
+    ),
+    (
+        "SEC029",
+        "FailedToServeRotationWebhook",
+        "Failed to serve rotation notifications on '{addr}'.",
+    ),
+    (
+        "SEC030",
+        "RotationWebhookOutsideRuntime",
+        "The rotation webhook on '{addr}' must be started from within a tokio runtime.",
+    ),
+    (
+        "K8S001",
+        "FailedToConnectToK8s",
+        "Failed to create a Kubernetes client from the in-cluster (or local kubeconfig) config.",
+    ),
+    (
+        "K8S002",
+        "FailedToFetchK8sObject",
+        "Failed to fetch {kind} '{namespace}/{name}' from the Kubernetes API.",
+    ),
+    (
+        "K8S003",
+        "InvalidK8sValue",
+        "{kind} '{namespace}/{name}' key '{key}' is not valid UTF-8.",
+    ),
+];
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::*;
+
+    use super::{ErrorCode, ERROR_CODES};
+
+    // Pins each error to its code, and checks that it is listed in ERROR_CODES
+    // with its name and message template.
+    macro_rules! assert_error_codes {
+        ($($name:ident => $code:literal),* $(,)?) => {{
+            $(
+                assert_eq!($name::CODE, $code);
+                assert!(
+                    ERROR_CODES.contains(&($code, stringify!($name), $name::TEMPLATE)),
+                    "{} is not listed in ERROR_CODES",
+                    stringify!($name)
+                );
+            )*
+            [$($code),*].len()
+        }};
+    }
+
+    #[test]
+    fn test_error_code() {
+        assert_eq!(
+            MissingEnvVariableError::new("REGION", "").error_code(),
+            Some("ENV002")
+        );
+        assert_eq!(
+            MissingSecretValue::new("API_KEY").error_code(),
+            Some("SEC007")
+        );
+        assert_eq!(ConfigError::critical("[ENV999] unknown").error_code(), None);
+
+        let error = MissingEnvVariableError::new("REGION", "");
+        assert_eq!(error.message(), "Missing environment variable 'REGION'.");
+        assert_eq!(
+            error.to_string(),
+            "[ENV002] Missing environment variable 'REGION'."
+        );
+    }
+
+    #[test]
+    fn test_error_codes_listed() {
+        let checked = assert_error_codes! {
+            InvalidEnvCloneInto => "ENV001",
+            MissingEnvVariableError => "ENV002",
+            MissingEnvVariables => "ENV003",
+            NotPrewarmed => "ENV004",
+            FailedToServeConfig => "ENV005",
+            FailedToReceiveSharedConfig => "ENV006",
+            InvalidSharedConfig => "ENV007",
+            StartupBudgetExceeded => "ENV008",
+            InvalidConfigWindow => "ENV009",
+            UndeclaredDynamicEnvVariable => "ENV010",
+            InvalidUtf8EnvVariable => "ENV011",
+            FailedToReadEnvVariableFile => "ENV012",
+            ConflictingEnvVariableFile => "ENV013",
+            InvalidEnvVariableType => "ENV014",
+            InvalidBase64Value => "ENV015",
+            InvalidPemValue => "ENV016",
+            InvalidEnvMapEntry => "ENV017",
+            InvalidEnvTableRow => "ENV018",
+            EnvVariableFailedConstraint => "ENV019",
+            RemovedEnvVariableSet => "ENV020",
+            InvalidEnvConstraint => "ENV021",
+            FailedEnvMigration => "ENV022",
+            EnvConfigValidationFailed => "ENV023",
+            EcsMetadataUnavailable => "ENV024",
+            FailedToFetchMetadata => "ENV025",
+            InvalidMetadataResponse => "ENV026",
+            FailedToReadConfigFile => "ENV027",
+            InvalidConfigCrateValue => "ENV028",
+            InvalidFigmentValue => "ENV029",
+            InvalidConfigFile => "ENV030",
+            InvalidConfigSignature => "ENV031",
+            FailedToOpenRegistryKey => "ENV032",
+            FailedToReadRegistryValue => "ENV033",
+            InjectedConfigFailure => "ENV034",
+            FailedToWriteConfigSnapshot => "ENV035",
+            InvalidFeatureFlagValue => "ENV036",
+            PolicyViolated => "ENV037",
+            EnvLoadFailed => "ENV038",
+            EnvKeyGroupNotSatisfied => "ENV039",
+            InvalidSchemaCommand => "ENV040",
+            EnvConfigDiverged => "ENV041",
+            SchemaManifestOutdated => "ENV042",
+            SocketPathInUse => "ENV043",
+            FailedToFetchSecretsJson => "SEC001",
+            SecretNotFound => "SEC002",
+            SecretsAccessDenied => "SEC003",
+            SecretsThrottled => "SEC004",
+            SecretsNetworkError => "SEC005",
+            MissingSecretKey => "SEC006",
+            MissingSecretValue => "SEC007",
+            FailedToReadKeychain => "SEC008",
+            InvalidSopsFile => "SEC009",
+            FailedToDecryptSopsDataKey => "SEC010",
+            FailedToDecryptSopsValue => "SEC011",
+            CoalescedSecretsFetchFailed => "SEC012",
+            FailedToReadSecretsCache => "SEC013",
+            FailedToWriteSecretsCache => "SEC014",
+            UnresolvedSecretsIdPlaceholder => "SEC015",
+            InvalidTenantId => "SEC016",
+            SecretsInvalidJson => "SEC017",
+            InvalidSecretValueType => "SEC018",
+            SecretBinaryNotSupported => "SEC019",
+            InvalidSecretBundle => "SEC020",
+            InvalidSecretFormat => "SEC021",
+            InvalidRotationNotification => "SEC022",
+            InvalidSecretsCloneInto => "SEC023",
+            SecretNotInRotationGroup => "SEC024",
+            FailedToGenerateRdsAuthToken => "SEC025",
+            FailedToAssumeRole => "SEC026",
+            SecretsConfigNotRegistered => "SEC027",
+            InvalidTenantTemplate => "SEC028",
+            FailedToServeRotationWebhook => "SEC029",
+            RotationWebhookOutsideRuntime => "SEC030",
+        };
+        #[cfg(feature = "k8s")]
+        let checked = checked
+            + assert_error_codes! {
+                FailedToConnectToK8s => "K8S001",
+                FailedToFetchK8sObject => "K8S002",
+                InvalidK8sValue => "K8S003",
+            };
+        // The K8S errors are only defined with the "k8s" feature.
+        #[cfg(not(feature = "k8s"))]
+        let checked = checked + 3;
+        assert_eq!(checked, ERROR_CODES.len());
+
+        let codes = ERROR_CODES
+            .iter()
+            .map(|(code, _, _)| code)
+            .collect::<HashSet<_>>();
+        assert_eq!(codes.len(), ERROR_CODES.len());
+    }
+}
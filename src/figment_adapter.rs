@@ -0,0 +1,138 @@
+use figment::value::{Dict, Map, Value};
+use figment::{Figment, Metadata, Profile, Provider};
+
+use crate::ConfigError;
+use crate::{
+    ConfigSource, EnvConfigEnum, EnvVariables, InvalidFigmentValue, SecretValues, SecretsConfigEnum,
+};
+
+// figment adapter.
+// --------------------------------------------------
+
+// Integration with figment (and so Rocket) in both directions. A Figment can
+// feed the typed validation like any other source:
+//
+// let figment = Figment::new()
+//     .merge(Toml::file("App.toml"))
+//     .merge(Env::prefixed("APP_"));
+// let config: EnvVariables<EnvConfig> = load_from_source(&figment)?;
+//
+// Variables are looked up by their exact name first, then lowercased (figment's
+// Env provider lowercases keys). Names can also be dotted paths into nested
+// dicts (ex. "database.host"). Scalars are converted to their string
+// representation, arrays are kept as JSON text, and dicts are rejected.
+//
+// Conversely, loaded EnvVariables and SecretValues are figment Providers, so
+// the validated values can be merged into an application's figment:
+//
+// let rocket_config = rocket::Config::figment().merge(&env).merge(&secrets);
+//
+// Values are provided under their variable names in the default profile.
+// Reading SecretValues this way counts as accessing every secret (see
+// audit.rs). Available with the "figment" feature.
+impl ConfigSource for Figment {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        match read_string(self, key, key)? {
+            None if key.chars().any(char::is_uppercase) => {
+                read_string(self, &key.to_lowercase(), key)
+            }
+            value => Ok(value),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "figment"
+    }
+}
+
+fn read_string(figment: &Figment, path: &str, key: &str) -> Result<Option<String>, ConfigError> {
+    match figment.extract_inner::<serde_json::Value>(path) {
+        Ok(serde_json::Value::Null) => Ok(None),
+        Ok(serde_json::Value::String(s)) => Ok(Some(s)),
+        Ok(serde_json::Value::Object(_)) => Err(InvalidFigmentValue::new(key)),
+        Ok(other) => Ok(Some(other.to_string())),
+        Err(e) if e.missing() => Ok(None),
+        Err(e) => Err(InvalidFigmentValue::with_source(key, e)),
+    }
+}
+
+impl<T: EnvConfigEnum> Provider for EnvVariables<T> {
+    fn metadata(&self) -> Metadata {
+        Metadata::named(std::any::type_name::<Self>())
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, figment::Error> {
+        let dict = T::value_list()
+            .iter()
+            .filter_map(|key| {
+                self.get_optional(key)
+                    .map(|value| (key.as_str().to_string(), Value::from(value.to_string())))
+            })
+            .collect();
+        Ok(Profile::Default.collect(dict))
+    }
+}
+
+impl<T: SecretsConfigEnum> Provider for SecretValues<T> {
+    fn metadata(&self) -> Metadata {
+        Metadata::named(std::any::type_name::<Self>())
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, figment::Error> {
+        let dict = T::value_list()
+            .iter()
+            .map(|key| {
+                self.get(key)
+                    .map(|value| (key.as_str().to_string(), Value::from(value.to_string())))
+                    .map_err(|e| figment::Error::from(e.to_string()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Profile::Default.collect(dict))
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use figment::providers::Serialized;
+    use figment::Figment;
+
+    use crate::{
+        define_env_config, define_env_variable, load_from_source, EnvConfigEnum, EnvVariables,
+    };
+
+    define_env_variable!(FIGMENT_TEST_REGION);
+
+    define_env_config!(
+        FigmentConfig,
+        Region => FIGMENT_TEST_REGION,
+    );
+
+    #[test]
+    fn test_load_from_figment() {
+        let figment = Figment::from(Serialized::default("figment_test_region", "us-west-2"));
+        let config = load_from_source::<FigmentConfig>(&figment).unwrap();
+        assert_eq!(config.get(&FigmentConfig::Region).unwrap(), "us-west-2");
+
+        assert!(load_from_source::<FigmentConfig>(&Figment::new()).is_err());
+    }
+
+    #[test]
+    fn test_env_variables_as_provider() {
+        let env: EnvVariables<FigmentConfig> = EnvVariables::from(HashMap::from([(
+            FIGMENT_TEST_REGION,
+            String::from("us-west-2"),
+        )]));
+        let figment = Figment::new().merge(&env);
+        assert_eq!(
+            figment
+                .extract_inner::<String>(FIGMENT_TEST_REGION)
+                .unwrap(),
+            "us-west-2"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use crate::ConfigError;
+use std::fmt;
+use std::sync::Arc;
+
+// Value codecs.
+// --------------------------------------------------
+
+// Project-specific transformations of a raw value (ex. decrypting with an
+// in-house scheme, decompressing, normalizing a list), declared per key
+// instead of being applied at each call site:
+//
+// struct Rot13;
+//
+// impl ValueCodec for Rot13 {
+//     fn decode(&self, key: &str, raw: &str) -> Result<String, ConfigError> { ... }
+// }
+//
+// define_env_config!(
+//     EnvConfig,
+//     LegacyToken => LEGACY_TOKEN { codec = Rot13 },
+//     Hosts => HOSTS { codec = |_: &str, raw: &str| Ok(raw.replace(';', ",")) },
+// );
+//
+// Codecs run during load, on the value read from the source (not on defaults),
+// before the key's constraints are checked. Several codecs on the same key are
+// applied in order. Errors abort the load like any other invalid value.
+pub trait ValueCodec: Send + Sync {
+    fn decode(&self, key: &str, raw: &str) -> Result<String, ConfigError>;
+}
+
+impl<F: Fn(&str, &str) -> Result<String, ConfigError> + Send + Sync> ValueCodec for F {
+    fn decode(&self, key: &str, raw: &str) -> Result<String, ConfigError> {
+        self(key, raw)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Codec(pub(crate) Arc<dyn ValueCodec>);
+
+impl fmt::Debug for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Codec(..)")
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::ConfigError;
+
+    use crate::{
+        define_env_config, define_env_variable, load_env_with, EnvConfigEnum,
+        EnvVariableFailedConstraint, LoadOptions,
+    };
+
+    use super::ValueCodec;
+
+    struct Reverse;
+
+    impl ValueCodec for Reverse {
+        fn decode(&self, _key: &str, raw: &str) -> Result<String, ConfigError> {
+            Ok(raw.chars().rev().collect())
+        }
+    }
+
+    fn reject_empty(key: &str, raw: &str) -> Result<String, ConfigError> {
+        match raw {
+            "" => Err(EnvVariableFailedConstraint::new(key, "non-empty")),
+            _ => Ok(raw.to_string()),
+        }
+    }
+
+    define_env_variable!(CODEC_TOKEN);
+    define_env_variable!(CODEC_HOSTS);
+
+    define_env_config!(
+        CodecConfig,
+        Token => CODEC_TOKEN { codec = Reverse, one_of = ["abc"] },
+        Hosts => CODEC_HOSTS {
+            codec = reject_empty,
+            codec = |_: &str, raw: &str| Ok(raw.replace(';', ",")),
+            default = "a;b",
+        },
+    );
+
+    #[test]
+    fn test_codecs() {
+        let fake_env = HashMap::from([("CODEC_TOKEN", "cba"), ("CODEC_HOSTS", "x;y")]);
+        let config = load_env_with::<CodecConfig>(LoadOptions::new().reader(&fake_env)).unwrap();
+        assert_eq!(config.get(&CodecConfig::Token).unwrap(), "abc");
+        assert_eq!(config.get(&CodecConfig::Hosts).unwrap(), "x,y");
+
+        let fake_env = HashMap::from([("CODEC_TOKEN", "cba")]);
+        let config = load_env_with::<CodecConfig>(LoadOptions::new().reader(&fake_env)).unwrap();
+        assert_eq!(config.get(&CodecConfig::Hosts).unwrap(), "a;b");
+
+        let fake_env = HashMap::from([("CODEC_TOKEN", "cba"), ("CODEC_HOSTS", "")]);
+        assert!(load_env_with::<CodecConfig>(LoadOptions::new().reader(&fake_env)).is_err());
+    }
+}
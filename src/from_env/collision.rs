@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, PoisonError};
+
+use super::warnings::{report_warning, ConfigWarning};
+use super::EnvConfigEnum;
+
+// Variable name collisions.
+// --------------------------------------------------
+
+// Two crates (or modules) each declaring their own variable of the same name
+// (ex. both running define_env_variable!(REGION)) silently read the same env
+// variable, even if they mean different things by it. Every config declared
+// with define_env_config! records where its variables come from, and the
+// configs are registered on each load_env (or load_env_with), so that a
+// collision is reported as a warning (see warnings.rs) the first time both
+// declarations are seen. A service can also lint all its configs at startup
+// (or in a test), without loading them:
+//
+// register_env_names::<BillingConfig>();
+// register_env_names::<SearchConfig>();
+// assert!(env_name_collisions().is_empty());
+//
+// Configs sharing the same variable (ex. both using crate::DB_HOST) don't
+// collide, since they use a single declaration.
+//
+// Detection is per process, and only covers configs that have been loaded or
+// registered: two crates declaring the same name go undetected unless both
+// their configs are loaded (or registered) in the same process.
+
+// A variable used by a config, recorded by define_env_config!.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+pub struct KeyOrigin {
+    pub name: &'static str,
+    // Address of the variable's static, which tells apart two declarations of
+    // the same name.
+    pub address: usize,
+    pub module: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvNameCollision {
+    pub name: &'static str,
+    // One module per declaration, in registration order.
+    pub modules: Vec<&'static str>,
+}
+
+#[derive(Default)]
+struct Declarations {
+    configs: HashSet<&'static str>,
+    by_name: HashMap<&'static str, Vec<KeyOrigin>>,
+}
+
+static DECLARATIONS: Mutex<Option<Declarations>> = Mutex::new(None);
+
+pub fn register_env_names<T: EnvConfigEnum>() {
+    let mut declarations = DECLARATIONS.lock().unwrap_or_else(PoisonError::into_inner);
+    let declarations = declarations.get_or_insert_with(Default::default);
+    if !declarations.configs.insert(std::any::type_name::<T>()) {
+        return;
+    }
+    for origin in T::key_origins() {
+        let known = declarations.by_name.entry(origin.name).or_default();
+        if known.iter().any(|other| other.address == origin.address) {
+            continue;
+        }
+        known.push(origin);
+        if known.len() > 1 {
+            report_warning(ConfigWarning::VariableNameCollision(collision(known)));
+        }
+    }
+}
+
+// Collisions between the configs registered so far, by variable name.
+pub fn env_name_collisions() -> Vec<EnvNameCollision> {
+    let declarations = DECLARATIONS.lock().unwrap_or_else(PoisonError::into_inner);
+    let Some(declarations) = declarations.as_ref() else {
+        return Vec::new();
+    };
+    let mut collisions = declarations
+        .by_name
+        .values()
+        .filter(|known| known.len() > 1)
+        .map(|known| collision(known))
+        .collect::<Vec<_>>();
+    collisions.sort_by_key(|collision| collision.name);
+    collisions
+}
+
+fn collision(known: &[KeyOrigin]) -> EnvNameCollision {
+    EnvNameCollision {
+        name: known[0].name,
+        modules: known.iter().map(|origin| origin.module).collect(),
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::from_env::warnings::{reported_warnings, ConfigWarning};
+    use crate::{load_env_with, LoadOptions};
+
+    use super::{env_name_collisions, register_env_names, EnvNameCollision};
+
+    mod billing {
+        use crate::{define_env_config, define_env_variable, EnvConfigEnum};
+
+        define_env_variable!(COLLISION_REGION);
+        define_env_variable!(COLLISION_SHARED);
+
+        define_env_config!(
+            BillingConfig,
+            Region => COLLISION_REGION,
+            Shared => COLLISION_SHARED,
+        );
+    }
+
+    mod search {
+        use crate::{define_env_config, define_env_variable, EnvConfigEnum};
+
+        use super::billing::COLLISION_SHARED;
+
+        define_env_variable!(COLLISION_REGION);
+
+        define_env_config!(
+            SearchConfig,
+            Region => COLLISION_REGION,
+            Shared => COLLISION_SHARED,
+        );
+    }
+
+    #[test]
+    fn test_name_collision() {
+        let source = HashMap::from([
+            ("COLLISION_REGION", "us-west-2"),
+            ("COLLISION_SHARED", "shared"),
+        ]);
+        load_env_with::<billing::BillingConfig>(LoadOptions::new().reader(source.clone())).unwrap();
+        register_env_names::<search::SearchConfig>();
+
+        let expected = EnvNameCollision {
+            name: "COLLISION_REGION",
+            modules: vec![
+                "fractic_env_config::from_env::collision::tests::billing",
+                "fractic_env_config::from_env::collision::tests::search",
+            ],
+        };
+        let collisions = env_name_collisions()
+            .into_iter()
+            .filter(|collision| collision.name.starts_with("COLLISION_"))
+            .collect::<Vec<_>>();
+        assert_eq!(collisions, std::slice::from_ref(&expected));
+        assert!(reported_warnings().contains(&ConfigWarning::VariableNameCollision(expected)));
+
+        // Registering (or loading) a config again doesn't report it again.
+        load_env_with::<search::SearchConfig>(LoadOptions::new().reader(source)).unwrap();
+        let reports = reported_warnings()
+            .into_iter()
+            .filter(|warning| {
+                matches!(warning, ConfigWarning::VariableNameCollision(collision) if collision.name == "COLLISION_REGION")
+            })
+            .count();
+        assert_eq!(reports, 1);
+    }
+}
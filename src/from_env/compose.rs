@@ -0,0 +1,213 @@
+use super::{EnvConfigEnum, EnvVariables};
+use crate::ConfigError;
+
+// Config composition.
+// --------------------------------------------------
+
+// Libraries declare the config they need, and how to construct themselves from
+// it:
+//
+// impl RequiresEnv for DynamoClient {
+//     type Config = DynamoConfig;
+//     fn from_config(cfg: EnvVariables<DynamoConfig>) -> Self { ... }
+// }
+//
+// A service then loads a single parent config (a superset of each library's
+// config), and constructs its clients by narrowing the parent for each:
+//
+// let dynamo = env.build::<DynamoClient>()?;
+// let (dynamo, s3, cognito) = env.build_all::<(DynamoClient, S3Client, CognitoClient)>()?;
+pub trait RequiresEnv: Sized {
+    type Config: EnvConfigEnum;
+    fn from_config(cfg: EnvVariables<Self::Config>) -> Self;
+}
+
+// Implemented for tuples of up to 8 RequiresEnv types.
+pub trait RequiresEnvSet<P: EnvConfigEnum>: Sized {
+    fn build_from(env: &EnvVariables<P>) -> Result<Self, ConfigError>;
+}
+
+macro_rules! impl_requires_env_set {
+    ($($C:ident),+) => {
+        impl<P: EnvConfigEnum, $($C: RequiresEnv),+> RequiresEnvSet<P> for ($($C,)+) {
+            fn build_from(env: &EnvVariables<P>) -> Result<Self, ConfigError> {
+                Ok(($(env.build::<$C>()?,)+))
+            }
+        }
+    };
+}
+
+impl_requires_env_set!(A);
+impl_requires_env_set!(A, B);
+impl_requires_env_set!(A, B, C);
+impl_requires_env_set!(A, B, C, D);
+impl_requires_env_set!(A, B, C, D, E);
+impl_requires_env_set!(A, B, C, D, E, F);
+impl_requires_env_set!(A, B, C, D, E, F, G);
+impl_requires_env_set!(A, B, C, D, E, F, G, H);
+
+impl<P: EnvConfigEnum> EnvVariables<P> {
+    pub fn build<C: RequiresEnv>(&self) -> Result<C, ConfigError> {
+        Ok(C::from_config(self.clone_into::<C::Config>()?))
+    }
+
+    pub fn build_all<S: RequiresEnvSet<P>>(&self) -> Result<S, ConfigError> {
+        S::build_from(self)
+    }
+}
+
+// Nested configs.
+// --------------------------------------------------
+
+// Instead of listing every key of each library again, a service's parent config
+// can be declared by nesting the libraries' configs:
+//
+// define_env_config!(AppConfig {
+//     db: DbEnvConfig,
+//     cache: CacheConfig,
+// });
+//
+// AppConfig then has all keys of its children (validated with the children's
+// constraints and validators), addressed through the child they come from:
+//
+// let env = load_env::<AppConfig>()?;
+// let host = env.get(&AppConfig::db(DbEnvConfig::DbHost))?;
+// let db_env = env.child::<DbEnvConfig>()?;
+//
+// Unlike window!, child() only compiles for the parent's declared children (or
+// the config itself). A config can't be nested twice under the same parent.
+pub trait ContainsConfig<C: EnvConfigEnum>: EnvConfigEnum {}
+
+impl<C: EnvConfigEnum> ContainsConfig<C> for C {}
+
+impl<P: EnvConfigEnum> EnvVariables<P> {
+    pub fn child<C: EnvConfigEnum>(&self) -> Result<EnvVariables<C>, ConfigError>
+    where
+        P: ContainsConfig<C>,
+    {
+        self.clone_into::<C>()
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        define_env_config, define_env_variable, load_from_source, EnvConfigEnum, EnvVariables,
+    };
+
+    use super::RequiresEnv;
+
+    define_env_variable!(COMPOSE_DYNAMO_REGION);
+    define_env_variable!(COMPOSE_BUCKET);
+    define_env_variable!(COMPOSE_OTHER);
+
+    define_env_config!(
+        ServiceConfig,
+        DynamoRegion => COMPOSE_DYNAMO_REGION,
+        Bucket => COMPOSE_BUCKET,
+    );
+    define_env_config!(
+        DynamoConfig,
+        DynamoRegion => COMPOSE_DYNAMO_REGION,
+    );
+    define_env_config!(
+        S3Config,
+        Bucket => COMPOSE_BUCKET,
+    );
+    define_env_config!(
+        OtherConfig,
+        Other => COMPOSE_OTHER,
+    );
+
+    struct DynamoClient(String);
+    struct S3Client(String);
+    struct OtherClient;
+
+    impl RequiresEnv for DynamoClient {
+        type Config = DynamoConfig;
+        fn from_config(cfg: EnvVariables<DynamoConfig>) -> Self {
+            DynamoClient(cfg.get(&DynamoConfig::DynamoRegion).unwrap().to_string())
+        }
+    }
+
+    impl RequiresEnv for S3Client {
+        type Config = S3Config;
+        fn from_config(cfg: EnvVariables<S3Config>) -> Self {
+            S3Client(cfg.get(&S3Config::Bucket).unwrap().to_string())
+        }
+    }
+
+    impl RequiresEnv for OtherClient {
+        type Config = OtherConfig;
+        fn from_config(_cfg: EnvVariables<OtherConfig>) -> Self {
+            OtherClient
+        }
+    }
+
+    fn service_env() -> EnvVariables<ServiceConfig> {
+        EnvVariables::from(HashMap::from([
+            (COMPOSE_DYNAMO_REGION, "us-west-2".to_string()),
+            (COMPOSE_BUCKET, "my-bucket".to_string()),
+        ]))
+    }
+
+    #[test]
+    fn test_build() {
+        let dynamo = service_env().build::<DynamoClient>().unwrap();
+        assert_eq!(dynamo.0, "us-west-2");
+        assert!(service_env().build::<OtherClient>().is_err());
+    }
+
+    #[test]
+    fn test_nested_config() {
+        define_env_config!(NestedConfig {
+            dynamo: DynamoConfig,
+            service: ServiceConfig,
+        });
+
+        assert_eq!(NestedConfig::COUNT, 2);
+        assert_eq!(
+            NestedConfig::value_list(),
+            [
+                NestedConfig::dynamo(DynamoConfig::DynamoRegion),
+                NestedConfig::service(ServiceConfig::Bucket),
+            ]
+        );
+        let source = HashMap::from([
+            (
+                String::from("COMPOSE_DYNAMO_REGION"),
+                String::from("us-west-2"),
+            ),
+            (String::from("COMPOSE_BUCKET"), String::from("my-bucket")),
+        ]);
+        let env = load_from_source::<NestedConfig>(&source).unwrap();
+        assert_eq!(
+            env.get(&NestedConfig::service(ServiceConfig::Bucket))
+                .unwrap(),
+            "my-bucket"
+        );
+        let dynamo = env.child::<DynamoConfig>().unwrap();
+        assert_eq!(
+            dynamo.get(&DynamoConfig::DynamoRegion).unwrap(),
+            "us-west-2"
+        );
+        assert!(load_from_source::<NestedConfig>(&HashMap::<String, String>::new()).is_err());
+    }
+
+    #[test]
+    fn test_build_all() {
+        let (dynamo, s3) = service_env()
+            .build_all::<(DynamoClient, S3Client)>()
+            .unwrap();
+        assert_eq!(dynamo.0, "us-west-2");
+        assert_eq!(s3.0, "my-bucket");
+        assert!(service_env()
+            .build_all::<(DynamoClient, OtherClient)>()
+            .is_err());
+    }
+}
@@ -1,9 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
-
-use fractic_server_error::{CriticalError, ServerError};
-
-use super::{InvalidEnvCloneInto, MissingEnvVariableError};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::ConfigError;
+use crate::Plain;
+
+use super::collision::{register_env_names, KeyOrigin};
+use super::divergence::check_divergence;
+use super::freeze::check_env_mutations;
+use super::metadata::LoadMetadata;
+use super::migrate::MigratedSource;
+use super::suggest::missing_hint;
+use super::usage;
+use super::{
+    ConfigSource, DivergenceMode, EnvLoadFailed, EnvReader, InvalidEnvCloneInto,
+    InvalidEnvVariableType, KeyGroup, KeySpec, LoadOptions, Migrations, MissingEnvVariableError,
+    ProcessEnv,
+};
 
 // Environment configuration.
 // --------------------------------------------------
@@ -25,13 +39,80 @@ use super::{InvalidEnvCloneInto, MissingEnvVariableError};
 //
 // Now the EnvConfig object can be used to fetch and manage the environment
 // variable values in a way that's largely type-checked by the compiler.
+//
+// Keys can additionally declare constraints on their values (see spec.rs),
+// which are checked when the config is loaded.
+//
+// Invariants spanning multiple variables can be enforced by a config-level
+// validate hook, which is run by load_env once all values are loaded:
+//
+// fn check_pool_sizes(env: &EnvVariables<EnvConfig>) -> Result<(), ConfigError> {
+//     let min = env.get_parsed::<u32>(&EnvConfig::MinPoolSize)?;
+//     let max = env.get_parsed::<u32>(&EnvConfig::MaxPoolSize)?;
+//     if min > max {
+//         return Err(EnvConfigValidationFailed::new("MIN_POOL_SIZE > MAX_POOL_SIZE"));
+//     }
+//     Ok(())
+// }
+//
+// define_env_config!(
+//     EnvConfig { validate = check_pool_sizes },
+//     MinPoolSize => MIN_POOL_SIZE,
+//     MaxPoolSize => MAX_POOL_SIZE,
+// );
 pub trait EnvConfigEnum:
-    std::fmt::Debug + PartialEq + Eq + core::hash::Hash + Clone + Send + Sync
+    std::fmt::Debug + PartialEq + Eq + core::hash::Hash + Clone + Send + Sync + 'static
 {
+    // The number of keys, and all keys in declaration order. The list is
+    // static, so listing keys (ex. on every clone_into) doesn't allocate.
+    const COUNT: usize = Self::KEY_IDS.len();
+
+    // An ID per key, in value_list() order, by which keys shared between
+    // nested configs are listed once: the name of the key's variable (ex.
+    // "DB_HOST"), since its value can't be read at compile time.
+    #[doc(hidden)]
+    const KEY_IDS: &'static [&'static str];
+
     fn as_str(&self) -> &'static str;
-    fn value_list() -> Vec<Self>;
+    fn value_list() -> &'static [Self];
+
+    fn spec(&self) -> KeySpec {
+        KeySpec::new(self.as_str())
+    }
+
+    fn validator() -> Option<ValidateFn<Self>> {
+        None
+    }
+
+    // See group.rs.
+    fn groups() -> Vec<KeyGroup> {
+        Vec::new()
+    }
+
+    // See migrate.rs.
+    fn migrations() -> Migrations {
+        Migrations::new()
+    }
+
+    // See divergence.rs.
+    fn divergence_mode() -> DivergenceMode {
+        DivergenceMode::Ignore
+    }
+
+    // See collision.rs.
+    #[doc(hidden)]
+    fn key_origins() -> Vec<KeyOrigin> {
+        Vec::new()
+    }
 }
 
+// Keys that may be missing from a loaded EnvVariables.
+pub(crate) fn may_be_absent<T: EnvConfigEnum>(key: &T) -> bool {
+    key.spec().is_conditional() || T::groups().iter().any(|group| group.contains(key.as_str()))
+}
+
+pub type ValidateFn<T> = fn(&EnvVariables<T>) -> Result<(), ConfigError>;
+
 // To initialize a given environment, call load_env::<EnvConfig>() to fetch all
 // the environment variable values for that config and store them in a
 // EnvVariables object (which is essentiall just a map).
@@ -44,27 +125,188 @@ pub trait EnvConfigEnum:
 // EnvConfig, and it is compiler-ensured that you don't accidentally try to
 // access any variables that were not specified in the config.
 #[derive(Debug, Clone)]
-pub struct EnvVariables<T: EnvConfigEnum>(HashMap<&'static str, String>, PhantomData<T>);
+pub struct EnvVariables<T: EnvConfigEnum>(
+    pub(crate) HashMap<&'static str, Arc<str>>,
+    PhantomData<T>,
+    pub(crate) LoadMetadata,
+);
 impl<T: EnvConfigEnum> EnvVariables<T> {
-    pub fn get(&self, key: &T) -> Result<&String, ServerError> {
+    pub fn get(&self, key: &T) -> Result<&str, ConfigError> {
+        self.get_stored(key).map(|value| &**value)
+    }
+    pub fn get_cloned(&self, key: &T) -> Result<String, ConfigError> {
+        self.get(key).map(str::to_string)
+    }
+    pub fn get_optional(&self, key: &T) -> Option<&str> {
+        usage::record_read(key.as_str());
+        self.0.get(key.as_str()).map(|value| &**value)
+    }
+    // Values are stored as Arc<str>, so they can be kept in long-lived state
+    // (and windows can be narrowed) without copying the strings.
+    pub fn get_arc(&self, key: &T) -> Result<Arc<str>, ConfigError> {
+        self.get_stored(key).cloned()
+    }
+    pub fn get_plain(&self, key: &T) -> Result<Plain<String>, ConfigError> {
+        self.get_cloned(key).map(Plain::new)
+    }
+    // Typed access, ex. config.get_parsed::<u16>(&EnvConfig::Port). Typically
+    // paired with a range constraint, so the value is validated at load time.
+    pub fn get_parsed<V: FromStr>(&self, key: &T) -> Result<V, ConfigError>
+    where
+        V::Err: std::fmt::Debug,
+    {
+        self.get(key)?.parse::<V>().map_err(|e| {
+            InvalidEnvVariableType::with_debug(key.as_str(), std::any::type_name::<V>(), &e)
+        })
+    }
+    fn get_stored(&self, key: &T) -> Result<&Arc<str>, ConfigError> {
+        usage::record_read(key.as_str());
+        if let Some(value) = self.0.get(key.as_str()) {
+            return Ok(value);
+        }
+        // Keys only required in some stages, or part of a group, may
+        // legitimately be missing.
+        if may_be_absent(key) {
+            return Err(MissingEnvVariableError::new(key.as_str(), ""));
+        }
         self.get_raw(key.as_str())
     }
-    fn get_raw(&self, key: &str) -> Result<&String, ServerError> {
-        self.0.get(key).ok_or(CriticalError::new(
+    fn get_raw(&self, key: &str) -> Result<&Arc<str>, ConfigError> {
+        self.0.get(key).ok_or(ConfigError::critical(
             &format!("Should be guaranteed any ENV variable EnvConfig::key is present in EnvVariables<EnvConfig>, but EnvConfig::{key} is missing."),
         ))
     }
 }
-pub fn load_env<T: EnvConfigEnum>() -> Result<EnvVariables<T>, ServerError> {
+pub fn load_env<T: EnvConfigEnum>() -> Result<EnvVariables<T>, ConfigError> {
+    check_env_mutations();
+    register_env_names::<T>();
+    let env = load_from_source(&ProcessEnv::new())?;
+    check_divergence(&env)?;
+    Ok(env)
+}
+// Same as load_env, with per-call policies (see options.rs):
+//
+// let config = load_env_with::<EnvConfig>(LoadOptions::new().trim(true))?;
+pub fn load_env_with<T: EnvConfigEnum>(
+    options: LoadOptions<impl EnvReader>,
+) -> Result<EnvVariables<T>, ConfigError> {
+    register_env_names::<T>();
+    let source = options.source();
+    let env = load_fields(&source, options.aggregates_errors())?;
+    check_divergence(&env)?;
+    Ok(env)
+}
+// Load from arbitrary key-value pairs instead of the process environment, for
+// embedders that collect the environment themselves (ex. WASM hosts, test
+// harnesses, or a config already parsed elsewhere):
+//
+// let config = load_env_from_iter::<EnvConfig>(host.env_pairs())?;
+//
+// If a key appears more than once, the last value wins.
+pub fn load_env_from_iter<T: EnvConfigEnum>(
+    vars: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+) -> Result<EnvVariables<T>, ConfigError> {
+    let source: HashMap<String, String> = vars
+        .into_iter()
+        .map(|(key, value)| (key.into(), value.into()))
+        .collect();
+    load_from_source(&source)
+}
+pub fn load_from_source<T: EnvConfigEnum>(
+    source: &impl ConfigSource,
+) -> Result<EnvVariables<T>, ConfigError> {
+    load_fields(source, false)
+}
+// Dry run of loading, for pre-flight checks (ex. admission webhooks or CI
+// deploy gates) that should not keep the values around:
+//
+// validate_env::<EnvConfig>()?;
+//
+// Checks presence, constraints and groups, reporting all failures together,
+// while each value is discarded as soon as it has been checked. The validate
+// hook is not run, since it needs the loaded values.
+pub fn validate_env<T: EnvConfigEnum>() -> Result<(), ConfigError> {
+    validate_source::<T>(&ProcessEnv::new())
+}
+pub fn validate_source<T: EnvConfigEnum>(source: &impl ConfigSource) -> Result<(), ConfigError> {
+    read_fields::<T>(source, true, |_, _| {})
+}
+fn load_fields<T: EnvConfigEnum>(
+    source: &impl ConfigSource,
+    aggregate_errors: bool,
+) -> Result<EnvVariables<T>, ConfigError> {
     let mut map = HashMap::new();
+    read_fields::<T>(source, aggregate_errors, |key, value| {
+        map.insert(key, Arc::from(value));
+    })?;
+
+    let env = EnvVariables(map, PhantomData, LoadMetadata::new(source.name()));
+    if let Some(validate) = T::validator() {
+        validate(&env)?;
+    }
+    Ok(env)
+}
+// With aggregate_errors, every key is still read after a failure, and all
+// failures are returned together.
+fn read_fields<T: EnvConfigEnum>(
+    source: &impl ConfigSource,
+    aggregate_errors: bool,
+    mut store: impl FnMut(&'static str, String),
+) -> Result<(), ConfigError> {
+    let source = &MigratedSource::new(T::migrations(), source);
+    let mut present = HashSet::new();
+    let mut errors = Vec::new();
+    let groups = T::groups();
 
     for field in T::value_list() {
-        let value = std::env::var(field.as_str())
-            .map_err(|_| MissingEnvVariableError::new(field.as_str()))?;
-        map.insert(field.as_str(), value);
+        match load_field(field, source, &groups) {
+            Ok(Some(value)) => {
+                present.insert(field.as_str());
+                store(field.as_str(), value);
+            }
+            Ok(None) => {}
+            Err(e) if aggregate_errors => errors.push(e),
+            Err(e) => return Err(e),
+        }
     }
+    for group in &groups {
+        match group.check(|key| present.contains(key)) {
+            Ok(()) => {}
+            Err(e) if aggregate_errors => errors.push(e),
+            Err(e) => return Err(e),
+        }
+    }
+    if !errors.is_empty() {
+        let messages = errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+        return Err(EnvLoadFailed::new(
+            &errors.len().to_string(),
+            &messages.join(" "),
+        ));
+    }
+    Ok(())
+}
 
-    Ok(EnvVariables(map, PhantomData))
+fn load_field<T: EnvConfigEnum>(
+    field: &T,
+    source: &impl ConfigSource,
+    groups: &[KeyGroup],
+) -> Result<Option<String>, ConfigError> {
+    #[cfg(any(test, feature = "testing"))]
+    if crate::chaos::is_key_failing(field.as_str()) {
+        return Err(super::InjectedConfigFailure::new(field.as_str()));
+    }
+    let spec = field.spec();
+    let Some(value) = spec.read(source)? else {
+        if spec.may_be_missing(source) || groups.iter().any(|g| g.contains(field.as_str())) {
+            return Ok(None);
+        }
+        return Err(MissingEnvVariableError::new(
+            field.as_str(),
+            &missing_hint(field.as_str(), source),
+        ));
+    };
+    spec.validate(&value)?;
+    Ok(Some(value))
 }
 
 // For tests, let an EnvVariables structure be easily made from a HashMap.
@@ -78,7 +320,12 @@ where
     U: Into<HashMap<&'static str, String>>,
 {
     fn from(map: U) -> Self {
-        EnvVariables(map.into(), PhantomData)
+        let map = map
+            .into()
+            .into_iter()
+            .map(|(key, value)| (key, Arc::from(value)))
+            .collect();
+        EnvVariables(map, PhantomData, LoadMetadata::new("map"))
     }
 }
 
@@ -87,22 +334,24 @@ where
 impl<ParentConfig: EnvConfigEnum> EnvVariables<ParentConfig> {
     pub fn clone_into<ChildConfig: EnvConfigEnum>(
         &self,
-    ) -> Result<EnvVariables<ChildConfig>, ServerError> {
-        let mut map = HashMap::new();
+    ) -> Result<EnvVariables<ChildConfig>, ConfigError> {
+        let mut map = HashMap::with_capacity(ChildConfig::COUNT);
         for value in ChildConfig::value_list() {
             let key_as_str = value.as_str();
-            let env_value = self.get_raw(key_as_str).map_err(|_critical_error| {
-                // Usually get_raw would return a critical error because the key
-                // should always exist. However, when building a window, it
-                // could be missing if the window config is not a proper subset
-                // of the parent config. In this case, just let the developer
-                // know the the parent EnvConfig needs to be updated by
-                // returning an InvalidEnvConfig error.
-                InvalidEnvCloneInto::new(key_as_str)
-            })?;
-            map.insert(key_as_str, env_value.clone());
+            // Present keys are copied without looking at their spec, which is
+            // only needed (and only built) for the keys that are missing.
+            match self.0.get(key_as_str) {
+                Some(env_value) => {
+                    map.insert(key_as_str, env_value.clone());
+                }
+                None if may_be_absent(value) => {}
+                // The window config is not a proper subset of the parent
+                // config, so let the developer know the parent EnvConfig
+                // needs to be updated.
+                None => return Err(InvalidEnvCloneInto::new(key_as_str)),
+            }
         }
-        Ok(EnvVariables(map, PhantomData))
+        Ok(EnvVariables(map, PhantomData, self.2.clone()))
     }
 }
 
@@ -111,19 +360,17 @@ impl<ParentConfig: EnvConfigEnum> EnvVariables<ParentConfig> {
 
 #[cfg(test)]
 mod tests {
-    use once_cell::sync::Lazy;
     use std::collections::HashMap;
-    use std::env;
-    use std::sync::Mutex;
+    use std::sync::Arc;
+
+    use crate::ConfigError;
 
     use crate::{
-        define_env_config, define_env_variable, from_env::config::load_env, EnvConfigEnum,
-        EnvVariables,
+        define_env_config, define_env_variable, from_env::config::load_env, load_env_from_iter,
+        load_env_with, load_from_source, validate_source, EnvConfigEnum, EnvConfigValidationFailed,
+        EnvVariables, FileSource, Format, LoadOptions, Stage,
     };
 
-    // Each test involving environment variables should be locked with ENV_LOCK.
-    static ENV_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
-
     define_env_variable!(COGNITO_REGION);
     define_env_variable!(COGNITO_USER_POOL_ID);
     define_env_variable!(DYNAMO_REGION);
@@ -144,6 +391,34 @@ mod tests {
 
     define_env_config!(EmptyConfig,);
 
+    fn check_regions_match(env: &EnvVariables<ValidatedConfig>) -> Result<(), ConfigError> {
+        if env.get(&ValidatedConfig::CognitoRegion)? != env.get(&ValidatedConfig::DynamoRegion)? {
+            return Err(EnvConfigValidationFailed::new(
+                "COGNITO_REGION and DYNAMO_REGION must match",
+            ));
+        }
+        Ok(())
+    }
+
+    define_env_config!(
+        ValidatedConfig { validate = check_regions_match },
+        CognitoRegion => COGNITO_REGION,
+        DynamoRegion => DYNAMO_REGION,
+    );
+
+    define_env_config!(
+        ConstrainedRegionConfig,
+        CognitoRegion => COGNITO_REGION { one_of = ["us-west-2", "us-east-1"] },
+    );
+
+    define_env_variable!(SENTRY_DSN);
+
+    define_env_config!(
+        StagedConfig,
+        CognitoRegion => COGNITO_REGION,
+        SentryDsn => SENTRY_DSN { required_in = [Stage::Prod] },
+    );
+
     #[test]
     fn test_env_variable_as_str() {
         // Just test a couple.
@@ -154,39 +429,63 @@ mod tests {
 
     #[test]
     fn test_load_config_partial_valid() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        env::set_var("COGNITO_REGION", "us-west-2");
-        env::remove_var("COGNITO_USER_POOL_ID");
-        env::remove_var("DYNAMO_REGION");
-        env::remove_var("POLLY_REGION");
+        let fake_env = HashMap::from([("COGNITO_REGION", "us-west-2")]);
 
-        let expected_config: HashMap<&'static str, String> =
-            [(COGNITO_REGION, String::from("us-west-2"))].into();
-        let config = load_env::<CognitoRegionOnlyConfig>().unwrap().0;
+        let expected_config: HashMap<&'static str, Arc<str>> =
+            [(COGNITO_REGION, Arc::from("us-west-2"))].into();
+        let config = load_env_with::<CognitoRegionOnlyConfig>(LoadOptions::new().reader(fake_env))
+            .unwrap()
+            .0;
         assert_eq!(config, expected_config);
     }
 
     #[test]
     fn test_load_config_partial_invalid() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        env::set_var("COGNITO_REGION", "us-west-2");
-        env::remove_var("COGNITO_USER_POOL_ID");
-        env::remove_var("DYNAMO_REGION");
-        env::remove_var("POLLY_REGION");
+        let fake_env = HashMap::from([("COGNITO_REGION", "us-west-2")]);
 
-        let config = load_env::<AllVariablesConfig>();
+        let config = load_env_with::<AllVariablesConfig>(LoadOptions::new().reader(fake_env));
         assert!(config.is_err());
     }
 
     #[test]
     fn test_load_config_var_not_set() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        env::remove_var("COGNITO_REGION");
-
+        // Reads the real environment, where no test sets COGNITO_REGION.
         let config = load_env::<CognitoRegionOnlyConfig>();
         assert!(config.is_err());
     }
 
+    #[test]
+    fn test_load_config_constraint_valid() {
+        let fake_env = HashMap::from([("COGNITO_REGION", "us-east-1")]);
+
+        let config =
+            load_env_with::<ConstrainedRegionConfig>(LoadOptions::new().reader(fake_env)).unwrap();
+        assert_eq!(
+            config.get(&ConstrainedRegionConfig::CognitoRegion).unwrap(),
+            "us-east-1"
+        );
+    }
+
+    #[test]
+    fn test_load_config_constraint_invalid() {
+        let fake_env = HashMap::from([("COGNITO_REGION", "us-wset-2")]);
+
+        let config = load_env_with::<ConstrainedRegionConfig>(LoadOptions::new().reader(fake_env));
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_load_config_validate_hook() {
+        let mut fake_env = HashMap::from([
+            ("COGNITO_REGION", "us-west-2"),
+            ("DYNAMO_REGION", "us-west-2"),
+        ]);
+        assert!(load_env_with::<ValidatedConfig>(LoadOptions::new().reader(&fake_env)).is_ok());
+
+        fake_env.insert("DYNAMO_REGION", "us-east-1");
+        assert!(load_env_with::<ValidatedConfig>(LoadOptions::new().reader(&fake_env)).is_err());
+    }
+
     #[test]
     fn test_load_config_empty() {
         let config = load_env::<EmptyConfig>().unwrap().0;
@@ -210,6 +509,14 @@ mod tests {
             subset.get(&CognitoRegionOnlyConfig::CognitoRegion).unwrap(),
             "us-west-2"
         );
+        assert!(Arc::ptr_eq(
+            &subset
+                .get_arc(&CognitoRegionOnlyConfig::CognitoRegion)
+                .unwrap(),
+            &env_variables
+                .get_arc(&AllVariablesConfig::CognitoRegion)
+                .unwrap()
+        ));
     }
 
     #[test]
@@ -253,6 +560,13 @@ mod tests {
                 .unwrap(),
             "us-west-2"
         );
+        assert_eq!(
+            env_variables
+                .get_plain(&AllVariablesConfig::CognitoRegion)
+                .unwrap()
+                .to_string(),
+            "us-west-2"
+        );
         assert_eq!(
             env_variables
                 .get(&AllVariablesConfig::DynamoRegion)
@@ -261,13 +575,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_env_variables_get_parsed() {
+        let input_map: HashMap<&'static str, String> = [
+            (COGNITO_REGION, String::from("us-west-2")),
+            (DYNAMO_REGION, String::from("8080")),
+        ]
+        .into();
+        let env_variables: EnvVariables<AllVariablesConfig> = EnvVariables::from(input_map);
+
+        let port: u16 = env_variables
+            .get_parsed(&AllVariablesConfig::DynamoRegion)
+            .unwrap();
+        assert_eq!(port, 8080);
+        assert!(env_variables
+            .get_parsed::<u16>(&AllVariablesConfig::CognitoRegion)
+            .is_err());
+    }
+
     #[test]
     fn test_env_variables_get_invalid_key() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let input_map: HashMap<&'static str, String> =
             [(COGNITO_REGION, String::from("us-west-2"))].into();
         let env_variables: EnvVariables<AllVariablesConfig> = EnvVariables::from(input_map);
         let result = env_variables.get(&AllVariablesConfig::PollyRegion);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_load_env_from_iter() {
+        let vars = vec![
+            ("COGNITO_REGION", "us-east-1"),
+            ("COGNITO_REGION", "us-west-2"),
+            ("UNRELATED", "ignored"),
+        ];
+        let config = load_env_from_iter::<CognitoRegionOnlyConfig>(vars).unwrap();
+        assert_eq!(
+            config.get(&CognitoRegionOnlyConfig::CognitoRegion).unwrap(),
+            "us-west-2"
+        );
+        let empty: Vec<(String, String)> = Vec::new();
+        assert!(load_env_from_iter::<CognitoRegionOnlyConfig>(empty).is_err());
+    }
+
+    #[test]
+    fn test_validate_source() {
+        let source = HashMap::from([(String::from("COGNITO_REGION"), String::from("us-west-2"))]);
+        assert!(validate_source::<CognitoRegionOnlyConfig>(&source).is_ok());
+
+        let error = validate_source::<AllVariablesConfig>(&source).unwrap_err();
+        assert!(error.to_string().contains("3 environment variable(s)"));
+    }
+
+    #[test]
+    fn test_load_config_required_in_stage() {
+        let source = |json: &str| FileSource::parse(json, Format::Json).unwrap();
+
+        let dev = source(r#"{ "STAGE": "dev", "COGNITO_REGION": "us-west-2" }"#);
+        let config = load_from_source::<StagedConfig>(&dev).unwrap();
+        assert_eq!(config.get_optional(&StagedConfig::SentryDsn), None);
+        assert!(config.get(&StagedConfig::SentryDsn).is_err());
+        assert!(config.clone_into::<StagedConfig>().is_ok());
+
+        let prod = source(r#"{ "STAGE": "prod", "COGNITO_REGION": "us-west-2" }"#);
+        assert!(load_from_source::<StagedConfig>(&prod).is_err());
+        let unknown = source(r#"{ "COGNITO_REGION": "us-west-2" }"#);
+        assert!(load_from_source::<StagedConfig>(&unknown).is_err());
+
+        let prod = source(
+            r#"{ "STAGE": "prod", "COGNITO_REGION": "us-west-2", "SENTRY_DSN": "https://dsn" }"#,
+        );
+        let config = load_from_source::<StagedConfig>(&prod).unwrap();
+        assert_eq!(config.get(&StagedConfig::SentryDsn).unwrap(), "https://dsn");
+    }
 }
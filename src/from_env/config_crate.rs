@@ -0,0 +1,89 @@
+use config::Config;
+
+use crate::ConfigError;
+
+use super::{ConfigSource, InvalidConfigCrateValue};
+
+// config crate adapter.
+// --------------------------------------------------
+
+// A config::Config, as built with the config crate's layered sources, can feed
+// the same typed validation as any other source:
+//
+// let settings = Config::builder()
+//     .add_source(config::File::with_name("settings"))
+//     .add_source(config::Environment::default())
+//     .build()?;
+// let config: EnvVariables<EnvConfig> = load_from_source(&settings)?;
+//
+// Variables are looked up by their exact name first, then lowercased (the
+// config crate's Environment source lowercases keys, ex. COGNITO_REGION is
+// found as cognito_region). Names can also be dotted paths into nested tables
+// (ex. "database.host"). Scalars are converted to their string representation,
+// while tables and arrays are rejected. Available with the "config" feature.
+impl ConfigSource for Config {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        match read_string(self, key, key)? {
+            None if key.chars().any(char::is_uppercase) => {
+                read_string(self, &key.to_lowercase(), key)
+            }
+            value => Ok(value),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "config"
+    }
+}
+
+fn read_string(config: &Config, path: &str, key: &str) -> Result<Option<String>, ConfigError> {
+    match config.get_string(path) {
+        Ok(value) => Ok(Some(value)),
+        Err(config::ConfigError::NotFound(_)) => Ok(None),
+        Err(e) => Err(InvalidConfigCrateValue::with_source(key, e)),
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use config::Config;
+
+    use crate::{define_env_config, define_env_variable, load_from_source, EnvConfigEnum};
+
+    define_env_variable!(CONFIG_CRATE_TEST_REGION);
+    define_env_variable!(CONFIG_CRATE_TEST_PORT);
+
+    define_env_config!(
+        ConfigCrateConfig,
+        Region => CONFIG_CRATE_TEST_REGION,
+        Port => CONFIG_CRATE_TEST_PORT { range = 1..=65535 },
+    );
+
+    #[test]
+    fn test_load_from_config_crate() {
+        let settings = Config::builder()
+            .set_override("config_crate_test_region", "us-west-2")
+            .unwrap()
+            .set_override("CONFIG_CRATE_TEST_PORT", 8080)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let config = load_from_source::<ConfigCrateConfig>(&settings).unwrap();
+        assert_eq!(config.get(&ConfigCrateConfig::Region).unwrap(), "us-west-2");
+        assert_eq!(
+            config.get_parsed::<u16>(&ConfigCrateConfig::Port).unwrap(),
+            8080
+        );
+
+        let settings = Config::builder()
+            .set_override("config_crate_test_region", "us-west-2")
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(load_from_source::<ConfigCrateConfig>(&settings).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+
+use super::snapshot::MASKED_VALUE;
+use super::{EnvConfigEnum, EnvVariables};
+
+// Masked display.
+// --------------------------------------------------
+
+// Startup banners often print the loaded configuration, so operators can
+// confirm it at a glance. This prints each key on its own line with only the
+// first and last 2 characters of the value visible:
+//
+// println!("Configuration:\n{}", config.masked_display());
+//
+// COGNITO_REGION = us...-2
+// DATABASE_URL = <redacted>
+// SENTRY_DSN = <unset>
+//
+// Values of keys flagged { sensitive = true }, and values too short to
+// partially reveal, are masked completely. Optional keys that are missing are
+// shown as <unset>.
+impl<T: EnvConfigEnum> EnvVariables<T> {
+    pub fn masked_display(&self) -> MaskedDisplay<'_, T> {
+        MaskedDisplay(self)
+    }
+}
+
+pub struct MaskedDisplay<'a, T: EnvConfigEnum>(&'a EnvVariables<T>);
+
+// Fewer characters than this are fully masked, since showing 4 of them would
+// reveal most of the value.
+const MIN_PARTIALLY_MASKED_LEN: usize = 8;
+
+pub(crate) fn mask_value(value: &str) -> String {
+    let chars = value.chars().collect::<Vec<_>>();
+    if chars.len() < MIN_PARTIALLY_MASKED_LEN {
+        return MASKED_VALUE.to_string();
+    }
+    let head = chars[..2].iter().collect::<String>();
+    let tail = chars[chars.len() - 2..].iter().collect::<String>();
+    format!("{head}...{tail}")
+}
+
+impl<T: EnvConfigEnum> fmt::Display for MaskedDisplay<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, key) in T::value_list().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let value = match self.0 .0.get(key.as_str()) {
+                None => "<unset>".to_string(),
+                Some(_) if key.spec().is_sensitive() => MASKED_VALUE.to_string(),
+                Some(value) => mask_value(value),
+            };
+            write!(f, "{} = {value}", key.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum, EnvVariables, Stage};
+
+    use super::mask_value;
+
+    define_env_variable!(DISPLAY_REGION);
+    define_env_variable!(DISPLAY_DATABASE_URL);
+    define_env_variable!(DISPLAY_SENTRY_DSN);
+
+    define_env_config!(
+        DisplayConfig,
+        Region => DISPLAY_REGION,
+        DatabaseUrl => DISPLAY_DATABASE_URL { sensitive = true },
+        SentryDsn => DISPLAY_SENTRY_DSN { required_in = [Stage::Prod] },
+    );
+
+    #[test]
+    fn test_mask_value() {
+        assert_eq!(mask_value("us-west-2"), "us...-2");
+        assert_eq!(mask_value("short"), "<redacted>");
+        assert_eq!(mask_value("ünïcødé-välue"), "ün...ue");
+    }
+
+    #[test]
+    fn test_masked_display() {
+        let config: EnvVariables<DisplayConfig> = EnvVariables::from(HashMap::from([
+            (DISPLAY_REGION, String::from("us-west-2")),
+            (
+                DISPLAY_DATABASE_URL,
+                String::from("postgres://user:pw@localhost/db"),
+            ),
+        ]));
+        assert_eq!(
+            config.masked_display().to_string(),
+            "DISPLAY_REGION = us...-2\n\
+             DISPLAY_DATABASE_URL = <redacted>\n\
+             DISPLAY_SENTRY_DSN = <unset>"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use super::snapshot::hash_value;
+use super::warnings::{report_warning, ConfigWarning};
+use super::{EnvConfigDiverged, EnvConfigEnum, EnvVariables};
+use crate::ConfigError;
+
+// Load divergence.
+// --------------------------------------------------
+
+// A config loaded more than once (ex. by several libraries, or lazily per
+// request) should see the same values every time. Code mutating the
+// environment after startup breaks this silently, which can be caught by
+// opting the config into a divergence check:
+//
+// define_env_config!(
+//     EnvConfig { divergence = DivergenceMode::Fail },
+//     ...
+// );
+//
+// The first load_env (or load_env_with) of the config records a hash of each
+// value. Later loads in the same process compare against it, and either report
+// a warning (Warn, see warnings.rs) or fail (Fail) listing the keys whose
+// values changed. Only hashes are kept, not the values themselves. Off by
+// default (Ignore).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DivergenceMode {
+    #[default]
+    Ignore,
+    Warn,
+    Fail,
+}
+
+type ValueHashes = HashMap<&'static str, Option<String>>;
+
+fn first_loads() -> &'static Mutex<HashMap<TypeId, ValueHashes>> {
+    static FIRST_LOADS: OnceLock<Mutex<HashMap<TypeId, ValueHashes>>> = OnceLock::new();
+    FIRST_LOADS.get_or_init(Default::default)
+}
+
+pub(crate) fn check_divergence<T: EnvConfigEnum>(env: &EnvVariables<T>) -> Result<(), ConfigError> {
+    let mode = T::divergence_mode();
+    if mode == DivergenceMode::Ignore {
+        return Ok(());
+    }
+    let hashes = T::value_list()
+        .iter()
+        .map(|key| (key.as_str(), env.0.get(key.as_str()).map(|v| hash_value(v))))
+        .collect::<ValueHashes>();
+    let mut first_loads = first_loads().lock().unwrap_or_else(PoisonError::into_inner);
+    let Some(first) = first_loads.get(&TypeId::of::<T>()) else {
+        first_loads.insert(TypeId::of::<T>(), hashes);
+        return Ok(());
+    };
+    let changed = T::value_list()
+        .iter()
+        .map(EnvConfigEnum::as_str)
+        .filter(|key| first.get(key) != hashes.get(key))
+        .collect::<Vec<_>>();
+    if changed.is_empty() {
+        return Ok(());
+    }
+    let config = std::any::type_name::<T>();
+    match mode {
+        DivergenceMode::Fail => Err(EnvConfigDiverged::new(config, &changed.join(", "))),
+        _ => {
+            report_warning(ConfigWarning::ConfigDiverged {
+                config,
+                keys: changed,
+            });
+            Ok(())
+        }
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        define_env_config, define_env_variable, load_env_with, EnvConfigEnum, LoadOptions,
+    };
+
+    use crate::from_env::warnings::{reported_warnings, ConfigWarning};
+
+    use super::DivergenceMode;
+
+    define_env_variable!(DIVERGENCE_REGION);
+    define_env_variable!(DIVERGENCE_BUCKET);
+
+    define_env_config!(
+        DivergenceConfig { divergence = DivergenceMode::Fail },
+        Region => DIVERGENCE_REGION,
+        Bucket => DIVERGENCE_BUCKET,
+    );
+
+    define_env_config!(
+        WarnDivergenceConfig { divergence = DivergenceMode::Warn },
+        Region => DIVERGENCE_REGION,
+        Bucket => DIVERGENCE_BUCKET,
+    );
+
+    #[test]
+    fn test_divergence() {
+        let env = |bucket: &'static str| {
+            HashMap::from([
+                ("DIVERGENCE_REGION", "us-west-2"),
+                ("DIVERGENCE_BUCKET", bucket),
+            ])
+        };
+        assert!(load_env_with::<DivergenceConfig>(LoadOptions::new().reader(env("a"))).is_ok());
+        assert!(load_env_with::<DivergenceConfig>(LoadOptions::new().reader(env("a"))).is_ok());
+
+        let error = load_env_with::<DivergenceConfig>(LoadOptions::new().reader(env("b")))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("DIVERGENCE_BUCKET"));
+        assert!(!error.contains("DIVERGENCE_REGION"));
+
+        assert!(load_env_with::<WarnDivergenceConfig>(LoadOptions::new().reader(env("a"))).is_ok());
+        assert!(load_env_with::<WarnDivergenceConfig>(LoadOptions::new().reader(env("b"))).is_ok());
+        assert!(
+            reported_warnings().contains(&ConfigWarning::ConfigDiverged {
+                config: std::any::type_name::<WarnDivergenceConfig>(),
+                keys: vec!["DIVERGENCE_BUCKET"],
+            })
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use super::{Constraint, EnvConfigEnum, KeySpec, PemKind};
+
+// Markdown docs.
+// --------------------------------------------------
+
+// Renders a table of the variables of a config, ready to paste into a service
+// README or publish from CI, so operational docs stay in sync with the code:
+//
+// std::fs::write("docs/config.md", generate_markdown_docs::<EnvConfig>())?;
+//
+// | Variable | Type | Default | Description | Required | Sensitive |
+// | --- | --- | --- | --- | --- | --- |
+// | `PORT` | integer (1..=65535) | `8080` | Port to listen on. | no | no |
+//
+// The type is derived from the key's constraints (see spec.rs), so unconstrained
+// keys are listed as strings. Also available as
+// format_schema::<EnvConfig>(SchemaFormat::Markdown).
+pub fn generate_markdown_docs<T: EnvConfigEnum>() -> String {
+    let mut out = String::from(
+        "| Variable | Type | Default | Description | Required | Sensitive |\n\
+         | --- | --- | --- | --- | --- | --- |\n",
+    );
+    for key in T::value_list() {
+        let spec = key.spec();
+        out.push_str(&format!(
+            "| `{}` | {} | {} | {} | {} | {} |\n",
+            spec.name(),
+            escape_cell(&value_type(&spec)),
+            spec.default_text()
+                .map(|value| format!("`{}`", escape_cell(value)))
+                .unwrap_or_default(),
+            escape_cell(spec.help_text().unwrap_or("")),
+            spec.requirement(),
+            if spec.is_sensitive() { "yes" } else { "no" },
+        ));
+    }
+    out
+}
+
+fn value_type(spec: &KeySpec) -> String {
+    let types = spec
+        .constraints()
+        .iter()
+        .map(|constraint| match constraint {
+            Constraint::Matches(pattern) => format!("string matching `{pattern}`"),
+            Constraint::OneOf(values) => format!(
+                "one of {}",
+                values
+                    .iter()
+                    .map(|value| format!("`{value}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Constraint::Range(..) => {
+                let range = constraint.to_string();
+                format!("integer ({})", range.trim_start_matches("range = "))
+            }
+            Constraint::Pem(PemKind::Certificate) => "PEM certificate(s)".to_string(),
+            Constraint::Pem(PemKind::PrivateKey) => "PEM private key".to_string(),
+        })
+        .collect::<Vec<_>>();
+    if types.is_empty() {
+        "string".to_string()
+    } else {
+        types.join(", ")
+    }
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum, PemKind};
+
+    use super::generate_markdown_docs;
+
+    define_env_variable!(DOCS_PORT);
+    define_env_variable!(DOCS_MODE);
+    define_env_variable!(DOCS_TLS_KEY);
+
+    define_env_config!(
+        DocsConfig,
+        Port => DOCS_PORT { range = 1..=65535, default = "8080", description = "Port to listen on." },
+        Mode => DOCS_MODE { one_of = ["a", "b"], description = "Either a | b." },
+        TlsKey => DOCS_TLS_KEY { pem = PemKind::PrivateKey, sensitive = true },
+    );
+
+    #[test]
+    fn test_generate_markdown_docs() {
+        assert_eq!(
+            generate_markdown_docs::<DocsConfig>(),
+            "| Variable | Type | Default | Description | Required | Sensitive |\n\
+             | --- | --- | --- | --- | --- | --- |\n\
+             | `DOCS_PORT` | integer (1..=65535) | `8080` | Port to listen on. | no | no |\n\
+             | `DOCS_MODE` | one of `a`, `b` |  | Either a \\| b. | yes | no |\n\
+             | `DOCS_TLS_KEY` | PEM private key |  |  | yes | yes |\n"
+        );
+    }
+}
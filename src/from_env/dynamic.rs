@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::suggest::missing_hint;
+use super::{
+    ConfigSource, InvalidEnvVariableType, KeySpec, MissingEnvVariableError, ProcessEnv,
+    UndeclaredDynamicEnvVariable,
+};
+use crate::ConfigError;
+
+// Dynamic configuration.
+// --------------------------------------------------
+
+// For plugin systems, where the required variables are only known at runtime,
+// the schema can be built dynamically instead of through define_env_config!:
+//
+// let schema = plugins.iter().fold(DynamicSchema::new(), |schema, plugin| {
+//     schema.key(KeySpec::new(format!("{}_TOKEN", plugin.prefix())))
+// });
+// let config = load_dynamic_env(&schema)?;
+// let token = config.get(&format!("{}_TOKEN", plugin.prefix()))?;
+//
+// Keys are validated the same way as static configs (including constraints on
+// each KeySpec), though reading a key that isn't part of the schema is only
+// caught at runtime.
+#[derive(Debug, Clone, Default)]
+pub struct DynamicSchema {
+    specs: Vec<KeySpec>,
+}
+
+impl DynamicSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key(mut self, spec: KeySpec) -> Self {
+        self.specs.push(spec);
+        self
+    }
+
+    pub fn require(self, name: impl Into<String>) -> Self {
+        self.key(KeySpec::new(name.into()))
+    }
+
+    pub fn specs(&self) -> &[KeySpec] {
+        &self.specs
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DynamicEnvVariables(HashMap<String, String>);
+
+impl DynamicEnvVariables {
+    pub fn get(&self, key: &str) -> Result<&str, ConfigError> {
+        self.get_raw(key).map(String::as_str)
+    }
+
+    pub fn get_cloned(&self, key: &str) -> Result<String, ConfigError> {
+        self.get(key).map(str::to_string)
+    }
+
+    #[deprecated(note = "use get, which returns &str")]
+    pub fn get_string(&self, key: &str) -> Result<&String, ConfigError> {
+        self.get_raw(key)
+    }
+
+    pub fn get_parsed<V: FromStr>(&self, key: &str) -> Result<V, ConfigError>
+    where
+        V::Err: std::fmt::Debug,
+    {
+        self.get(key)?
+            .parse::<V>()
+            .map_err(|e| InvalidEnvVariableType::with_debug(key, std::any::type_name::<V>(), &e))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    fn get_raw(&self, key: &str) -> Result<&String, ConfigError> {
+        self.0
+            .get(key)
+            .ok_or_else(|| UndeclaredDynamicEnvVariable::new(key))
+    }
+}
+
+pub fn load_dynamic_env(schema: &DynamicSchema) -> Result<DynamicEnvVariables, ConfigError> {
+    load_dynamic_from_source(schema, &ProcessEnv::new())
+}
+
+pub fn load_dynamic_from_source(
+    schema: &DynamicSchema,
+    source: &impl ConfigSource,
+) -> Result<DynamicEnvVariables, ConfigError> {
+    let mut map = HashMap::new();
+    for spec in schema.specs() {
+        #[cfg(any(test, feature = "testing"))]
+        if crate::chaos::is_key_failing(spec.name()) {
+            return Err(super::InjectedConfigFailure::new(spec.name()));
+        }
+        let Some(value) = spec.read(source)? else {
+            if spec.may_be_missing(source) {
+                continue;
+            }
+            return Err(MissingEnvVariableError::new(
+                spec.name(),
+                &missing_hint(spec.name(), source),
+            ));
+        };
+        spec.validate(&value)?;
+        map.insert(spec.name().to_string(), value);
+    }
+    Ok(DynamicEnvVariables(map))
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::{FileSource, Format, KeySpec};
+
+    use super::{load_dynamic_from_source, DynamicSchema};
+
+    fn source() -> FileSource {
+        FileSource::parse(
+            r#"{ "PLUGIN_A_TOKEN": "abc123", "PLUGIN_A_PORT": "8080" }"#,
+            Format::Json,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_load_dynamic_config() {
+        let schema = DynamicSchema::new()
+            .require(format!("PLUGIN_{}_TOKEN", "A"))
+            .key(KeySpec::new("PLUGIN_A_PORT").range(1..=65535));
+
+        let config = load_dynamic_from_source(&schema, &source()).unwrap();
+        assert_eq!(config.get("PLUGIN_A_TOKEN").unwrap(), "abc123");
+        assert_eq!(config.get_parsed::<u16>("PLUGIN_A_PORT").unwrap(), 8080);
+        assert!(config.get("PLUGIN_B_TOKEN").is_err());
+    }
+
+    #[test]
+    fn test_load_dynamic_config_missing_or_invalid() {
+        let schema = DynamicSchema::new().require("PLUGIN_B_TOKEN");
+        assert!(load_dynamic_from_source(&schema, &source()).is_err());
+
+        let schema = DynamicSchema::new().key(KeySpec::new("PLUGIN_A_PORT").range(1..=1024));
+        assert!(load_dynamic_from_source(&schema, &source()).is_err());
+    }
+}
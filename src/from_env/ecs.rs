@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::define_env_variable;
+use crate::ConfigError;
+
+use super::http::http_request;
+use super::{ConfigSource, EcsMetadataUnavailable, FailedToFetchMetadata, InvalidMetadataResponse};
+
+define_env_variable!(ECS_TASK_ARN);
+define_env_variable!(ECS_CLUSTER);
+define_env_variable!(ECS_AVAILABILITY_ZONE);
+define_env_variable!(ECS_TASK_FAMILY);
+define_env_variable!(ECS_TASK_REVISION);
+define_env_variable!(ECS_LAUNCH_TYPE);
+
+// ECS task metadata source.
+// --------------------------------------------------
+
+// On Amazon ECS, the identity of the running task can be included in the typed
+// config, read from the task metadata endpoint instead of the environment:
+//
+// define_env_config!(
+//     TaskConfig,
+//     TaskArn => ECS_TASK_ARN,
+//     AvailabilityZone => ECS_AVAILABILITY_ZONE,
+// );
+//
+// let task = load_from_source::<TaskConfig>(&EcsMetadataSource::fetch()?)?;
+//
+// The endpoint is found through ECS_CONTAINER_METADATA_URI_V4 (or the older
+// ECS_CONTAINER_METADATA_URI), which the ECS agent sets in every container.
+// The task metadata is fetched once, and provides ECS_TASK_ARN, ECS_CLUSTER,
+// ECS_AVAILABILITY_ZONE, ECS_TASK_FAMILY, ECS_TASK_REVISION and
+// ECS_LAUNCH_TYPE. Fields the endpoint doesn't report (ex. the availability
+// zone on older agents) are missing.
+#[derive(Debug, Clone, Default)]
+pub struct EcsMetadataSource(HashMap<&'static str, String>);
+
+const METADATA_URI_VARS: [&str; 2] = [
+    "ECS_CONTAINER_METADATA_URI_V4",
+    "ECS_CONTAINER_METADATA_URI",
+];
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+impl EcsMetadataSource {
+    pub fn fetch() -> Result<Self, ConfigError> {
+        let metadata_uri = METADATA_URI_VARS
+            .iter()
+            .find_map(|var| std::env::var(var).ok())
+            .ok_or_else(|| EcsMetadataUnavailable::new(METADATA_URI_VARS[0]))?;
+        Self::fetch_from(&metadata_uri)
+    }
+
+    // From the container metadata URI, ex. http://169.254.170.2/v4/<id>.
+    pub fn fetch_from(metadata_uri: &str) -> Result<Self, ConfigError> {
+        let endpoint = format!("{}/task", metadata_uri.trim_end_matches('/'));
+        let response = http_request("GET", &endpoint, &[], TIMEOUT)
+            .map_err(|e| FailedToFetchMetadata::with_source(&endpoint, e))?;
+        if response.status != 200 {
+            return Err(InvalidMetadataResponse::new(
+                &endpoint,
+                &format!("status {}", response.status),
+            ));
+        }
+        Self::parse(&response.body)
+            .map_err(|reason| InvalidMetadataResponse::new(&endpoint, &reason))
+    }
+
+    fn parse(task_metadata: &str) -> Result<Self, String> {
+        let task = serde_json::from_str::<Value>(task_metadata)
+            .map_err(|_| "task metadata is not valid JSON".to_string())?;
+        let fields = [
+            (ECS_TASK_ARN, "TaskARN"),
+            (ECS_CLUSTER, "Cluster"),
+            (ECS_AVAILABILITY_ZONE, "AvailabilityZone"),
+            (ECS_TASK_FAMILY, "Family"),
+            (ECS_TASK_REVISION, "Revision"),
+            (ECS_LAUNCH_TYPE, "LaunchType"),
+        ];
+        let values = fields
+            .into_iter()
+            .filter_map(|(key, field)| Some((key, task[field].as_str()?.to_string())))
+            .collect();
+        Ok(Self(values))
+    }
+}
+
+impl ConfigSource for EcsMetadataSource {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        Ok(self.0.get(key).cloned())
+    }
+
+    fn name(&self) -> &'static str {
+        "ecs-metadata"
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::from_env::http::tests::serve;
+    use crate::{define_env_config, load_from_source, EnvConfigEnum};
+
+    use super::{EcsMetadataSource, ECS_AVAILABILITY_ZONE, ECS_CLUSTER, ECS_TASK_ARN};
+
+    define_env_config!(
+        TaskConfig,
+        TaskArn => ECS_TASK_ARN,
+        Cluster => ECS_CLUSTER,
+        AvailabilityZone => ECS_AVAILABILITY_ZONE,
+    );
+
+    #[test]
+    fn test_ecs_metadata_source() {
+        let (url, server) = serve(vec![concat!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n",
+            r#"{"Cluster": "default", "TaskARN": "arn:aws:ecs:us-west-2:123456789012:task/default/abc", "AvailabilityZone": "us-west-2a"}"#,
+        )]);
+        let source = EcsMetadataSource::fetch_from(&format!("{url}/v4/abc")).unwrap();
+        assert!(server.join().unwrap()[0].starts_with("GET /v4/abc/task HTTP/1.1"));
+
+        let task = load_from_source::<TaskConfig>(&source).unwrap();
+        assert_eq!(task.get(&TaskConfig::Cluster).unwrap(), "default");
+        assert_eq!(
+            task.get(&TaskConfig::AvailabilityZone).unwrap(),
+            "us-west-2a"
+        );
+    }
+}
@@ -1,12 +1,260 @@
-use fractic_server_error::define_internal_error;
+use crate::error::define_config_error;
 
-define_internal_error!(
+define_config_error!(
     InvalidEnvCloneInto,
+    "ENV001",
     "Invalid clone_into(...). Parent config missing key '{missing_var}'.",
     { missing_var: &str }
 );
-define_internal_error!(
+define_config_error!(
     MissingEnvVariableError,
-    "Missing environment variable '{missing_var}'.",
-    { missing_var: &str }
+    "ENV002",
+    "Missing environment variable '{missing_var}'.{hint}",
+    { missing_var: &str, hint: &str }
+);
+define_config_error!(
+    MissingEnvVariables,
+    "ENV003",
+    "Missing environment variables: {missing_vars}.",
+    { missing_vars: &str }
+);
+define_config_error!(
+    NotPrewarmed,
+    "ENV004",
+    "No prewarmed value of type '{type_name}'. Call prewarm() during init first.",
+    { type_name: &str }
+);
+define_config_error!(
+    FailedToServeConfig,
+    "ENV005",
+    "Failed to serve config on socket '{path}'.",
+    { path: &str }
+);
+define_config_error!(
+    FailedToReceiveSharedConfig,
+    "ENV006",
+    "Failed to receive config from socket '{path}'.",
+    { path: &str }
+);
+define_config_error!(
+    InvalidSharedConfig,
+    "ENV007",
+    "Config received from socket '{path}' is invalid: {reason}.",
+    { path: &str, reason: &str }
+);
+define_config_error!(
+    StartupBudgetExceeded,
+    "ENV008",
+    "Config loading exceeded its {budget} budget, still waiting for: {pending}.",
+    { budget: &str, pending: &str }
+);
+define_config_error!(
+    InvalidConfigWindow,
+    "ENV009",
+    "Invalid window into '{child}' at {location}.",
+    { child: &str, location: &str }
+);
+define_config_error!(
+    UndeclaredDynamicEnvVariable,
+    "ENV010",
+    "Environment variable '{var}' is not declared in the dynamic schema.",
+    { var: &str }
+);
+define_config_error!(
+    InvalidUtf8EnvVariable,
+    "ENV011",
+    "Environment variable '{var}' is set, but its value is not valid UTF-8.",
+    { var: &str }
+);
+define_config_error!(
+    FailedToReadEnvVariableFile,
+    "ENV012",
+    "Failed to read environment variable '{var}' from file '{path}'.",
+    { var: &str, path: &str }
+);
+define_config_error!(
+    ConflictingEnvVariableFile,
+    "ENV013",
+    "Both '{var}' and '{file_var}' are set. Only one of them should be provided.",
+    { var: &str, file_var: &str }
+);
+define_config_error!(
+    InvalidEnvVariableType,
+    "ENV014",
+    "Environment variable '{var}' could not be parsed as '{expected_type}'.",
+    { var: &str, expected_type: &str }
+);
+define_config_error!(
+    InvalidBase64Value,
+    "ENV015",
+    "Value of '{key}' is not valid base64 (invalid {position}).",
+    { key: &str, position: &str }
+);
+define_config_error!(
+    InvalidPemValue,
+    "ENV016",
+    "Environment variable '{var}' is not a valid PEM {kind}: {reason}.",
+    { var: &str, kind: &str, reason: &str }
+);
+define_config_error!(
+    InvalidEnvMapEntry,
+    "ENV017",
+    "Environment variable '{var}' has a malformed key-value entry '{entry}'.",
+    { var: &str, entry: &str }
+);
+define_config_error!(
+    InvalidEnvTableRow,
+    "ENV018",
+    "Environment variable '{var}' has a malformed table row '{row}': {reason}.",
+    { var: &str, row: &str, reason: &str }
+);
+define_config_error!(
+    EnvVariableFailedConstraint,
+    "ENV019",
+    "Environment variable '{var}' does not satisfy constraint: {constraint}.",
+    { var: &str, constraint: &str }
+);
+define_config_error!(
+    RemovedEnvVariableSet,
+    "ENV020",
+    "Environment variable '{var}' was removed after {removed_after}, but is still set. Delete it from the environment.",
+    { var: &str, removed_after: &str }
+);
+define_config_error!(
+    InvalidEnvConstraint,
+    "ENV021",
+    "Invalid constraint declared for environment variable '{var}': {constraint}.",
+    { var: &str, constraint: &str }
+);
+define_config_error!(
+    FailedEnvMigration,
+    "ENV022",
+    "Failed to migrate environment variable '{var}' to schema version {version}: {reason}.",
+    { var: &str, version: &str, reason: &str }
+);
+define_config_error!(
+    EnvConfigValidationFailed,
+    "ENV023",
+    "Environment config failed validation: {reason}.",
+    { reason: &str }
+);
+define_config_error!(
+    EcsMetadataUnavailable,
+    "ENV024",
+    "ECS task metadata is unavailable: '{var}' is not set (not running on Amazon ECS?).",
+    { var: &str }
+);
+define_config_error!(
+    FailedToFetchMetadata,
+    "ENV025",
+    "Failed to fetch metadata from '{endpoint}'.",
+    { endpoint: &str }
+);
+define_config_error!(
+    InvalidMetadataResponse,
+    "ENV026",
+    "Invalid metadata response from '{endpoint}': {reason}.",
+    { endpoint: &str, reason: &str }
+);
+define_config_error!(
+    FailedToReadConfigFile,
+    "ENV027",
+    "Failed to read config file '{path}'.",
+    { path: &str }
+);
+define_config_error!(
+    InvalidConfigCrateValue,
+    "ENV028",
+    "Value '{key}' of the config::Config source could not be read as a string.",
+    { key: &str }
+);
+define_config_error!(
+    InvalidFigmentValue,
+    "ENV029",
+    "Value '{key}' of the figment source is a dict, expected a scalar or array.",
+    { key: &str }
+);
+define_config_error!(
+    InvalidConfigFile,
+    "ENV030",
+    "Config file '{path}' is not a valid {format} object.",
+    { path: &str, format: &str }
+);
+define_config_error!(
+    InvalidConfigSignature,
+    "ENV031",
+    "Config file '{path}' failed signature verification: {reason}.",
+    { path: &str, reason: &str }
+);
+define_config_error!(
+    FailedToOpenRegistryKey,
+    "ENV032",
+    "Failed to open registry key '{path}'.",
+    { path: &str }
+);
+define_config_error!(
+    FailedToReadRegistryValue,
+    "ENV033",
+    "Failed to read value '{name}' from registry key '{path}'.",
+    { path: &str, name: &str }
+);
+define_config_error!(
+    InjectedConfigFailure,
+    "ENV034",
+    "Injected failure for config key '{key}' (chaos mode).",
+    { key: &str }
+);
+define_config_error!(
+    FailedToWriteConfigSnapshot,
+    "ENV035",
+    "Failed to write config snapshot to '{path}'.",
+    { path: &str }
+);
+define_config_error!(
+    InvalidFeatureFlagValue,
+    "ENV036",
+    "Feature flag '{var}' has invalid value '{value}' (expected on/off or a percentage like 25%).",
+    { var: &str, value: &str }
+);
+define_config_error!(
+    PolicyViolated,
+    "ENV037",
+    "Config violates {count} policy rule(s): {violations}.",
+    { count: &str, violations: &str }
+);
+define_config_error!(
+    EnvLoadFailed,
+    "ENV038",
+    "Failed to load {count} environment variable(s): {errors}",
+    { count: &str, errors: &str }
+);
+define_config_error!(
+    EnvKeyGroupNotSatisfied,
+    "ENV039",
+    "Invalid combination of environment variables ({problem}). Expected {expected}.",
+    { problem: &str, expected: &str }
+);
+define_config_error!(
+    InvalidSchemaCommand,
+    "ENV040",
+    "Invalid schema command: {reason}. Usage: print-schema [--format json|markdown|dotenv].",
+    { reason: &str }
+);
+define_config_error!(
+    EnvConfigDiverged,
+    "ENV041",
+    "Config '{config}' was loaded again with different values for: {keys}.",
+    { config: &str, keys: &str }
+);
+define_config_error!(
+    SchemaManifestOutdated,
+    "ENV042",
+    "Schema manifest '{path}' does not match the config ({changes}). Regenerate it with print-schema --format json.",
+    { path: &str, changes: &str }
+);
+define_config_error!(
+    SocketPathInUse,
+    "ENV043",
+    "Can't serve the config at '{path}', since a file that isn't a socket already exists there.",
+    { path: &str }
 );
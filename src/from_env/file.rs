@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::ConfigError;
+
+use super::{
+    load_from_source, ConfigSource, EnvConfigEnum, EnvVariables, FailedToReadConfigFile,
+    InvalidConfigFile,
+};
+
+// File sources.
+// --------------------------------------------------
+
+// In environments where env variables are impractical, the same typed config
+// can be driven by a config file instead:
+//
+// let config = load_from_file::<EnvConfig>("config.toml", Format::Toml)?;
+//
+// Nested tables are flattened into dotted paths, so keys are matched either at
+// the top level (ex. COGNITO_REGION) or by their full path (ex. database.host,
+// which can be declared with `pub static DB_HOST: &str = "database.host";`).
+//
+// Scalars are converted to their string representation, arrays are kept as
+// JSON text, and nulls are treated as missing. TOML and YAML support are
+// enabled by the "toml" and "yaml" features respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl Format {
+    fn name(&self) -> &'static str {
+        match self {
+            Format::Json => "JSON",
+            #[cfg(feature = "toml")]
+            Format::Toml => "TOML",
+            #[cfg(feature = "yaml")]
+            Format::Yaml => "YAML",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileSource(HashMap<String, String>);
+
+impl FileSource {
+    pub fn open(path: impl AsRef<Path>, format: Format) -> Result<Self, ConfigError> {
+        let path_str = path.as_ref().display().to_string();
+        let contents = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| FailedToReadConfigFile::with_source(&path_str, e))?;
+        Self::parse_named(&contents, format, &path_str)
+    }
+
+    pub fn parse(contents: &str, format: Format) -> Result<Self, ConfigError> {
+        Self::parse_named(contents, format, "<inline>")
+    }
+
+    fn parse_named(contents: &str, format: Format, name: &str) -> Result<Self, ConfigError> {
+        Ok(Self::from_value(parse_root(contents, format, name)?))
+    }
+
+    // Flatten an already-parsed root object, for sources that need to
+    // transform the document before it is read (ex. decrypting SOPS values).
+    pub(crate) fn from_value(root: Value) -> Self {
+        let mut values = HashMap::new();
+        flatten(None, root, &mut values);
+        FileSource(values)
+    }
+}
+
+pub(crate) fn parse_root(contents: &str, format: Format, name: &str) -> Result<Value, ConfigError> {
+    let root = match format {
+        Format::Json => serde_json::from_str::<Value>(contents)
+            .map_err(|e| InvalidConfigFile::with_source(name, format.name(), e))?,
+        #[cfg(feature = "toml")]
+        Format::Toml => toml::from_str::<Value>(contents)
+            .map_err(|e| InvalidConfigFile::with_source(name, format.name(), e))?,
+        #[cfg(feature = "yaml")]
+        Format::Yaml => serde_yaml::from_str::<Value>(contents)
+            .map_err(|e| InvalidConfigFile::with_source(name, format.name(), e))?,
+    };
+    if !root.is_object() {
+        return Err(InvalidConfigFile::new(name, format.name()));
+    }
+    Ok(root)
+}
+
+fn flatten(prefix: Option<&str>, value: Value, out: &mut HashMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let path = match prefix {
+                    Some(prefix) => format!("{prefix}.{key}"),
+                    None => key,
+                };
+                flatten(Some(&path), child, out);
+            }
+        }
+        Value::Null => {}
+        Value::String(s) => {
+            if let Some(prefix) = prefix {
+                out.insert(prefix.to_string(), s);
+            }
+        }
+        other => {
+            if let Some(prefix) = prefix {
+                out.insert(prefix.to_string(), other.to_string());
+            }
+        }
+    }
+}
+
+impl ConfigSource for FileSource {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        Ok(self.0.get(key).cloned())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.0.keys().cloned().collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "file"
+    }
+}
+
+pub fn load_from_file<T: EnvConfigEnum>(
+    path: impl AsRef<Path>,
+    format: Format,
+) -> Result<EnvVariables<T>, ConfigError> {
+    load_from_source(&FileSource::open(path, format)?)
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum};
+
+    use super::{load_from_file, ConfigSource, FileSource, Format};
+
+    define_env_variable!(REGION);
+    pub static DB_HOST: &str = "database.host";
+    pub static DB_PORT: &str = "database.port";
+
+    define_env_config!(
+        FileConfig,
+        Region => REGION,
+        DbHost => DB_HOST,
+        DbPort => DB_PORT,
+    );
+
+    #[test]
+    fn test_parse_json_flattens_nested_keys() {
+        let source = FileSource::parse(
+            r#"{
+                "REGION": "us-west-2",
+                "database": { "host": "localhost", "port": 5432, "replicas": ["a", "b"] },
+                "unset": null
+            }"#,
+            Format::Json,
+        )
+        .unwrap();
+
+        assert_eq!(source.get("REGION").unwrap().unwrap(), "us-west-2");
+        assert_eq!(source.get("database.host").unwrap().unwrap(), "localhost");
+        assert_eq!(source.get("database.port").unwrap().unwrap(), "5432");
+        assert_eq!(
+            source.get("database.replicas").unwrap().unwrap(),
+            r#"["a","b"]"#
+        );
+        assert_eq!(source.get("unset").unwrap(), None);
+        assert_eq!(source.get("database").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_invalid_json() {
+        assert!(FileSource::parse("{ not json", Format::Json).is_err());
+        assert!(FileSource::parse(r#"["not", "an", "object"]"#, Format::Json).is_err());
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let path = std::env::temp_dir().join("fractic_env_config_load_from_file.json");
+        std::fs::write(
+            &path,
+            r#"{ "REGION": "us-west-2", "database": { "host": "db", "port": 5432 } }"#,
+        )
+        .unwrap();
+
+        let config = load_from_file::<FileConfig>(&path, Format::Json).unwrap();
+        assert_eq!(config.get(&FileConfig::Region).unwrap(), "us-west-2");
+        assert_eq!(config.get(&FileConfig::DbHost).unwrap(), "db");
+        assert_eq!(config.get_parsed::<u16>(&FileConfig::DbPort).unwrap(), 5432);
+    }
+
+    #[test]
+    fn test_load_from_file_missing_key() {
+        let path = std::env::temp_dir().join("fractic_env_config_load_from_file_missing.json");
+        std::fs::write(&path, r#"{ "REGION": "us-west-2" }"#).unwrap();
+
+        assert!(load_from_file::<FileConfig>(&path, Format::Json).is_err());
+    }
+
+    #[test]
+    fn test_load_from_file_not_found() {
+        assert!(load_from_file::<FileConfig>("/nonexistent/config.json", Format::Json).is_err());
+    }
+}
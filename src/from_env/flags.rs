@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{PoisonError, RwLock};
+
+use sha2::{Digest, Sha256};
+
+use super::{ConfigSource, EnvConfigEnum, InvalidFeatureFlagValue, ProcessEnv};
+use crate::ConfigError;
+
+// Feature flags.
+// --------------------------------------------------
+
+// Interprets each key of a config as a boolean feature flag:
+//
+// define_env_config!(
+//     Flags,
+//     NewCheckout => FLAG_NEW_CHECKOUT,
+//     FastSearch => FLAG_FAST_SEARCH,
+// );
+//
+// let flags = FeatureFlags::<Flags>::load()?;
+// if flags.is_enabled(&Flags::NewCheckout) { ... }
+//
+// Flags are off unless set, so unlike load_env no key is required. Values are
+// on/off (also true/false, yes/no, 1/0), or a percentage for gradual rollouts:
+//
+// FLAG_FAST_SEARCH=25%
+//
+// if flags.is_enabled_for(&Flags::FastSearch, &user_id) { ... }
+//
+// Each id is assigned a stable bucket from a hash of the flag name and the id,
+// so the same user consistently sees the same behaviour, and raising the
+// percentage only adds users. Without an id, a partial rollout counts as off.
+//
+// The flags can be reloaded while the service is running (ex. on SIGHUP, or on
+// a timer), swapping in the new values only if all of them parse:
+//
+// flags.reload()?;
+#[derive(Debug)]
+pub struct FeatureFlags<T: EnvConfigEnum>(RwLock<HashMap<&'static str, FlagState>>, PhantomData<T>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagState {
+    Off,
+    On,
+    Rollout(u8),
+}
+
+impl<T: EnvConfigEnum> FeatureFlags<T> {
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_from(&ProcessEnv::new())
+    }
+
+    pub fn load_from(source: &impl ConfigSource) -> Result<Self, ConfigError> {
+        Ok(Self(RwLock::new(read_flags::<T>(source)?), PhantomData))
+    }
+
+    pub fn reload(&self) -> Result<(), ConfigError> {
+        self.reload_from(&ProcessEnv::new())
+    }
+
+    // On failure, the previous values are kept.
+    pub fn reload_from(&self, source: &impl ConfigSource) -> Result<(), ConfigError> {
+        let flags = read_flags::<T>(source)?;
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = flags;
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, flag: &T) -> bool {
+        match self.state(flag) {
+            FlagState::On | FlagState::Rollout(100) => true,
+            FlagState::Off | FlagState::Rollout(_) => false,
+        }
+    }
+
+    pub fn is_enabled_for(&self, flag: &T, id: &str) -> bool {
+        match self.state(flag) {
+            FlagState::On => true,
+            FlagState::Off => false,
+            FlagState::Rollout(percent) => rollout_bucket(flag.as_str(), id) < percent,
+        }
+    }
+
+    fn state(&self, flag: &T) -> FlagState {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(flag.as_str())
+            .copied()
+            .unwrap_or(FlagState::Off)
+    }
+}
+
+fn read_flags<T: EnvConfigEnum>(
+    source: &impl ConfigSource,
+) -> Result<HashMap<&'static str, FlagState>, ConfigError> {
+    let mut flags = HashMap::new();
+    for flag in T::value_list() {
+        if let Some(value) = source.get(flag.as_str())? {
+            flags.insert(flag.as_str(), parse_flag(flag.as_str(), &value)?);
+        }
+    }
+    Ok(flags)
+}
+
+fn parse_flag(var: &str, value: &str) -> Result<FlagState, ConfigError> {
+    let trimmed = value.trim();
+    if let Some(percent) = trimmed.strip_suffix('%') {
+        return match percent.trim().parse::<u8>() {
+            Ok(percent) if percent <= 100 => Ok(FlagState::Rollout(percent)),
+            _ => Err(InvalidFeatureFlagValue::new(var, value)),
+        };
+    }
+    match trimmed.to_ascii_lowercase().as_str() {
+        "on" | "true" | "yes" | "1" | "enabled" => Ok(FlagState::On),
+        "off" | "false" | "no" | "0" | "disabled" | "" => Ok(FlagState::Off),
+        _ => Err(InvalidFeatureFlagValue::new(var, value)),
+    }
+}
+
+// Stable bucket in 0..100.
+fn rollout_bucket(flag: &str, id: &str) -> u8 {
+    let digest = Sha256::digest(format!("{flag}:{id}").as_bytes());
+    let hash = u64::from_be_bytes(digest[..8].try_into().expect("digest is 32 bytes"));
+    (hash % 100) as u8
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum, FileSource, Format};
+
+    use super::{parse_flag, FeatureFlags, FlagState};
+
+    define_env_variable!(FLAG_NEW_CHECKOUT);
+    define_env_variable!(FLAG_FAST_SEARCH);
+    define_env_variable!(FLAG_UNSET);
+
+    define_env_config!(
+        Flags,
+        NewCheckout => FLAG_NEW_CHECKOUT,
+        FastSearch => FLAG_FAST_SEARCH,
+        Unset => FLAG_UNSET,
+    );
+
+    fn source(json: &str) -> FileSource {
+        FileSource::parse(json, Format::Json).unwrap()
+    }
+
+    #[test]
+    fn test_parse_flag() {
+        assert_eq!(parse_flag("F", "TRUE").unwrap(), FlagState::On);
+        assert_eq!(parse_flag("F", " off ").unwrap(), FlagState::Off);
+        assert_eq!(parse_flag("F", "25%").unwrap(), FlagState::Rollout(25));
+        assert!(parse_flag("F", "150%").is_err());
+        assert!(parse_flag("F", "maybe").is_err());
+    }
+
+    #[test]
+    fn test_feature_flags() {
+        let flags = FeatureFlags::<Flags>::load_from(&source(
+            r#"{ "FLAG_NEW_CHECKOUT": "on", "FLAG_FAST_SEARCH": "25%" }"#,
+        ))
+        .unwrap();
+        assert!(flags.is_enabled(&Flags::NewCheckout));
+        assert!(!flags.is_enabled(&Flags::FastSearch));
+        assert!(!flags.is_enabled(&Flags::Unset));
+
+        let ids = (0..1000).map(|i| format!("user-{i}")).collect::<Vec<_>>();
+        let enabled = ids
+            .iter()
+            .filter(|id| flags.is_enabled_for(&Flags::FastSearch, id))
+            .collect::<Vec<_>>();
+        assert!((150..350).contains(&enabled.len()));
+
+        // Raising the percentage keeps everyone already enabled.
+        flags
+            .reload_from(&source(r#"{ "FLAG_FAST_SEARCH": "50%" }"#))
+            .unwrap();
+        assert!(!flags.is_enabled(&Flags::NewCheckout));
+        assert!(enabled
+            .iter()
+            .all(|id| flags.is_enabled_for(&Flags::FastSearch, id)));
+
+        // Invalid values keep the previous flags.
+        assert!(flags
+            .reload_from(&source(r#"{ "FLAG_NEW_CHECKOUT": "sometimes" }"#))
+            .is_err());
+        assert!(flags.is_enabled_for(&Flags::FastSearch, enabled[0]));
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use super::warnings::{report_warning, ConfigWarning};
+
+// Environment freezing.
+// --------------------------------------------------
+
+// The process environment is shared by every test of a test binary, so a test
+// calling std::env::set_var (or remove_var) changes what later loads see in
+// unrelated tests, which then fail depending on the order they run in. To
+// track this down, the environment can be frozen from a helper every test
+// calls first:
+//
+// fn setup() {
+//     freeze_env();
+// }
+//
+// The first load_env afterwards takes a snapshot of the process environment,
+// and every later load_env reports a warning (see warnings.rs) naming the
+// variables that were set, changed or removed since (which then become the new
+// snapshot, so each mutation is only reported once). Overrides applied with
+// EnvOverrideScope don't mutate the environment, so aren't reported.
+//
+// Only checked in debug builds; in release builds, freeze_env has no effect.
+static FROZEN: AtomicBool = AtomicBool::new(false);
+static SNAPSHOT: Mutex<Option<HashMap<OsString, OsString>>> = Mutex::new(None);
+
+pub fn freeze_env() {
+    FROZEN.store(true, Ordering::SeqCst);
+}
+
+pub(crate) fn check_env_mutations() {
+    if !cfg!(debug_assertions) || !FROZEN.load(Ordering::SeqCst) {
+        return;
+    }
+    let current = std::env::vars_os().collect::<HashMap<_, _>>();
+    let mut snapshot = SNAPSHOT.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(previous) = snapshot.as_ref() {
+        let mutated = mutated_vars(previous, &current);
+        if !mutated.is_empty() {
+            report_warning(ConfigWarning::EnvironmentMutated { vars: mutated });
+        }
+    }
+    *snapshot = Some(current);
+}
+
+fn mutated_vars(
+    previous: &HashMap<OsString, OsString>,
+    current: &HashMap<OsString, OsString>,
+) -> Vec<String> {
+    let mut mutated = current
+        .iter()
+        .filter(|(key, value)| previous.get(*key) != Some(*value))
+        .map(|(key, _)| key)
+        .chain(previous.keys().filter(|key| !current.contains_key(*key)))
+        .map(|key| key.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    mutated.sort();
+    mutated
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::ffi::OsString;
+
+    use super::mutated_vars;
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<OsString, OsString> {
+        vars.iter()
+            .map(|(key, value)| (OsString::from(key), OsString::from(value)))
+            .collect()
+    }
+
+    #[test]
+    fn test_mutated_vars() {
+        let previous = env(&[("REGION", "us-west-2"), ("BUCKET", "a"), ("HOME", "/root")]);
+        let current = env(&[("REGION", "us-east-1"), ("HOME", "/root"), ("DEBUG", "1")]);
+        assert_eq!(
+            mutated_vars(&previous, &current),
+            vec!["BUCKET", "DEBUG", "REGION"]
+        );
+        assert!(mutated_vars(&current, &current).is_empty());
+    }
+}
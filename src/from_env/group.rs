@@ -0,0 +1,165 @@
+use std::fmt;
+
+use super::EnvKeyGroupNotSatisfied;
+use crate::ConfigError;
+
+// Key groups.
+// --------------------------------------------------
+
+// Some settings can be given in more than one way, ex. either a DATABASE_URL or
+// the individual DB_HOST/DB_PORT/DB_USER/DB_NAME variables. Such combinations
+// are declared at the config level:
+//
+// define_env_config!(
+//     EnvConfig {
+//         groups = [KeyGroup::exactly_one_of(&[
+//             &[DATABASE_URL],
+//             &[DB_HOST, DB_PORT, DB_USER, DB_NAME],
+//         ])],
+//     },
+//     DatabaseUrl => DATABASE_URL,
+//     DbHost => DB_HOST,
+//     ...
+// );
+//
+// Keys in a group are not required individually (read them with
+// EnvVariables::get_optional). Instead, load_env checks that exactly one (or,
+// with at_least_one_of, at least one) of the combinations is fully set, and
+// that no combination is only partially set. Errors list the accepted
+// combinations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyGroup {
+    alternatives: Vec<Vec<&'static str>>,
+    exclusive: bool,
+}
+
+impl KeyGroup {
+    pub fn exactly_one_of(alternatives: &[&[&'static str]]) -> Self {
+        Self {
+            alternatives: alternatives.iter().map(|keys| keys.to_vec()).collect(),
+            exclusive: true,
+        }
+    }
+
+    pub fn at_least_one_of(alternatives: &[&[&'static str]]) -> Self {
+        Self {
+            exclusive: false,
+            ..Self::exactly_one_of(alternatives)
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.alternatives.iter().flatten().any(|k| *k == key)
+    }
+
+    pub(crate) fn check(&self, is_set: impl Fn(&str) -> bool) -> Result<(), ConfigError> {
+        let mut complete = 0;
+        for keys in &self.alternatives {
+            let missing = keys.iter().filter(|key| !is_set(key)).collect::<Vec<_>>();
+            if missing.is_empty() {
+                complete += 1;
+            } else if missing.len() < keys.len() {
+                let missing = missing
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect::<Vec<_>>();
+                return Err(EnvKeyGroupNotSatisfied::new(
+                    &format!(
+                        "{} partially set, missing {}",
+                        keys.join(" + "),
+                        missing.join(", ")
+                    ),
+                    &self.to_string(),
+                ));
+            }
+        }
+        match complete {
+            0 => Err(EnvKeyGroupNotSatisfied::new("none set", &self.to_string())),
+            1 => Ok(()),
+            _ if self.exclusive => Err(EnvKeyGroupNotSatisfied::new(
+                "more than one set",
+                &self.to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for KeyGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.exclusive {
+            "exactly one of"
+        } else {
+            "at least one of"
+        };
+        let alternatives = self
+            .alternatives
+            .iter()
+            .map(|keys| keys.join(" + "))
+            .collect::<Vec<_>>();
+        write!(f, "{kind}: {}", alternatives.join(" | "))
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        define_env_config, define_env_variable, load_env_with, EnvConfigEnum, LoadOptions,
+    };
+
+    use super::KeyGroup;
+
+    define_env_variable!(GROUP_DATABASE_URL);
+    define_env_variable!(GROUP_DB_HOST);
+    define_env_variable!(GROUP_DB_PORT);
+
+    define_env_config!(
+        GroupConfig {
+            groups = [KeyGroup::exactly_one_of(&[
+                &[GROUP_DATABASE_URL],
+                &[GROUP_DB_HOST, GROUP_DB_PORT],
+            ])],
+        },
+        DatabaseUrl => GROUP_DATABASE_URL,
+        DbHost => GROUP_DB_HOST,
+        DbPort => GROUP_DB_PORT,
+    );
+
+    fn load(vars: &[(&str, &str)]) -> Result<(), String> {
+        let fake_env = vars.iter().copied().collect::<HashMap<_, _>>();
+        load_env_with::<GroupConfig>(LoadOptions::new().reader(fake_env))
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_key_group_display() {
+        let group = KeyGroup::at_least_one_of(&[&["A"], &["B", "C"]]);
+        assert_eq!(group.to_string(), "at least one of: A | B + C");
+        assert!(group.contains("C"));
+        assert!(!group.contains("D"));
+    }
+
+    #[test]
+    fn test_key_group_load() {
+        assert!(load(&[("GROUP_DATABASE_URL", "postgres://db")]).is_ok());
+        assert!(load(&[("GROUP_DB_HOST", "db"), ("GROUP_DB_PORT", "5432")]).is_ok());
+
+        let error = load(&[]).unwrap_err();
+        assert!(error.contains("GROUP_DATABASE_URL | GROUP_DB_HOST + GROUP_DB_PORT"));
+        assert!(load(&[("GROUP_DB_HOST", "db")])
+            .unwrap_err()
+            .contains("missing GROUP_DB_PORT"));
+        assert!(load(&[
+            ("GROUP_DATABASE_URL", "postgres://db"),
+            ("GROUP_DB_HOST", "db"),
+            ("GROUP_DB_PORT", "5432"),
+        ])
+        .is_err());
+    }
+}
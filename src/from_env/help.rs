@@ -0,0 +1,90 @@
+use super::{EnvConfigEnum, KeySpec};
+
+// CLI help.
+// --------------------------------------------------
+
+// Renders the variables of a config as an aligned block for the --help output
+// of binaries, so user-facing help stays in sync with the typed config:
+//
+// println!("{USAGE}\n\n{}", format_env_help::<EnvConfig>());
+//
+// Environment variables:
+//   NAME        DESCRIPTION         DEFAULT  REQUIRED
+//   REGION      AWS region.         -        yes
+//   LOG_LEVEL   Minimum log level.  info     no
+//   SENTRY_DSN  -                   -        in prod
+//
+// Descriptions and defaults come from the key specs (see spec.rs).
+pub fn format_env_help<T: EnvConfigEnum>() -> String {
+    let rows = T::value_list()
+        .iter()
+        .map(|key| help_row(&key.spec()))
+        .collect::<Vec<_>>();
+    format_table(&rows)
+}
+
+const HEADER: [&str; 4] = ["NAME", "DESCRIPTION", "DEFAULT", "REQUIRED"];
+
+fn help_row(spec: &KeySpec) -> [String; 4] {
+    [
+        spec.name().to_string(),
+        spec.help_text().unwrap_or("-").to_string(),
+        spec.default_text().unwrap_or("-").to_string(),
+        spec.requirement(),
+    ]
+}
+
+fn format_table(rows: &[[String; 4]]) -> String {
+    let mut widths = HEADER.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let header = HEADER.map(str::to_string);
+    let mut out = String::from("Environment variables:");
+    for row in std::iter::once(&header).chain(rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.push_str("\n  ");
+        out.push_str(line.trim_end());
+    }
+    out
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum, Stage};
+
+    use super::format_env_help;
+
+    define_env_variable!(REGION);
+    define_env_variable!(LOG_LEVEL);
+    define_env_variable!(SENTRY_DSN);
+
+    define_env_config!(
+        HelpConfig,
+        Region => REGION { description = "AWS region." },
+        LogLevel => LOG_LEVEL { description = "Minimum log level.", default = "info" },
+        SentryDsn => SENTRY_DSN { required_in = [Stage::Prod] },
+    );
+
+    #[test]
+    fn test_format_env_help() {
+        assert_eq!(
+            format_env_help::<HelpConfig>(),
+            "Environment variables:\n  \
+             NAME        DESCRIPTION         DEFAULT  REQUIRED\n  \
+             REGION      AWS region.         -        yes\n  \
+             LOG_LEVEL   Minimum log level.  info     no\n  \
+             SENTRY_DSN  -                   -        in prod"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use crate::ConfigError;
+
+use super::http::http_request;
+use super::{ConfigSource, FailedToFetchMetadata, InvalidMetadataResponse};
+
+// Common instance metadata paths, usable as key names.
+pub static IMDS_INSTANCE_ID: &str = "instance-id";
+pub static IMDS_INSTANCE_TYPE: &str = "instance-type";
+pub static IMDS_REGION: &str = "placement/region";
+pub static IMDS_AVAILABILITY_ZONE: &str = "placement/availability-zone";
+
+// EC2 instance metadata source.
+// --------------------------------------------------
+
+// On EC2, instance identity can be read into the typed config from the
+// instance metadata service (IMDSv2). Keys are metadata paths, relative to
+// /latest/meta-data/:
+//
+// pub static INSTANCE_ROLE: &str = "tags/instance/Role";
+//
+// define_env_config!(
+//     InstanceConfig,
+//     Id => IMDS_INSTANCE_ID,
+//     Region => IMDS_REGION,
+//     Role => INSTANCE_ROLE,
+// );
+//
+// let instance = load_from_source::<InstanceConfig>(&ImdsSource::connect()?)?;
+//
+// A session token is requested once on connect, and each key is then fetched
+// with it when loaded. Paths the service doesn't have (404) are missing. Tags
+// are only available under tags/instance/ if instance metadata tags are
+// enabled on the instance.
+//
+// The service is reached at its default address, unless overridden by
+// AWS_EC2_METADATA_SERVICE_ENDPOINT (as with the AWS SDKs).
+#[derive(Debug, Clone)]
+pub struct ImdsSource {
+    endpoint: String,
+    token: String,
+}
+
+const DEFAULT_ENDPOINT: &str = "http://169.254.169.254";
+const TOKEN_TTL_SECS: &str = "21600";
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+impl ImdsSource {
+    pub fn connect() -> Result<Self, ConfigError> {
+        let endpoint = std::env::var("AWS_EC2_METADATA_SERVICE_ENDPOINT")
+            .unwrap_or_else(|_| DEFAULT_ENDPOINT.to_string());
+        Self::connect_to(&endpoint)
+    }
+
+    pub fn connect_to(endpoint: &str) -> Result<Self, ConfigError> {
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+        let token_url = format!("{endpoint}/latest/api/token");
+        let response = http_request(
+            "PUT",
+            &token_url,
+            &[("X-aws-ec2-metadata-token-ttl-seconds", TOKEN_TTL_SECS)],
+            TIMEOUT,
+        )
+        .map_err(|e| FailedToFetchMetadata::with_source(&token_url, e))?;
+        if response.status != 200 {
+            return Err(InvalidMetadataResponse::new(
+                &token_url,
+                &format!("status {}", response.status),
+            ));
+        }
+        Ok(Self {
+            endpoint,
+            token: response.body.trim().to_string(),
+        })
+    }
+}
+
+impl ConfigSource for ImdsSource {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        let url = format!("{}/latest/meta-data/{key}", self.endpoint);
+        let response = http_request(
+            "GET",
+            &url,
+            &[("X-aws-ec2-metadata-token", &self.token)],
+            TIMEOUT,
+        )
+        .map_err(|e| FailedToFetchMetadata::with_source(&url, e))?;
+        match response.status {
+            200 => Ok(Some(response.body)),
+            404 => Ok(None),
+            status => Err(InvalidMetadataResponse::new(
+                &url,
+                &format!("status {status}"),
+            )),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "imds"
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::from_env::http::tests::serve;
+    use crate::{define_env_config, load_from_source, EnvConfigEnum};
+
+    use super::{ImdsSource, IMDS_INSTANCE_ID, IMDS_REGION};
+
+    pub static IMDS_TEST_ROLE: &str = "tags/instance/Role";
+
+    define_env_config!(
+        InstanceConfig,
+        Id => IMDS_INSTANCE_ID,
+        Region => IMDS_REGION,
+        Role => IMDS_TEST_ROLE,
+    );
+
+    #[test]
+    fn test_imds_source() {
+        let (url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\n\r\ntest-token",
+            "HTTP/1.1 200 OK\r\n\r\ni-0123456789abcdef0",
+            "HTTP/1.1 200 OK\r\n\r\nus-west-2",
+            "HTTP/1.1 404 Not Found\r\n\r\n",
+        ]);
+        let source = ImdsSource::connect_to(&url).unwrap();
+        let error = load_from_source::<InstanceConfig>(&source).unwrap_err();
+        assert!(error.to_string().contains("tags/instance/Role"));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("PUT /latest/api/token HTTP/1.1"));
+        assert!(requests[1].starts_with("GET /latest/meta-data/instance-id HTTP/1.1"));
+        assert!(requests[1].contains("X-aws-ec2-metadata-token: test-token"));
+    }
+}
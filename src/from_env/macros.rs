@@ -1,32 +1,359 @@
+// Names are checked at compile time to be portable (POSIX) env variable names,
+// which some platforms otherwise silently never resolve: uppercase letters,
+// digits and underscores, not starting with a digit.
 #[macro_export]
 macro_rules! define_env_variable {
     ($T:ident) => {
         pub static $T: &str = stringify!($T);
+        const _: () = assert!(
+            $crate::__is_portable_env_var_name(stringify!($T)),
+            concat!(
+                "'",
+                stringify!($T),
+                "' is not a portable env variable name (only A-Z, 0-9 and '_')."
+            )
+        );
     };
 }
 
+#[doc(hidden)]
+pub const fn __is_portable_env_var_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    if bytes.is_empty() || bytes[0].is_ascii_digit() {
+        return false;
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if !(b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_') {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// Key identities of nested configs (see compose.rs): the distinct IDs across
+// the children's KEY_IDS, in first-seen order.
+#[doc(hidden)]
+pub const fn __count_distinct_key_ids(parts: &[&[&'static str]]) -> usize {
+    let mut count = 0;
+    let mut part = 0;
+    while part < parts.len() {
+        let mut i = 0;
+        while i < parts[part].len() {
+            if !seen_before(parts, part, i) {
+                count += 1;
+            }
+            i += 1;
+        }
+        part += 1;
+    }
+    count
+}
+
+#[doc(hidden)]
+pub const fn __distinct_key_ids<const N: usize>(parts: &[&[&'static str]]) -> [&'static str; N] {
+    let mut ids = [""; N];
+    let mut count = 0;
+    let mut part = 0;
+    while part < parts.len() {
+        let mut i = 0;
+        while i < parts[part].len() {
+            if !seen_before(parts, part, i) {
+                ids[count] = parts[part][i];
+                count += 1;
+            }
+            i += 1;
+        }
+        part += 1;
+    }
+    ids
+}
+
+const fn seen_before(parts: &[&[&str]], part: usize, i: usize) -> bool {
+    let id = parts[part][i];
+    let mut p = 0;
+    while p <= part {
+        let end = if p == part { i } else { parts[p].len() };
+        let mut j = 0;
+        while j < end {
+            if str_eq(parts[p][j], id) {
+                return true;
+            }
+            j += 1;
+        }
+        p += 1;
+    }
+    false
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
 #[macro_export]
 macro_rules! define_env_config {
-    ($T:ident, $($k:ident => $v:ident),* $(,)?) => {
+    // Parent of existing configs (see compose.rs), with a variant per child.
+    ($T:ident { $($field:ident: $C:ty),* $(,)? } $(,)?) => {
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+        pub enum $T {
+            $($field($C)),*
+        }
+
+        impl EnvConfigEnum for $T {
+            // Keys shared by several children are listed (and counted) once.
+            const KEY_IDS: &'static [&'static str] = &$crate::__distinct_key_ids::<
+                { $crate::__count_distinct_key_ids(&[$(<$C as EnvConfigEnum>::KEY_IDS),*]) },
+            >(&[$(<$C as EnvConfigEnum>::KEY_IDS),*]);
+
+            fn as_str(&self) -> &'static str {
+                match self {
+                    $($T::$field(key) => key.as_str()),*
+                }
+            }
+
+            fn value_list() -> &'static [Self] {
+                static KEYS: ::std::sync::OnceLock<::std::vec::Vec<$T>> =
+                    ::std::sync::OnceLock::new();
+                KEYS.get_or_init(|| {
+                    let mut ids = ::std::vec::Vec::<&str>::new();
+                    let mut keys = ::std::vec::Vec::<$T>::new();
+                    $(for (key, id) in <$C as EnvConfigEnum>::value_list()
+                        .iter()
+                        .zip(<$C as EnvConfigEnum>::KEY_IDS)
+                    {
+                        if !ids.contains(id) {
+                            ids.push(id);
+                            keys.push($T::$field(key.clone()));
+                        }
+                    })*
+                    keys
+                })
+            }
+
+            fn spec(&self) -> $crate::KeySpec {
+                match self {
+                    $($T::$field(key) => key.spec()),*
+                }
+            }
+
+            fn validator() -> Option<$crate::ValidateFn<Self>> {
+                let validate_children: $crate::ValidateFn<Self> = |env| {
+                    $(if let Some(validate) = <$C as EnvConfigEnum>::validator() {
+                        validate(&env.clone_into::<$C>()?)?;
+                    })*
+                    Ok(())
+                };
+                Some(validate_children)
+            }
+
+            fn groups() -> Vec<$crate::KeyGroup> {
+                let mut groups = Vec::new();
+                $(groups.extend(<$C as EnvConfigEnum>::groups());)*
+                groups
+            }
+
+            fn key_origins() -> Vec<$crate::KeyOrigin> {
+                let mut origins = Vec::new();
+                $(origins.extend(<$C as EnvConfigEnum>::key_origins());)*
+                origins
+            }
+        }
+
+        $(impl $crate::ContainsConfig<$C> for $T {})*
+    };
+    (
+        $T:ident $({ $($copt:ident = $cval:expr),* $(,)? })?,
+        $($k:ident => $v:ident $({ $($opt:ident = $val:expr),* $(,)? })?),* $(,)?
+    ) => {
         #[derive(Debug, PartialEq, Eq, Hash, Clone)]
         pub enum $T {
             $($k),*
         }
 
         impl EnvConfigEnum for $T {
+            const KEY_IDS: &'static [&'static str] = &[$(stringify!($v)),*];
+
             fn as_str(&self) -> &'static str {
                 match *self {
                     $($T::$k => $v),*
                 }
             }
 
-            fn value_list() -> Vec<Self> {
-                [$($T::$k),*].to_vec()
+            fn value_list() -> &'static [Self] {
+                &[$($T::$k),*]
+            }
+
+            fn spec(&self) -> $crate::KeySpec {
+                match *self {
+                    $($T::$k => $crate::KeySpec::new($v) $($(.$opt($val))*)?),*
+                }
+            }
+
+            fn key_origins() -> Vec<$crate::KeyOrigin> {
+                vec![$($crate::KeyOrigin {
+                    name: $v,
+                    address: ::core::ptr::addr_of!($v) as usize,
+                    module: module_path!(),
+                }),*]
+            }
+
+            $($($crate::__env_config_option!($copt, $cval);)*)?
+        }
+    };
+}
+
+// Config-level options of define_env_config!, each mapping to an item of the
+// EnvConfigEnum impl.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __env_config_option {
+    (validate, $validate:expr) => {
+        fn validator() -> Option<$crate::ValidateFn<Self>> {
+            Some($validate)
+        }
+    };
+    (groups, $groups:expr) => {
+        fn groups() -> Vec<$crate::KeyGroup> {
+            Vec::from($groups)
+        }
+    };
+    (migrations, $migrations:expr) => {
+        fn migrations() -> $crate::Migrations {
+            $migrations
+        }
+    };
+    (divergence, $mode:expr) => {
+        fn divergence_mode() -> $crate::DivergenceMode {
+            $mode
+        }
+    };
+}
+
+// Generates a plain struct of typed fields for a config, converted from its
+// EnvVariables with TryFrom (see typed.rs):
+//
+// define_env_values!(
+//     EnvValues from EnvConfig,
+//     cognito_region: String => CognitoRegion,
+//     port: u16 => Port,
+// );
+#[macro_export]
+macro_rules! define_env_values {
+    (
+        $(#[$meta:meta])*
+        $S:ident from $T:ident,
+        $($field:ident: $ty:ty => $k:ident),* $(,)?
+    ) => {
+        $(#[$meta])*
+        pub struct $S {
+            $(pub $field: $ty),*
+        }
+
+        impl ::core::convert::TryFrom<&$crate::EnvVariables<$T>> for $S {
+            type Error = $crate::ConfigError;
+
+            fn try_from(env: &$crate::EnvVariables<$T>) -> Result<Self, Self::Error> {
+                Ok(Self {
+                    $($field: <$ty as $crate::FromEnvValue>::from_env_value(
+                        <$T as $crate::EnvConfigEnum>::as_str(&$T::$k),
+                        env.get_optional(&$T::$k),
+                    )?),*
+                })
+            }
+        }
+
+        impl ::core::convert::TryFrom<$crate::EnvVariables<$T>> for $S {
+            type Error = $crate::ConfigError;
+
+            fn try_from(env: $crate::EnvVariables<$T>) -> Result<Self, Self::Error> {
+                Self::try_from(&env)
             }
         }
     };
 }
 
+// Narrow a parent config (EnvVariables or SecretValues) down to a child config,
+// propagating the error with the location of the window site:
+//
+// let dynamo_env = window!(env => DynamoConfig);
+//
+// Expands to env.clone_into::<DynamoConfig>()?, so must be used in a function
+// returning Result<_, ConfigError>.
+#[macro_export]
+macro_rules! window {
+    ($parent:expr => $Child:ty) => {
+        $parent.clone_into::<$Child>().map_err(|e| {
+            $crate::InvalidConfigWindow::with_source(
+                stringify!($Child),
+                concat!(file!(), ":", line!()),
+                e,
+            )
+        })?
+    };
+}
+
+// Declares a function taking a narrowed config, as a function taking any
+// parent config that contains it (see ContainsConfig in compose.rs):
+//
+// requires_env! {
+//     pub async fn create_table(env: DynamoConfig, name: &str) -> Result<(), ConfigError> {
+//         let region = env.get(&DynamoConfig::DynamoRegion)?;
+//         ...
+//     }
+// }
+//
+// create_table(&app_env, "users").await?;
+//
+// Expands to a function generic over the parent config, which narrows it down
+// on entry, so (as with window!) the function must return a Result with an
+// error convertible from ConfigError. Calls with a config that doesn't contain
+// DynamoConfig don't compile.
+#[macro_export]
+macro_rules! requires_env {
+    (
+        $(#[$meta:meta])*
+        $vis:vis async fn $name:ident(
+            $env:ident: $C:ty $(, $arg:ident: $ty:ty)* $(,)?
+        ) -> $ret:ty $body:block
+    ) => {
+        $(#[$meta])*
+        $vis async fn $name<P: $crate::ContainsConfig<$C>>(
+            $env: &$crate::EnvVariables<P> $(, $arg: $ty)*
+        ) -> $ret {
+            let $env = $env.child::<$C>()?;
+            $body
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis fn $name:ident(
+            $env:ident: $C:ty $(, $arg:ident: $ty:ty)* $(,)?
+        ) -> $ret:ty $body:block
+    ) => {
+        $(#[$meta])*
+        $vis fn $name<P: $crate::ContainsConfig<$C>>(
+            $env: &$crate::EnvVariables<P> $(, $arg: $ty)*
+        ) -> $ret {
+            let $env = $env.child::<$C>()?;
+            $body
+        }
+    };
+}
+
 #[cfg(test)]
 mod macro_tests {
     use crate::{define_env_config, define_env_variable, EnvConfigEnum, EnvVariables};
@@ -38,6 +365,18 @@ mod macro_tests {
         assert_eq!(TEST_ENV_VAR, "TEST_ENV_VAR");
     }
 
+    #[test]
+    fn test_portable_env_var_names() {
+        use super::__is_portable_env_var_name;
+
+        assert!(__is_portable_env_var_name("DB_HOST_2"));
+        assert!(__is_portable_env_var_name("_PRIVATE"));
+        assert!(!__is_portable_env_var_name("db_host"));
+        assert!(!__is_portable_env_var_name("DB-HOST"));
+        assert!(!__is_portable_env_var_name("2FA_SECRET"));
+        assert!(!__is_portable_env_var_name(""));
+    }
+
     #[test]
     fn test_define_env_config() {
         define_env_variable!(TEST_ENV_VAR_1);
@@ -54,6 +393,7 @@ mod macro_tests {
 
         let expected_list = vec![TestConfig::TestVar1, TestConfig::TestVar2];
         assert_eq!(TestConfig::value_list(), expected_list);
+        assert_eq!(TestConfig::COUNT, 2);
     }
 
     #[test]
@@ -82,6 +422,7 @@ mod macro_tests {
         define_env_config!(EmptyConfig,);
         let expected_list: Vec<EmptyConfig> = vec![];
         assert_eq!(EmptyConfig::value_list(), expected_list);
+        assert_eq!(EmptyConfig::COUNT, 0);
     }
 
     #[test]
@@ -98,4 +439,100 @@ mod macro_tests {
         let expected_list = vec![SingleConfig::SingleVar];
         assert_eq!(SingleConfig::value_list(), expected_list);
     }
+
+    #[test]
+    fn test_define_env_config_with_constraints() {
+        define_env_variable!(TEST_ENV_VAR_REGION);
+        define_env_variable!(TEST_ENV_VAR_BUCKET);
+        define_env_variable!(TEST_ENV_VAR_PORT);
+        define_env_variable!(TEST_ENV_VAR_PLAIN);
+
+        define_env_config!(
+            ConstrainedConfig,
+            Region => TEST_ENV_VAR_REGION { one_of = ["us-west-2", "us-east-1"] },
+            Port => TEST_ENV_VAR_PORT { range = 1..=65535 },
+            Bucket => TEST_ENV_VAR_BUCKET {
+                matches = r"^[a-z0-9-]+$",
+                one_of = ["bucket-a", "bucket-b"],
+            },
+            Plain => TEST_ENV_VAR_PLAIN,
+        );
+
+        assert_eq!(ConstrainedConfig::Region.as_str(), "TEST_ENV_VAR_REGION");
+        assert_eq!(ConstrainedConfig::Region.spec().constraints().len(), 1);
+        assert_eq!(ConstrainedConfig::Bucket.spec().constraints().len(), 2);
+        assert!(ConstrainedConfig::Port.spec().validate("8080").is_ok());
+        assert!(ConstrainedConfig::Port.spec().validate("0").is_err());
+        assert!(ConstrainedConfig::Plain.spec().constraints().is_empty());
+    }
+
+    #[test]
+    fn test_window() {
+        use crate::ConfigError;
+
+        define_env_variable!(TEST_ENV_VAR_1);
+        define_env_variable!(TEST_ENV_VAR_2);
+
+        define_env_config!(
+            ParentConfig,
+            TestVar1 => TEST_ENV_VAR_1,
+            TestVar2 => TEST_ENV_VAR_2,
+        );
+        define_env_config!(
+            ChildConfig,
+            TestVar1 => TEST_ENV_VAR_1,
+        );
+
+        fn narrow(parent: &EnvVariables<ParentConfig>) -> Result<String, ConfigError> {
+            let child = window!(parent => ChildConfig);
+            Ok(child.get(&ChildConfig::TestVar1)?.to_string())
+        }
+        fn widen(child: &EnvVariables<ChildConfig>) -> Result<(), ConfigError> {
+            window!(child => ParentConfig);
+            Ok(())
+        }
+
+        let parent: EnvVariables<ParentConfig> = EnvVariables::from(HashMap::from([
+            (TEST_ENV_VAR_1, String::from("value1")),
+            (TEST_ENV_VAR_2, String::from("value2")),
+        ]));
+        assert_eq!(narrow(&parent).unwrap(), "value1");
+        assert!(widen(&parent.clone_into::<ChildConfig>().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_requires_env() {
+        use crate::ConfigError;
+
+        define_env_variable!(TEST_ENV_VAR_1);
+        define_env_variable!(TEST_ENV_VAR_2);
+
+        define_env_config!(
+            ChildConfig,
+            TestVar1 => TEST_ENV_VAR_1,
+        );
+        define_env_config!(
+            OtherConfig,
+            TestVar2 => TEST_ENV_VAR_2,
+        );
+        define_env_config!(ParentConfig {
+            child: ChildConfig,
+            other: OtherConfig,
+        });
+
+        requires_env! {
+            fn read_child(env: ChildConfig, suffix: &str) -> Result<String, ConfigError> {
+                Ok(format!("{}{suffix}", env.get(&ChildConfig::TestVar1)?))
+            }
+        }
+
+        let child: EnvVariables<ChildConfig> =
+            EnvVariables::from(HashMap::from([(TEST_ENV_VAR_1, String::from("value1"))]));
+        assert_eq!(read_child(&child, "!").unwrap(), "value1!");
+        let parent: EnvVariables<ParentConfig> = EnvVariables::from(HashMap::from([
+            (TEST_ENV_VAR_1, String::from("value1")),
+            (TEST_ENV_VAR_2, String::from("value2")),
+        ]));
+        assert_eq!(read_child(&parent, "?").unwrap(), "value1?");
+    }
 }
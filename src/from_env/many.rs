@@ -0,0 +1,109 @@
+use super::{EnvConfigEnum, EnvVariables, MissingEnvVariables};
+use crate::ConfigError;
+
+// Batch access.
+// --------------------------------------------------
+
+// Constructors reading several values in a row can fetch them in one call:
+//
+// let (region, table, queue_url) = env.get_many((
+//     EnvConfig::DynamoRegion,
+//     EnvConfig::DynamoTable,
+//     EnvConfig::QueueUrl,
+// ))?;
+//
+// Rather than stopping at the first failure, the returned error lists every
+// key that could not be read (ex. several keys only required in other
+// stages). Implemented for tuples of up to 12 keys.
+pub trait KeyTuple<T: EnvConfigEnum> {
+    type Values<'a>;
+
+    fn get_from(self, env: &EnvVariables<T>) -> Result<Self::Values<'_>, ConfigError>;
+}
+
+impl<T: EnvConfigEnum> EnvVariables<T> {
+    pub fn get_many<K: KeyTuple<T>>(&self, keys: K) -> Result<K::Values<'_>, ConfigError> {
+        keys.get_from(self)
+    }
+}
+
+macro_rules! of_key {
+    ($k:ident, $ty:ty) => {
+        $ty
+    };
+}
+
+macro_rules! impl_key_tuple {
+    ($($k:ident),+) => {
+        impl<T: EnvConfigEnum> KeyTuple<T> for ($(of_key!($k, T),)+) {
+            type Values<'a> = ($(of_key!($k, &'a str),)+);
+
+            fn get_from(self, env: &EnvVariables<T>) -> Result<Self::Values<'_>, ConfigError> {
+                let ($($k,)+) = self;
+                $(let $k = env.get(&$k).map_err(|_| $k.as_str());)+
+                match ($($k,)+) {
+                    ($(Ok($k),)+) => Ok(($($k,)+)),
+                    ($($k,)+) => {
+                        let missing = [$($k.err()),+].into_iter().flatten().collect::<Vec<_>>();
+                        Err(MissingEnvVariables::new(&missing.join(", ")))
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_key_tuple!(a);
+impl_key_tuple!(a, b);
+impl_key_tuple!(a, b, c);
+impl_key_tuple!(a, b, c, d);
+impl_key_tuple!(a, b, c, d, e);
+impl_key_tuple!(a, b, c, d, e, f);
+impl_key_tuple!(a, b, c, d, e, f, g);
+impl_key_tuple!(a, b, c, d, e, f, g, h);
+impl_key_tuple!(a, b, c, d, e, f, g, h, i);
+impl_key_tuple!(a, b, c, d, e, f, g, h, i, j);
+impl_key_tuple!(a, b, c, d, e, f, g, h, i, j, k);
+impl_key_tuple!(a, b, c, d, e, f, g, h, i, j, k, l);
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum, EnvVariables, Stage};
+
+    define_env_variable!(MANY_TEST_REGION);
+    define_env_variable!(MANY_TEST_TABLE);
+    define_env_variable!(MANY_TEST_QUEUE_URL);
+    define_env_variable!(MANY_TEST_DSN);
+
+    define_env_config!(
+        ManyConfig,
+        Region => MANY_TEST_REGION,
+        Table => MANY_TEST_TABLE,
+        QueueUrl => MANY_TEST_QUEUE_URL { required_in = [Stage::Prod] },
+        Dsn => MANY_TEST_DSN { required_in = [Stage::Prod] },
+    );
+
+    #[test]
+    fn test_get_many() {
+        let env: EnvVariables<ManyConfig> = EnvVariables::from(HashMap::from([
+            (MANY_TEST_REGION, String::from("us-west-2")),
+            (MANY_TEST_TABLE, String::from("users")),
+        ]));
+        let (region, table) = env
+            .get_many((ManyConfig::Region, ManyConfig::Table))
+            .unwrap();
+        assert_eq!((region, table), ("us-west-2", "users"));
+
+        let error = env
+            .get_many((ManyConfig::Region, ManyConfig::QueueUrl, ManyConfig::Dsn))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("MANY_TEST_QUEUE_URL, MANY_TEST_DSN"));
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::{Duration, SystemTime};
+
+use super::{EnvConfigEnum, EnvVariables};
+
+// Load metadata.
+// --------------------------------------------------
+
+// EnvVariables and SecretValues record when and where they were loaded from,
+// so health checks and debug endpoints can report the age of the config:
+//
+// let env = load_env::<EnvConfig>()?;
+// let age = env.loaded_at().elapsed().unwrap_or_default();
+// debug_info.insert("config_source", env.source()); // "env"
+//
+// The source is the name of the ConfigSource (ex. "env", "file", "k8s"), or
+// "secrets:<secret ID>" for secrets loaded through a SecretsFetcher. Values
+// built from a map (ex. in tests) have source "map". expires_at is the time the
+// values should be considered stale, if the source has a TTL (ex. secrets
+// fetched through a CachedSecretsFetcher). Values cloned with clone_into (or
+// window!) keep the metadata of their parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LoadMetadata {
+    pub(crate) loaded_at: SystemTime,
+    pub(crate) source: String,
+    pub(crate) expires_at: Option<SystemTime>,
+}
+
+impl LoadMetadata {
+    pub(crate) fn new(source: impl Into<String>) -> Self {
+        Self {
+            loaded_at: SystemTime::now(),
+            source: source.into(),
+            expires_at: None,
+        }
+    }
+
+    pub(crate) fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.expires_at = ttl.map(|ttl| self.loaded_at + ttl);
+        self
+    }
+}
+
+impl<T: EnvConfigEnum> EnvVariables<T> {
+    pub fn loaded_at(&self) -> SystemTime {
+        self.2.loaded_at
+    }
+
+    pub fn source(&self) -> &str {
+        &self.2.source
+    }
+
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.2.expires_at
+    }
+
+    pub(crate) fn with_metadata(mut self, metadata: LoadMetadata) -> Self {
+        self.2 = metadata;
+        self
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{Duration, SystemTime};
+
+    use crate::{define_env_config, define_env_variable, load_from_source, EnvConfigEnum};
+
+    use super::LoadMetadata;
+
+    define_env_variable!(METADATA_REGION);
+
+    define_env_config!(
+        MetadataConfig,
+        Region => METADATA_REGION,
+    );
+
+    #[test]
+    fn test_load_metadata() {
+        let before = SystemTime::now();
+        let source = HashMap::from([(String::from("METADATA_REGION"), String::from("us-west-2"))]);
+        let env = load_from_source::<MetadataConfig>(&source).unwrap();
+        assert!(env.loaded_at() >= before);
+        assert_eq!(env.source(), "map");
+        assert_eq!(env.expires_at(), None);
+
+        let metadata = LoadMetadata::new("file").with_ttl(Some(Duration::from_secs(60)));
+        let env = env.with_metadata(metadata.clone());
+        let window = env.clone_into::<MetadataConfig>().unwrap();
+        assert_eq!(window.source(), "file");
+        assert_eq!(
+            window.expires_at(),
+            Some(metadata.loaded_at + Duration::from_secs(60))
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use super::{ConfigSource, FailedEnvMigration, InvalidEnvVariableType};
+use crate::ConfigError;
+
+// Migrations.
+// --------------------------------------------------
+
+// Renaming or splitting variables usually means updating every deployment's
+// environment at the same time as the code. Instead, the config can declare
+// migrations from older schema versions, which are applied during load:
+//
+// define_env_config!(
+//     EnvConfig {
+//         migrations = Migrations::new()
+//             .version_var(CONFIG_VERSION)
+//             .rename(2, DB_HOST, DATABASE_HOST)
+//             .split(3, DATABASE_ADDR, &[DATABASE_HOST, DATABASE_PORT], split_addr),
+//     },
+//     DatabaseHost => DATABASE_HOST,
+//     DatabasePort => DATABASE_PORT,
+// );
+//
+// fn split_addr(addr: &str) -> Result<Vec<String>, String> {
+//     let (host, port) = addr.split_once(':').ok_or("expected HOST:PORT")?;
+//     Ok(vec![host.to_string(), port.to_string()])
+// }
+//
+// Each migration is tagged with the schema version it upgrades to. A variable
+// missing from the source is derived from the variables it was migrated from,
+// following the migrations back from the newest (so a variable renamed twice
+// is still found under its original name). Variables already set under their
+// new name always win.
+//
+// If a version_var is declared and set, the environment is taken to already
+// be at that version, and only newer migrations are applied. Otherwise every
+// migration applies.
+pub type SplitFn = fn(&str) -> Result<Vec<String>, String>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Migrations {
+    version_var: Option<&'static str>,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    version: u32,
+    from: &'static str,
+    into: Vec<&'static str>,
+    split: Option<SplitFn>,
+}
+
+impl Migrations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn version_var(mut self, var: &'static str) -> Self {
+        self.version_var = Some(var);
+        self
+    }
+
+    pub fn rename(mut self, version: u32, from: &'static str, to: &'static str) -> Self {
+        self.steps.push(Step {
+            version,
+            from,
+            into: vec![to],
+            split: None,
+        });
+        self
+    }
+
+    // The split function returns one value per variable in `into`, in order.
+    pub fn split(
+        mut self,
+        version: u32,
+        from: &'static str,
+        into: &[&'static str],
+        split: SplitFn,
+    ) -> Self {
+        self.steps.push(Step {
+            version,
+            from,
+            into: into.to_vec(),
+            split: Some(split),
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+// A ConfigSource with the migrations applied on top of another source.
+pub(crate) struct MigratedSource<'a, S: ConfigSource> {
+    migrations: Migrations,
+    source: &'a S,
+}
+
+impl<'a, S: ConfigSource> MigratedSource<'a, S> {
+    pub(crate) fn new(migrations: Migrations, source: &'a S) -> Self {
+        Self { migrations, source }
+    }
+
+    fn current_version(&self) -> Result<u32, ConfigError> {
+        let Some(var) = self.migrations.version_var else {
+            return Ok(0);
+        };
+        match self.source.get(var)? {
+            Some(version) => version
+                .trim()
+                .parse::<u32>()
+                .map_err(|e| InvalidEnvVariableType::with_source(var, "u32", e)),
+            None => Ok(0),
+        }
+    }
+
+    // Only migrations to versions below `below` are considered, so each step
+    // back through the migrations strictly decreases it.
+    fn lookup(&self, key: &str, below: u32, current: u32) -> Result<Option<String>, ConfigError> {
+        if let Some(value) = self.source.get(key)? {
+            return Ok(Some(value));
+        }
+        let mut steps = self
+            .migrations
+            .steps
+            .iter()
+            .filter(|step| step.version < below && step.version > current)
+            .collect::<Vec<_>>();
+        steps.sort_by_key(|step| std::cmp::Reverse(step.version));
+        for step in steps {
+            let Some(index) = step.into.iter().position(|into| *into == key) else {
+                continue;
+            };
+            let Some(old_value) = self.lookup(step.from, step.version, current)? else {
+                continue;
+            };
+            let Some(split) = step.split else {
+                return Ok(Some(old_value));
+            };
+            let version = step.version.to_string();
+            let mut values = split(&old_value)
+                .map_err(|reason| FailedEnvMigration::new(step.from, &version, &reason))?;
+            if values.len() != step.into.len() {
+                return Err(FailedEnvMigration::new(
+                    step.from,
+                    &version,
+                    &format!("expected {} values, got {}", step.into.len(), values.len()),
+                ));
+            }
+            return Ok(Some(values.swap_remove(index)));
+        }
+        Ok(None)
+    }
+}
+
+impl<S: ConfigSource> ConfigSource for MigratedSource<'_, S> {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        if self.migrations.is_empty() {
+            return self.source.get(key);
+        }
+        self.lookup(key, u32::MAX, self.current_version()?)
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.source.keys()
+    }
+
+    fn name(&self) -> &'static str {
+        self.source.name()
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{define_env_config, define_env_variable, load_from_source, EnvConfigEnum};
+
+    use super::Migrations;
+
+    define_env_variable!(MIGRATE_TEST_VERSION);
+    define_env_variable!(MIGRATE_TEST_DB_HOST);
+    define_env_variable!(MIGRATE_TEST_DATABASE_ADDR);
+    define_env_variable!(MIGRATE_TEST_DATABASE_HOST);
+    define_env_variable!(MIGRATE_TEST_DATABASE_PORT);
+
+    fn split_addr(addr: &str) -> Result<Vec<String>, String> {
+        let (host, port) = addr.split_once(':').ok_or("expected HOST:PORT")?;
+        Ok(vec![host.to_string(), port.to_string()])
+    }
+
+    define_env_config!(
+        MigratedConfig {
+            migrations = Migrations::new()
+                .version_var(MIGRATE_TEST_VERSION)
+                .rename(2, MIGRATE_TEST_DB_HOST, MIGRATE_TEST_DATABASE_ADDR)
+                .split(
+                    3,
+                    MIGRATE_TEST_DATABASE_ADDR,
+                    &[MIGRATE_TEST_DATABASE_HOST, MIGRATE_TEST_DATABASE_PORT],
+                    split_addr,
+                ),
+        },
+        DatabaseHost => MIGRATE_TEST_DATABASE_HOST,
+        DatabasePort => MIGRATE_TEST_DATABASE_PORT,
+    );
+
+    fn source(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_migrations() {
+        let env =
+            load_from_source::<MigratedConfig>(&source(&[("MIGRATE_TEST_DB_HOST", "db:5432")]))
+                .unwrap();
+        assert_eq!(env.get(&MigratedConfig::DatabaseHost).unwrap(), "db");
+        assert_eq!(env.get(&MigratedConfig::DatabasePort).unwrap(), "5432");
+
+        // Variables under their new name win.
+        let env = load_from_source::<MigratedConfig>(&source(&[
+            ("MIGRATE_TEST_DATABASE_ADDR", "db:5432"),
+            ("MIGRATE_TEST_DATABASE_PORT", "6432"),
+        ]))
+        .unwrap();
+        assert_eq!(env.get(&MigratedConfig::DatabasePort).unwrap(), "6432");
+
+        // Environments already at version 3 are not migrated.
+        assert!(load_from_source::<MigratedConfig>(&source(&[
+            ("MIGRATE_TEST_VERSION", "3"),
+            ("MIGRATE_TEST_DATABASE_ADDR", "db:5432"),
+        ]))
+        .is_err());
+
+        let error = load_from_source::<MigratedConfig>(&source(&[("MIGRATE_TEST_DB_HOST", "db")]))
+            .unwrap_err();
+        assert!(error.to_string().contains("expected HOST:PORT"));
+    }
+}
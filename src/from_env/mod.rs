@@ -1,6 +1,93 @@
-mod config;
+mod codec;
+mod collision;
+mod compose;
+pub(crate) mod config;
+#[cfg(feature = "config")]
+mod config_crate;
+mod display;
+mod divergence;
+mod docs;
+mod dynamic;
+mod ecs;
 mod errors;
+pub(crate) mod file;
+mod flags;
+mod freeze;
+mod group;
+mod help;
+mod http;
+mod imds;
 mod macros;
+mod many;
+pub(crate) mod metadata;
+mod migrate;
+mod options;
+mod overrides;
+pub(crate) mod pem;
+mod policy;
+#[cfg(all(windows, feature = "windows-registry"))]
+mod registry;
+mod removal;
+mod schema;
+#[cfg(feature = "signed")]
+mod signed;
+pub(crate) mod snapshot;
+mod source;
+mod spec;
+mod stage;
+#[cfg(feature = "strum")]
+mod strum_adapter;
+mod suggest;
+mod typed;
+mod usage;
+pub(crate) mod values;
+mod warnings;
 
-pub use config::{load_env, EnvConfigEnum, EnvVariables};
+pub use codec::ValueCodec;
+#[doc(hidden)]
+pub use collision::KeyOrigin;
+pub use collision::{env_name_collisions, register_env_names, EnvNameCollision};
+pub use compose::{ContainsConfig, RequiresEnv, RequiresEnvSet};
+pub use config::{
+    load_env, load_env_from_iter, load_env_with, load_from_source, validate_env, validate_source,
+    EnvConfigEnum, EnvVariables, ValidateFn,
+};
+pub use display::MaskedDisplay;
+pub use divergence::DivergenceMode;
+pub use docs::generate_markdown_docs;
+pub use dynamic::{load_dynamic_env, load_dynamic_from_source, DynamicEnvVariables, DynamicSchema};
+pub use ecs::{
+    EcsMetadataSource, ECS_AVAILABILITY_ZONE, ECS_CLUSTER, ECS_LAUNCH_TYPE, ECS_TASK_ARN,
+    ECS_TASK_FAMILY, ECS_TASK_REVISION,
+};
 pub use errors::*;
+pub use file::{load_from_file, FileSource, Format};
+pub use flags::FeatureFlags;
+pub use freeze::freeze_env;
+pub use group::KeyGroup;
+pub use help::format_env_help;
+pub use imds::{
+    ImdsSource, IMDS_AVAILABILITY_ZONE, IMDS_INSTANCE_ID, IMDS_INSTANCE_TYPE, IMDS_REGION,
+};
+#[doc(hidden)]
+pub use macros::{__count_distinct_key_ids, __distinct_key_ids, __is_portable_env_var_name};
+pub use many::KeyTuple;
+pub use migrate::{Migrations, SplitFn};
+pub use options::LoadOptions;
+pub use overrides::{EnvOverrideGuard, EnvOverrideScope};
+pub use pem::PemKind;
+pub use policy::{Policy, PolicyCheckFn, PolicyMode, PolicyReport, PolicyRule, PolicyViolation};
+#[cfg(all(windows, feature = "windows-registry"))]
+pub use registry::{load_from_registry, RegistryHive, RegistrySource};
+pub use schema::{format_schema, run_schema_command, verify_schema_manifest, SchemaFormat};
+#[cfg(feature = "signed")]
+pub use signed::load_from_signed_file;
+pub use snapshot::{RedactionPolicy, SnapshotPolicy};
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub use source::set_env_provider;
+pub use source::{ConfigSource, EnvReader, ProcessEnv, StdEnv};
+pub use spec::{Constraint, KeySpec, Pattern};
+pub use stage::{load_stage, load_stage_from, Stage};
+pub use typed::{parse_env_value, FromEnvValue};
+pub use usage::{track_key_usage, UnusedKeysGuard};
+pub use warnings::{clear_warning_reporter, set_warning_reporter, ConfigWarning, WarningReporter};
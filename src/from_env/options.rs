@@ -0,0 +1,163 @@
+use super::{ConfigSource, EnvReader, ProcessEnv, StdEnv};
+use crate::ConfigError;
+
+// Load options.
+// --------------------------------------------------
+
+// Per-call policies for load_env_with, so new ones can be added without
+// changing the plain load_env() signature:
+//
+// let config = load_env_with::<EnvConfig>(
+//     LoadOptions::new()
+//         .trim(true)
+//         .treat_empty_as_missing(true)
+//         .aggregate_errors(true),
+// )?;
+//
+// - trim: strip surrounding whitespace from values (before validation).
+// - treat_empty_as_missing: treat set-but-empty variables (ex. FOO= in a
+//   .env file) as unset, so they fall back to the default or fail as missing.
+// - aggregate_errors: report every failing key at once, instead of stopping at
+//   the first one.
+// - reader: where the raw variables come from (see EnvReader in source.rs),
+//   ex. a hermetic fake environment in tests.
+//
+// All policies are off by default, matching load_env.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions<R: EnvReader = StdEnv> {
+    trim: bool,
+    treat_empty_as_missing: bool,
+    aggregate_errors: bool,
+    reader: R,
+}
+
+impl LoadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<R: EnvReader> LoadOptions<R> {
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    pub fn treat_empty_as_missing(mut self, treat_empty_as_missing: bool) -> Self {
+        self.treat_empty_as_missing = treat_empty_as_missing;
+        self
+    }
+
+    pub fn aggregate_errors(mut self, aggregate_errors: bool) -> Self {
+        self.aggregate_errors = aggregate_errors;
+        self
+    }
+
+    pub fn reader<R2: EnvReader>(self, reader: R2) -> LoadOptions<R2> {
+        LoadOptions {
+            trim: self.trim,
+            treat_empty_as_missing: self.treat_empty_as_missing,
+            aggregate_errors: self.aggregate_errors,
+            reader,
+        }
+    }
+
+    pub(crate) fn aggregates_errors(&self) -> bool {
+        self.aggregate_errors
+    }
+
+    pub(crate) fn source(&self) -> OptionsSource<'_, R> {
+        OptionsSource {
+            options: self,
+            env: ProcessEnv::with_reader(&self.reader),
+        }
+    }
+}
+
+// ProcessEnv over the options' reader, with the value policies applied.
+pub(crate) struct OptionsSource<'a, R: EnvReader> {
+    options: &'a LoadOptions<R>,
+    env: ProcessEnv<&'a R>,
+}
+
+impl<R: EnvReader> ConfigSource for OptionsSource<'_, R> {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        let Some(mut value) = self.env.get(key)? else {
+            return Ok(None);
+        };
+        if self.options.trim {
+            value = value.trim().to_string();
+        }
+        if self.options.treat_empty_as_missing && value.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(value))
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.env.keys()
+    }
+
+    fn name(&self) -> &'static str {
+        self.env.name()
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{define_env_config, define_env_variable, load_env_with, EnvConfigEnum};
+
+    use super::LoadOptions;
+
+    define_env_variable!(OPTIONS_REGION);
+    define_env_variable!(OPTIONS_BUCKET);
+
+    define_env_config!(
+        OptionsConfig,
+        Region => OPTIONS_REGION,
+        Bucket => OPTIONS_BUCKET { default = "assets" },
+    );
+
+    #[test]
+    fn test_trim_and_empty() {
+        let fake_env = HashMap::from([("OPTIONS_REGION", " us-west-2 "), ("OPTIONS_BUCKET", "")]);
+
+        let config = load_env_with::<OptionsConfig>(LoadOptions::new().reader(&fake_env)).unwrap();
+        assert_eq!(config.get(&OptionsConfig::Region).unwrap(), " us-west-2 ");
+        assert_eq!(config.get(&OptionsConfig::Bucket).unwrap(), "");
+
+        let options = LoadOptions::new()
+            .trim(true)
+            .treat_empty_as_missing(true)
+            .reader(&fake_env);
+        let config = load_env_with::<OptionsConfig>(options).unwrap();
+        assert_eq!(config.get(&OptionsConfig::Region).unwrap(), "us-west-2");
+        assert_eq!(config.get(&OptionsConfig::Bucket).unwrap(), "assets");
+    }
+
+    #[test]
+    fn test_aggregate_errors() {
+        define_env_config!(
+            StrictConfig,
+            Region => OPTIONS_REGION { one_of = ["us-east-1"] },
+            Bucket => OPTIONS_BUCKET,
+        );
+        let fake_env = HashMap::from([("OPTIONS_REGION", "us-west-2")]);
+
+        let error = load_env_with::<StrictConfig>(LoadOptions::new().reader(&fake_env))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("OPTIONS_REGION") && !error.contains("OPTIONS_BUCKET"));
+
+        let options = LoadOptions::new().aggregate_errors(true).reader(&fake_env);
+        let error = load_env_with::<StrictConfig>(options)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("OPTIONS_REGION") && error.contains("OPTIONS_BUCKET"));
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{PoisonError, RwLock};
+
+// Environment overrides.
+// --------------------------------------------------
+
+// For integration tests, values can be overridden process-wide without mutating
+// the real process environment (which, unlike this, isn't thread-safe):
+//
+// let _guard = EnvOverrideScope::new()
+//     .set("COGNITO_REGION", "test")
+//     .unset("DYNAMO_REGION")
+//     .apply();
+// let config = load_env::<EnvConfig>()?; // Sees COGNITO_REGION=test.
+//
+// The overrides stay visible to load_env (from any thread) until the guard is
+// dropped. Scopes can be nested, in which case the most recently applied scope
+// takes precedence for the keys it sets.
+#[derive(Debug, Clone, Default)]
+pub struct EnvOverrideScope {
+    values: HashMap<String, Option<String>>,
+}
+
+#[must_use = "overrides are removed as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct EnvOverrideGuard {
+    id: u64,
+}
+
+struct OverrideLayer {
+    id: u64,
+    values: HashMap<String, Option<String>>,
+}
+
+static OVERRIDE_LAYERS: RwLock<Vec<OverrideLayer>> = RwLock::new(Vec::new());
+static NEXT_LAYER_ID: AtomicU64 = AtomicU64::new(0);
+
+impl EnvOverrideScope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.values.insert(key.into(), Some(value.into()));
+        self
+    }
+
+    // Hide a variable, even if it is set in the real process environment.
+    pub fn unset(mut self, key: impl Into<String>) -> Self {
+        self.values.insert(key.into(), None);
+        self
+    }
+
+    pub fn apply(self) -> EnvOverrideGuard {
+        let id = NEXT_LAYER_ID.fetch_add(1, Ordering::Relaxed);
+        OVERRIDE_LAYERS
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(OverrideLayer {
+                id,
+                values: self.values,
+            });
+        EnvOverrideGuard { id }
+    }
+}
+
+impl Drop for EnvOverrideGuard {
+    fn drop(&mut self) {
+        OVERRIDE_LAYERS
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|layer| layer.id != self.id);
+    }
+}
+
+// Returns Some(..) if the key is overridden by any active scope, where the
+// inner None means the key was explicitly unset.
+pub(crate) fn lookup_override(key: &str) -> Option<Option<String>> {
+    OVERRIDE_LAYERS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .rev()
+        .find_map(|layer| layer.values.get(key).cloned())
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::{define_env_config, define_env_variable, load_env, EnvConfigEnum};
+
+    use super::{lookup_override, EnvOverrideScope};
+
+    define_env_variable!(OVERRIDE_TEST_REGION);
+
+    define_env_config!(
+        OverrideConfig,
+        Region => OVERRIDE_TEST_REGION,
+    );
+
+    #[test]
+    fn test_override_visible_to_load_env() {
+        // No test sets OVERRIDE_TEST_REGION in the real environment.
+        assert!(load_env::<OverrideConfig>().is_err());
+
+        let guard = EnvOverrideScope::new()
+            .set("OVERRIDE_TEST_REGION", "test")
+            .apply();
+        let config = load_env::<OverrideConfig>().unwrap();
+        assert_eq!(config.get(&OverrideConfig::Region).unwrap(), "test");
+        assert!(env::var("OVERRIDE_TEST_REGION").is_err());
+
+        drop(guard);
+        assert!(load_env::<OverrideConfig>().is_err());
+    }
+
+    #[test]
+    fn test_override_unset() {
+        let _outer = EnvOverrideScope::new()
+            .set("OVERRIDE_TEST_UNSET", "real")
+            .apply();
+        let _inner = EnvOverrideScope::new().unset("OVERRIDE_TEST_UNSET").apply();
+        assert_eq!(lookup_override("OVERRIDE_TEST_UNSET"), Some(None));
+    }
+
+    #[test]
+    fn test_override_nested_scopes() {
+        let _outer = EnvOverrideScope::new()
+            .set("OVERRIDE_TEST_NESTED_A", "outer")
+            .set("OVERRIDE_TEST_NESTED_B", "outer")
+            .apply();
+        {
+            let _inner = EnvOverrideScope::new()
+                .set("OVERRIDE_TEST_NESTED_A", "inner")
+                .apply();
+            assert_eq!(
+                lookup_override("OVERRIDE_TEST_NESTED_A"),
+                Some(Some(String::from("inner")))
+            );
+            assert_eq!(
+                lookup_override("OVERRIDE_TEST_NESTED_B"),
+                Some(Some(String::from("outer")))
+            );
+        }
+        assert_eq!(
+            lookup_override("OVERRIDE_TEST_NESTED_A"),
+            Some(Some(String::from("outer")))
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use std::fmt;
+
+use super::warnings::{report_warning, ConfigWarning};
+use super::{EnvConfigEnum, EnvVariables, PolicyViolated, Stage};
+use crate::ConfigError;
+
+// Config policies.
+// --------------------------------------------------
+
+// Beyond what each key accepts, organisations often have rules about which
+// values are acceptable where. These can be registered as a policy, evaluated
+// once the config is loaded:
+//
+// let policy = Policy::new()
+//     .rule(PolicyRule::deny(EnvConfig::LogLevel, &["debug", "trace"]).in_stages(&[Stage::Prod]))
+//     .rule(PolicyRule::require(EnvConfig::ApiUrl, "must use https", |url| {
+//         url.starts_with("https://")
+//     }))
+//     .rule(PolicyRule::check("replicas within quota", check_quota));
+//
+// let config = load_env::<EnvConfig>()?;
+// policy.enforce(&config, &load_stage()?)?;
+//
+// Every applicable rule is evaluated, and all violations are reported
+// together. Rules apply in every stage unless limited with in_stages, and key
+// rules are skipped when the key is unset. Denied values are compared
+// case-insensitively.
+//
+// By default, any violation fails enforce. With mode(PolicyMode::Warn), enforce
+// instead returns the report with the violations (ex. while rolling out a new
+// rule), and reports each of them as a warning (see warnings.rs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PolicyMode {
+    #[default]
+    Fail,
+    Warn,
+}
+
+pub type PolicyCheckFn<T> = fn(&EnvVariables<T>) -> Result<(), String>;
+
+#[derive(Debug, Clone)]
+enum RuleKind<T: EnvConfigEnum> {
+    Deny(T, Vec<&'static str>),
+    Require(T, &'static str, fn(&str) -> bool),
+    Check(&'static str, PolicyCheckFn<T>),
+}
+
+#[derive(Debug, Clone)]
+pub struct PolicyRule<T: EnvConfigEnum> {
+    kind: RuleKind<T>,
+    stages: Vec<Stage>,
+}
+
+impl<T: EnvConfigEnum> PolicyRule<T> {
+    // The key must not be set to any of the values.
+    pub fn deny(key: T, values: &[&'static str]) -> Self {
+        Self::from_kind(RuleKind::Deny(key, values.to_vec()))
+    }
+
+    // The key's value must pass the check, described by rule (ex. "must use
+    // https") when violated.
+    pub fn require(key: T, rule: &'static str, check: fn(&str) -> bool) -> Self {
+        Self::from_kind(RuleKind::Require(key, rule, check))
+    }
+
+    // Arbitrary check over the loaded config, returning the reason it fails.
+    pub fn check(name: &'static str, check: PolicyCheckFn<T>) -> Self {
+        Self::from_kind(RuleKind::Check(name, check))
+    }
+
+    pub fn in_stages(mut self, stages: &[Stage]) -> Self {
+        self.stages = stages.to_vec();
+        self
+    }
+
+    fn from_kind(kind: RuleKind<T>) -> Self {
+        Self {
+            kind,
+            stages: Vec::new(),
+        }
+    }
+
+    fn evaluate(&self, env: &EnvVariables<T>, stage: &Stage) -> Option<PolicyViolation> {
+        if !self.stages.is_empty() && !self.stages.contains(stage) {
+            return None;
+        }
+        let violation = |key: Option<&T>, message: String| PolicyViolation {
+            key: key.map(T::as_str),
+            message,
+        };
+        // Values are read directly, so that policies don't count as reads for
+        // UnusedKeysGuard (see usage.rs).
+        match &self.kind {
+            RuleKind::Deny(key, values) => {
+                let value = env.0.get(key.as_str())?;
+                let denied = values.iter().find(|v| v.eq_ignore_ascii_case(value))?;
+                Some(violation(
+                    Some(key),
+                    format!("{} must not be '{denied}' in {stage}", key.as_str()),
+                ))
+            }
+            RuleKind::Require(key, rule, check) => {
+                let value = env.0.get(key.as_str())?;
+                (!check(value)).then(|| violation(Some(key), format!("{} {rule}", key.as_str())))
+            }
+            RuleKind::Check(name, check) => check(env)
+                .err()
+                .map(|reason| violation(None, format!("{name}: {reason}"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyViolation {
+    pub key: Option<&'static str>,
+    pub message: String,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyReport {
+    violations: Vec<PolicyViolation>,
+}
+
+impl PolicyReport {
+    pub fn violations(&self) -> &[PolicyViolation] {
+        &self.violations
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Policy<T: EnvConfigEnum> {
+    rules: Vec<PolicyRule<T>>,
+    mode: PolicyMode,
+}
+
+impl<T: EnvConfigEnum> Default for Policy<T> {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            mode: PolicyMode::default(),
+        }
+    }
+}
+
+impl<T: EnvConfigEnum> Policy<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rule(mut self, rule: PolicyRule<T>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn mode(mut self, mode: PolicyMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn evaluate(&self, env: &EnvVariables<T>, stage: &Stage) -> PolicyReport {
+        PolicyReport {
+            violations: self
+                .rules
+                .iter()
+                .filter_map(|rule| rule.evaluate(env, stage))
+                .collect(),
+        }
+    }
+
+    pub fn enforce(
+        &self,
+        env: &EnvVariables<T>,
+        stage: &Stage,
+    ) -> Result<PolicyReport, ConfigError> {
+        let report = self.evaluate(env, stage);
+        if report.is_clean() {
+            return Ok(report);
+        }
+        match self.mode {
+            PolicyMode::Fail => {
+                let messages = report
+                    .violations
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                Err(PolicyViolated::new(
+                    &report.violations.len().to_string(),
+                    &messages.join("; "),
+                ))
+            }
+            PolicyMode::Warn => {
+                for violation in &report.violations {
+                    report_warning(ConfigWarning::PolicyViolation(violation.clone()));
+                }
+                Ok(report)
+            }
+        }
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{define_env_config, define_env_variable, load_from_source, EnvConfigEnum, Stage};
+
+    use crate::from_env::warnings::{reported_warnings, ConfigWarning};
+
+    use super::{Policy, PolicyMode, PolicyRule};
+
+    define_env_variable!(POLICY_LOG_LEVEL);
+    define_env_variable!(POLICY_API_URL);
+    define_env_variable!(POLICY_CALLBACK_URL);
+
+    define_env_config!(
+        PolicyConfig,
+        LogLevel => POLICY_LOG_LEVEL,
+        ApiUrl => POLICY_API_URL,
+        CallbackUrl => POLICY_CALLBACK_URL { required_in = [Stage::Prod] },
+    );
+
+    fn https(url: &str) -> bool {
+        url.starts_with("https://")
+    }
+
+    #[test]
+    fn test_policy() {
+        let source = HashMap::from([
+            (String::from("POLICY_LOG_LEVEL"), String::from("DEBUG")),
+            (String::from("POLICY_API_URL"), String::from("http://api")),
+            (String::from("STAGE"), String::from("dev")),
+        ]);
+        let env = load_from_source::<PolicyConfig>(&source).unwrap();
+        let policy = Policy::new()
+            .rule(PolicyRule::deny(PolicyConfig::LogLevel, &["debug"]).in_stages(&[Stage::Prod]))
+            .rule(PolicyRule::require(
+                PolicyConfig::ApiUrl,
+                "must use https",
+                https,
+            ))
+            .rule(PolicyRule::require(
+                PolicyConfig::CallbackUrl,
+                "must use https",
+                https,
+            ));
+
+        let report = policy.evaluate(&env, &Stage::Dev);
+        assert_eq!(report.violations().len(), 1);
+        assert_eq!(report.violations()[0].key, Some("POLICY_API_URL"));
+
+        let error = policy.enforce(&env, &Stage::Prod).unwrap_err().to_string();
+        assert!(error.contains("POLICY_LOG_LEVEL must not be 'debug' in prod"));
+        assert!(error.contains("POLICY_API_URL must use https"));
+
+        let report = policy
+            .mode(PolicyMode::Warn)
+            .enforce(&env, &Stage::Prod)
+            .unwrap();
+        assert_eq!(report.violations().len(), 2);
+        let reported = reported_warnings();
+        for violation in report.violations() {
+            assert!(reported.contains(&ConfigWarning::PolicyViolation(violation.clone())));
+        }
+    }
+}
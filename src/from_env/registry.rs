@@ -0,0 +1,70 @@
+use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+use winreg::RegKey;
+
+use crate::ConfigError;
+
+use super::{
+    load_from_source, ConfigSource, EnvConfigEnum, EnvVariables, FailedToOpenRegistryKey,
+    FailedToReadRegistryValue,
+};
+
+// Windows registry source.
+// --------------------------------------------------
+
+// Windows services often can't rely on an orchestrator to set the process
+// environment, so values can instead be read from a registry key, where each
+// variable is a string value under that key:
+//
+// let config = load_from_registry::<EnvConfig>(
+//     RegistryHive::LocalMachine,
+//     r"SOFTWARE\Fractic\MyService",
+// )?;
+//
+// Only available on Windows, with the "windows-registry" feature enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryHive {
+    LocalMachine,
+    CurrentUser,
+}
+
+pub struct RegistrySource {
+    key: RegKey,
+    path: String,
+}
+
+impl RegistrySource {
+    pub fn open(hive: RegistryHive, path: &str) -> Result<Self, ConfigError> {
+        let root = RegKey::predef(match hive {
+            RegistryHive::LocalMachine => HKEY_LOCAL_MACHINE,
+            RegistryHive::CurrentUser => HKEY_CURRENT_USER,
+        });
+        let key = root
+            .open_subkey(path)
+            .map_err(|e| FailedToOpenRegistryKey::with_source(path, e))?;
+        Ok(Self {
+            key,
+            path: path.to_string(),
+        })
+    }
+}
+
+impl ConfigSource for RegistrySource {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        match self.key.get_value::<String, _>(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(FailedToReadRegistryValue::with_source(&self.path, key, e)),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "windows-registry"
+    }
+}
+
+pub fn load_from_registry<T: EnvConfigEnum>(
+    hive: RegistryHive,
+    path: &str,
+) -> Result<EnvVariables<T>, ConfigError> {
+    load_from_source(&RegistrySource::open(hive, path)?)
+}
@@ -0,0 +1,128 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::warnings::{report_warning, ConfigWarning};
+use super::{InvalidEnvConstraint, RemovedEnvVariableSet};
+use crate::ConfigError;
+
+// Removed variables.
+// --------------------------------------------------
+
+// Variables being phased out can be given a removal date, so environments
+// don't keep accumulating settings nothing reads anymore:
+//
+// LegacyApiUrl => LEGACY_API_URL { removed_after = "2025-06-01" },
+//
+// Such keys are optional. Until the date, setting one reports a warning asking
+// operators to delete it (see warnings.rs). From the day after, its presence fails the load. The
+// date is compared against the current UTC date.
+pub(crate) fn check_removal(var: &str, removed_after: &str) -> Result<(), ConfigError> {
+    let date = parse_date(removed_after).ok_or_else(|| {
+        InvalidEnvConstraint::new(var, &format!("removed_after = \"{removed_after}\""))
+    })?;
+    if today() > date {
+        return Err(RemovedEnvVariableSet::new(var, removed_after));
+    }
+    report_warning(ConfigWarning::DeprecatedVariableSet {
+        var: var.to_string(),
+        removed_after: removed_after.to_string(),
+    });
+    Ok(())
+}
+
+// Parses a YYYY-MM-DD date into a comparable (year, month, day).
+fn parse_date(date: &str) -> Option<(i64, u32, u32)> {
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts
+        .next()?
+        .parse()
+        .ok()
+        .filter(|m| (1..=12).contains(m))?;
+    let day = parts
+        .next()?
+        .parse()
+        .ok()
+        .filter(|d| (1..=31).contains(d))?;
+    Some((year, month, day))
+}
+
+fn today() -> (i64, u32, u32) {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86_400);
+    civil_from_days(days as i64)
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's
+// algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{define_env_config, define_env_variable, load_from_source, EnvConfigEnum};
+
+    use crate::from_env::warnings::{reported_warnings, ConfigWarning};
+
+    use super::civil_from_days;
+
+    define_env_variable!(REMOVAL_TEST_REGION);
+    define_env_variable!(REMOVAL_TEST_OLD_URL);
+    define_env_variable!(REMOVAL_TEST_NEXT_URL);
+
+    define_env_config!(
+        RemovalConfig,
+        Region => REMOVAL_TEST_REGION,
+        OldUrl => REMOVAL_TEST_OLD_URL { removed_after = "2000-01-01" },
+        NextUrl => REMOVAL_TEST_NEXT_URL { removed_after = "2999-12-31" },
+    );
+
+    fn source(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_removed_after() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_875), (2024, 6, 1));
+
+        let env = load_from_source::<RemovalConfig>(&source(&[
+            ("REMOVAL_TEST_REGION", "us-west-2"),
+            ("REMOVAL_TEST_NEXT_URL", "https://example.com"),
+        ]))
+        .unwrap();
+        assert!(env.get_optional(&RemovalConfig::OldUrl).is_none());
+        assert!(env.get_optional(&RemovalConfig::NextUrl).is_some());
+        assert!(
+            reported_warnings().contains(&ConfigWarning::DeprecatedVariableSet {
+                var: String::from("REMOVAL_TEST_NEXT_URL"),
+                removed_after: String::from("2999-12-31"),
+            })
+        );
+
+        let error = load_from_source::<RemovalConfig>(&source(&[
+            ("REMOVAL_TEST_REGION", "us-west-2"),
+            ("REMOVAL_TEST_OLD_URL", "https://example.com"),
+        ]))
+        .unwrap_err();
+        assert!(error.to_string().contains("REMOVAL_TEST_OLD_URL"));
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::ConfigError;
+
+use super::{
+    generate_markdown_docs, EnvConfigEnum, FailedToReadConfigFile, InvalidSchemaCommand, KeySpec,
+    SchemaManifestOutdated,
+};
+
+// Schema export.
+// --------------------------------------------------
+
+// Renders the variables of a config for scripts and docs pipelines:
+//
+// let schema = format_schema::<EnvConfig>(SchemaFormat::Json);
+//
+// - Json: an array with one object per variable (name, description, default,
+//   required, required_in, sensitive, constraints).
+// - Markdown: a table ready to paste into a README (see docs.rs).
+// - Dotenv: a commented .env template, with defaults filled in.
+//
+// Binaries can also expose it as a subcommand, taking the arguments after the
+// binary name:
+//
+// if args.first().map(String::as_str) == Some("print-schema") {
+//     print!("{}", run_schema_command::<EnvConfig>(&args)?);
+//     return Ok(());
+// }
+//
+// $ my-service print-schema --format markdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchemaFormat {
+    #[default]
+    Json,
+    Markdown,
+    Dotenv,
+}
+
+impl SchemaFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(SchemaFormat::Json),
+            "markdown" | "md" => Some(SchemaFormat::Markdown),
+            "dotenv" | "env" => Some(SchemaFormat::Dotenv),
+            _ => None,
+        }
+    }
+}
+
+pub fn format_schema<T: EnvConfigEnum>(format: SchemaFormat) -> String {
+    let specs = T::value_list()
+        .iter()
+        .map(EnvConfigEnum::spec)
+        .collect::<Vec<_>>();
+    match format {
+        SchemaFormat::Json => {
+            let entries = specs.iter().map(schema_entry).collect::<Vec<_>>();
+            let json = serde_json::to_string_pretty(&Value::Array(entries))
+                .expect("a JSON value always serializes");
+            format!("{json}\n")
+        }
+        SchemaFormat::Markdown => generate_markdown_docs::<T>(),
+        SchemaFormat::Dotenv => specs
+            .iter()
+            .map(|spec| {
+                let mut entry = String::new();
+                if let Some(description) = spec.help_text() {
+                    entry.push_str(&format!("# {description}\n"));
+                }
+                entry.push_str(&format!("# Required: {}.\n", spec.requirement()));
+                let default = spec.default_value().filter(|_| !spec.is_sensitive());
+                entry.push_str(&format!("{}={}\n", spec.name(), default.unwrap_or("")));
+                entry
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+// Handles "print-schema [--format json|markdown|dotenv]", returning the schema
+// to print, and "verify-manifest <path>" (see verify_schema_manifest).
+pub fn run_schema_command<T: EnvConfigEnum>(
+    args: &[impl AsRef<str>],
+) -> Result<String, ConfigError> {
+    let mut args = args.iter().map(AsRef::as_ref);
+    match args.next() {
+        Some("print-schema") => {}
+        Some("verify-manifest") => {
+            let path = args
+                .next()
+                .ok_or_else(|| InvalidSchemaCommand::new("missing manifest path"))?;
+            if let Some(arg) = args.next() {
+                return Err(InvalidSchemaCommand::new(&format!(
+                    "unknown argument '{arg}'"
+                )));
+            }
+            verify_schema_manifest::<T>(path)?;
+            return Ok(format!("Schema manifest '{path}' is up to date.\n"));
+        }
+        Some(other) => {
+            return Err(InvalidSchemaCommand::new(&format!(
+                "unknown command '{other}'"
+            )))
+        }
+        None => return Err(InvalidSchemaCommand::new("missing command")),
+    }
+    let mut format = SchemaFormat::default();
+    while let Some(arg) = args.next() {
+        let name = match arg.strip_prefix("--format=") {
+            Some(name) => name,
+            None if arg == "--format" => args
+                .next()
+                .ok_or_else(|| InvalidSchemaCommand::new("missing value for --format"))?,
+            None => {
+                return Err(InvalidSchemaCommand::new(&format!(
+                    "unknown argument '{arg}'"
+                )))
+            }
+        };
+        format = SchemaFormat::from_name(name)
+            .ok_or_else(|| InvalidSchemaCommand::new(&format!("unknown format '{name}'")))?;
+    }
+    Ok(format_schema::<T>(format))
+}
+
+// Deployment manifests (ex. Helm values, task definitions) list the variables
+// a service expects, and easily fall behind when a variable is added. Checking
+// in the JSON schema next to them, ex. as env-manifest.json:
+//
+// $ my-service print-schema --format json > env-manifest.json
+//
+// lets a pre-commit hook or CI step fail whenever the config changed without
+// the manifest being regenerated (and so, reviewed alongside the deployment
+// manifests):
+//
+// $ my-service verify-manifest env-manifest.json
+//
+// Variables are compared by name, so reordering them doesn't count as a
+// change. The error lists the added, removed and changed variables.
+pub fn verify_schema_manifest<T: EnvConfigEnum>(path: &str) -> Result<(), ConfigError> {
+    let manifest =
+        std::fs::read_to_string(path).map_err(|e| FailedToReadConfigFile::with_source(path, e))?;
+    let changes = manifest_changes::<T>(&manifest);
+    if changes.is_empty() {
+        return Ok(());
+    }
+    Err(SchemaManifestOutdated::new(path, &changes.join("; ")))
+}
+
+fn manifest_changes<T: EnvConfigEnum>(manifest: &str) -> Vec<String> {
+    let manifest = serde_json::from_str::<Value>(manifest).ok();
+    let Some(manifest) = manifest
+        .as_ref()
+        .and_then(Value::as_array)
+        .map(|e| by_name(e))
+    else {
+        return vec![String::from("not a JSON schema")];
+    };
+    let current = T::value_list()
+        .iter()
+        .map(|key| schema_entry(&key.spec()))
+        .collect::<Vec<_>>();
+    let current = by_name(&current);
+
+    let names = |filter: &dyn Fn(&&str) -> bool| {
+        let mut names = current
+            .keys()
+            .chain(manifest.keys().filter(|name| !current.contains_key(*name)))
+            .copied()
+            .filter(filter)
+            .collect::<Vec<_>>();
+        names.sort();
+        names.join(", ")
+    };
+    let changed = |name: &&str| match (manifest.get(name), current.get(name)) {
+        (Some(before), Some(after)) => before != after,
+        _ => false,
+    };
+    [
+        ("added", names(&|name| !manifest.contains_key(name))),
+        ("removed", names(&|name| !current.contains_key(name))),
+        ("changed", names(&changed)),
+    ]
+    .into_iter()
+    .filter(|(_, names)| !names.is_empty())
+    .map(|(change, names)| format!("{change}: {names}"))
+    .collect()
+}
+
+fn by_name(entries: &[Value]) -> HashMap<&str, &Value> {
+    entries
+        .iter()
+        .filter_map(|entry| Some((entry.get("name")?.as_str()?, entry)))
+        .collect()
+}
+
+fn schema_entry(spec: &KeySpec) -> Value {
+    let mut entry = Map::new();
+    entry.insert("name".to_string(), Value::from(spec.name()));
+    entry.insert("description".to_string(), Value::from(spec.help_text()));
+    entry.insert("default".to_string(), Value::from(spec.default_value()));
+    entry.insert(
+        "required".to_string(),
+        Value::from(!spec.has_default() && !spec.is_conditional()),
+    );
+    entry.insert(
+        "required_in".to_string(),
+        Value::from(spec.required_stages().map(|stages| {
+            stages
+                .iter()
+                .map(|stage| Value::from(stage.to_string()))
+                .collect::<Vec<_>>()
+        })),
+    );
+    entry.insert("sensitive".to_string(), Value::from(spec.is_sensitive()));
+    entry.insert(
+        "constraints".to_string(),
+        Value::Array(
+            spec.constraints()
+                .iter()
+                .map(|constraint| Value::from(constraint.to_string()))
+                .collect(),
+        ),
+    );
+    Value::Object(entry)
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum};
+
+    use super::{format_schema, run_schema_command, verify_schema_manifest, SchemaFormat};
+
+    define_env_variable!(SCHEMA_REGION);
+    define_env_variable!(SCHEMA_LOG_LEVEL);
+    define_env_variable!(SCHEMA_API_TOKEN);
+
+    define_env_config!(
+        SchemaConfig,
+        Region => SCHEMA_REGION { description = "AWS region.", one_of = ["us-west-2"] },
+        LogLevel => SCHEMA_LOG_LEVEL { default = "info" },
+        ApiToken => SCHEMA_API_TOKEN { sensitive = true },
+    );
+
+    #[test]
+    fn test_schema_json() {
+        let schema: Value =
+            serde_json::from_str(&format_schema::<SchemaConfig>(SchemaFormat::Json)).unwrap();
+        let entries = schema.as_array().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0].get("name").unwrap().as_str(),
+            Some("SCHEMA_REGION")
+        );
+        assert_eq!(entries[1].get("required").unwrap().as_bool(), Some(false));
+        assert_eq!(entries[2].get("sensitive").unwrap().as_bool(), Some(true));
+    }
+
+    #[test]
+    fn test_schema_markdown_and_dotenv() {
+        let markdown = format_schema::<SchemaConfig>(SchemaFormat::Markdown);
+        assert!(markdown.contains("| `SCHEMA_LOG_LEVEL` | string | `info` |  | no | no |\n"));
+
+        assert_eq!(
+            format_schema::<SchemaConfig>(SchemaFormat::Dotenv),
+            "# AWS region.\n# Required: yes.\nSCHEMA_REGION=\n\n\
+             # Required: no.\nSCHEMA_LOG_LEVEL=info\n\n\
+             # Required: yes.\nSCHEMA_API_TOKEN=\n"
+        );
+    }
+
+    #[test]
+    fn test_run_schema_command() {
+        assert_eq!(
+            run_schema_command::<SchemaConfig>(&["print-schema", "--format", "dotenv"]).unwrap(),
+            format_schema::<SchemaConfig>(SchemaFormat::Dotenv)
+        );
+        assert!(run_schema_command::<SchemaConfig>(&["print-schema", "--format=md"]).is_ok());
+        assert!(run_schema_command::<SchemaConfig>(&["print-schema", "--format", "xml"]).is_err());
+        assert!(run_schema_command::<SchemaConfig>(&["completions"]).is_err());
+    }
+
+    #[test]
+    fn test_verify_manifest() {
+        let path = std::env::temp_dir().join("fractic_env_config_manifest.json");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, format_schema::<SchemaConfig>(SchemaFormat::Json)).unwrap();
+        assert!(run_schema_command::<SchemaConfig>(&["verify-manifest", path]).is_ok());
+        assert!(run_schema_command::<SchemaConfig>(&["verify-manifest"]).is_err());
+
+        define_env_variable!(SCHEMA_BUCKET);
+        define_env_config!(
+            ChangedConfig,
+            Region => SCHEMA_REGION { description = "AWS region." },
+            LogLevel => SCHEMA_LOG_LEVEL { default = "info" },
+            Bucket => SCHEMA_BUCKET,
+        );
+        let error = verify_schema_manifest::<ChangedConfig>(path)
+            .unwrap_err()
+            .to_string();
+        assert!(error
+            .contains("added: SCHEMA_BUCKET; removed: SCHEMA_API_TOKEN; changed: SCHEMA_REGION"));
+    }
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+
+use crate::ConfigError;
+
+use super::file::parse_root;
+use super::{
+    load_from_source, EnvConfigEnum, EnvVariables, FailedToReadConfigFile, FileSource, Format,
+    InvalidConfigSignature,
+};
+
+// Signed config files.
+// --------------------------------------------------
+
+// Where config integrity must be attested, a config file can be shipped with a
+// detached ed25519 signature, and is only accepted if it verifies against a
+// public key pinned in the binary:
+//
+// const CONFIG_KEY: [u8; 32] = *include_bytes!("../keys/config.pub");
+//
+// let config = load_from_signed_file::<EnvConfig>("config.toml", Format::Toml, &CONFIG_KEY)?;
+//
+// The signature is read from the same path with ".sig" appended (ex.
+// config.toml.sig), as the base64 encoding of the 64-byte signature over the
+// exact bytes of the file. It is checked before the file is parsed, so no
+// value of a tampered or unsigned file is ever read.
+impl FileSource {
+    pub fn open_signed(
+        path: impl AsRef<Path>,
+        format: Format,
+        public_key: &[u8; 32],
+    ) -> Result<Self, ConfigError> {
+        let path_str = path.as_ref().display().to_string();
+        let signature_path = format!("{path_str}.sig");
+        let contents = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| FailedToReadConfigFile::with_source(&path_str, e))?;
+        let signature = std::fs::read_to_string(&signature_path)
+            .map_err(|e| FailedToReadConfigFile::with_source(&signature_path, e))?;
+        Self::parse_signed_named(&contents, &signature, format, public_key, &path_str)
+    }
+
+    // From the file contents and its base64-encoded signature.
+    pub fn parse_signed(
+        contents: &str,
+        signature: &str,
+        format: Format,
+        public_key: &[u8; 32],
+    ) -> Result<Self, ConfigError> {
+        Self::parse_signed_named(contents, signature, format, public_key, "<inline>")
+    }
+
+    fn parse_signed_named(
+        contents: &str,
+        signature: &str,
+        format: Format,
+        public_key: &[u8; 32],
+        name: &str,
+    ) -> Result<Self, ConfigError> {
+        verify(contents.as_bytes(), signature, public_key)
+            .map_err(|reason| InvalidConfigSignature::new(name, reason))?;
+        Ok(Self::from_value(parse_root(contents, format, name)?))
+    }
+}
+
+fn verify(contents: &[u8], signature: &str, public_key: &[u8; 32]) -> Result<(), &'static str> {
+    let key = VerifyingKey::from_bytes(public_key).map_err(|_| "invalid public key")?;
+    let signature = STANDARD
+        .decode(signature.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .ok_or("signature is not a base64-encoded ed25519 signature")?;
+    key.verify_strict(contents, &Signature::from_bytes(&signature))
+        .map_err(|_| "signature does not match")
+}
+
+pub fn load_from_signed_file<T: EnvConfigEnum>(
+    path: impl AsRef<Path>,
+    format: Format,
+    public_key: &[u8; 32],
+) -> Result<EnvVariables<T>, ConfigError> {
+    load_from_source(&FileSource::open_signed(path, format, public_key)?)
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum};
+
+    use super::{load_from_signed_file, FileSource, Format};
+
+    define_env_variable!(SIGNED_TEST_REGION);
+
+    define_env_config!(
+        SignedConfig,
+        Region => SIGNED_TEST_REGION,
+    );
+
+    const CONTENTS: &str = r#"{ "SIGNED_TEST_REGION": "us-west-2" }"#;
+
+    #[test]
+    fn test_load_from_signed_file() {
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = signing_key.verifying_key().to_bytes();
+        let signature = STANDARD.encode(signing_key.sign(CONTENTS.as_bytes()).to_bytes());
+
+        let path = std::env::temp_dir().join("fractic_env_config_signed.json");
+        std::fs::write(&path, CONTENTS).unwrap();
+        std::fs::write(path.with_extension("json.sig"), format!("{signature}\n")).unwrap();
+        let config =
+            load_from_signed_file::<SignedConfig>(&path, Format::Json, &public_key).unwrap();
+        assert_eq!(config.get(&SignedConfig::Region).unwrap(), "us-west-2");
+
+        let tampered = CONTENTS.replace("us-west-2", "us-east-1");
+        assert!(
+            FileSource::parse_signed(&tampered, &signature, Format::Json, &public_key).is_err()
+        );
+        let other_key = SigningKey::from_bytes(&[8; 32]).verifying_key().to_bytes();
+        assert!(FileSource::parse_signed(CONTENTS, &signature, Format::Json, &other_key).is_err());
+        assert!(
+            FileSource::parse_signed(CONTENTS, "not-base64", Format::Json, &public_key).is_err()
+        );
+    }
+}
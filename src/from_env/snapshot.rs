@@ -0,0 +1,216 @@
+use std::path::Path;
+
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::ConfigError;
+
+use super::display::mask_value;
+use super::{EnvConfigEnum, EnvVariables, FailedToWriteConfigSnapshot};
+
+// Config snapshots.
+// --------------------------------------------------
+
+// When a service crashes it's useful to know exactly which configuration it was
+// running with. A crash handler can dump the loaded config to disk before the
+// process exits:
+//
+// config.write_snapshot("/tmp/config-snapshot.json", SnapshotPolicy::HashValues)?;
+// secrets.write_snapshot("/tmp/secrets-snapshot.json")?;
+//
+// Environment values are either included as-is or replaced by their SHA-256
+// hash (enough to compare against a known value without leaking it). Keys
+// flagged { sensitive = true } are masked under either policy, as are secret
+// values, for which only the key names are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotPolicy {
+    IncludeValues,
+    HashValues,
+}
+
+pub(crate) static MASKED_VALUE: &str = "<redacted>";
+
+pub(crate) fn hash_value(value: &str) -> String {
+    format!("sha256:{:x}", Sha256::digest(value.as_bytes()))
+}
+
+pub(crate) fn write_snapshot_file(
+    path: &Path,
+    snapshot: Map<String, Value>,
+) -> Result<(), ConfigError> {
+    let path_str = path.display().to_string();
+    let contents = serde_json::to_string_pretty(&Value::Object(snapshot))
+        .map_err(|e| FailedToWriteConfigSnapshot::with_source(&path_str, e))?;
+    std::fs::write(path, contents)
+        .map_err(|e| FailedToWriteConfigSnapshot::with_source(&path_str, e))
+}
+
+impl<T: EnvConfigEnum> EnvVariables<T> {
+    pub fn write_snapshot(
+        &self,
+        path: impl AsRef<Path>,
+        policy: SnapshotPolicy,
+    ) -> Result<(), ConfigError> {
+        let snapshot = T::value_list()
+            .iter()
+            .filter_map(|key| {
+                let value = self.0.get(key.as_str())?;
+                let value = match policy {
+                    _ if key.spec().is_sensitive() => MASKED_VALUE.to_string(),
+                    SnapshotPolicy::IncludeValues => value.to_string(),
+                    SnapshotPolicy::HashValues => hash_value(value),
+                };
+                Some((key.as_str().to_string(), Value::String(value)))
+            })
+            .collect();
+        write_snapshot_file(path.as_ref(), snapshot)
+    }
+}
+
+// For error reports and telemetry, the effective config can be exported as a
+// JSON object instead, where only the keys flagged { sensitive = true } are
+// redacted:
+//
+// report.attach("config", config.to_json(RedactionPolicy::Hash));
+//
+// Sensitive values are replaced by "<redacted>" (Mask), by their SHA-256 hash
+// (Hash), or by their first and last 2 characters as in masked_display
+// (Partial). Other values are included as-is, and unset optional keys are null.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedactionPolicy {
+    #[default]
+    Mask,
+    Hash,
+    Partial,
+}
+
+impl<T: EnvConfigEnum> EnvVariables<T> {
+    pub fn to_json(&self, policy: RedactionPolicy) -> Value {
+        let values = T::value_list()
+            .iter()
+            .map(|key| {
+                let value = match self.0.get(key.as_str()) {
+                    None => Value::Null,
+                    Some(value) if !key.spec().is_sensitive() => Value::from(&**value),
+                    Some(value) => Value::from(match policy {
+                        RedactionPolicy::Mask => MASKED_VALUE.to_string(),
+                        RedactionPolicy::Hash => hash_value(value),
+                        RedactionPolicy::Partial => mask_value(value),
+                    }),
+                };
+                (key.as_str().to_string(), value)
+            })
+            .collect();
+        Value::Object(values)
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum, EnvVariables};
+
+    use super::{hash_value, RedactionPolicy, SnapshotPolicy};
+
+    define_env_variable!(SNAPSHOT_REGION);
+    define_env_variable!(SNAPSHOT_TOKEN);
+
+    define_env_config!(
+        SnapshotConfig,
+        Region => SNAPSHOT_REGION,
+        Token => SNAPSHOT_TOKEN,
+    );
+    define_env_config!(
+        RedactedConfig,
+        Region => SNAPSHOT_REGION,
+        Token => SNAPSHOT_TOKEN { sensitive = true },
+    );
+
+    fn snapshot_env() -> EnvVariables<SnapshotConfig> {
+        let map: HashMap<&'static str, String> = [
+            (SNAPSHOT_REGION, String::from("us-west-2")),
+            (SNAPSHOT_TOKEN, String::from("abc123")),
+        ]
+        .into();
+        EnvVariables::from(map)
+    }
+
+    // Unique per test and per test process, so concurrent runs don't read each
+    // other's snapshots.
+    fn snapshot_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "fractic_env_config_snapshot_{name}_{}.json",
+            std::process::id()
+        ))
+    }
+
+    fn read_snapshot(path: &Path) -> HashMap<String, String> {
+        let contents = std::fs::read_to_string(path).unwrap();
+        serde_json::from_str(&contents).unwrap()
+    }
+
+    #[test]
+    fn test_write_snapshot_include_values() {
+        let path = snapshot_path("include");
+        snapshot_env()
+            .write_snapshot(&path, SnapshotPolicy::IncludeValues)
+            .unwrap();
+
+        let snapshot = read_snapshot(&path);
+        assert_eq!(snapshot.get("SNAPSHOT_REGION").unwrap(), "us-west-2");
+        assert_eq!(snapshot.get("SNAPSHOT_TOKEN").unwrap(), "abc123");
+
+        let path = snapshot_path("include_sensitive");
+        snapshot_env()
+            .clone_into::<RedactedConfig>()
+            .unwrap()
+            .write_snapshot(&path, SnapshotPolicy::IncludeValues)
+            .unwrap();
+
+        let snapshot = read_snapshot(&path);
+        assert_eq!(snapshot.get("SNAPSHOT_REGION").unwrap(), "us-west-2");
+        assert_eq!(snapshot.get("SNAPSHOT_TOKEN").unwrap(), "<redacted>");
+    }
+
+    #[test]
+    fn test_write_snapshot_hash_values() {
+        let path = snapshot_path("hash");
+        snapshot_env()
+            .write_snapshot(&path, SnapshotPolicy::HashValues)
+            .unwrap();
+
+        let snapshot = read_snapshot(&path);
+        assert_eq!(
+            snapshot.get("SNAPSHOT_TOKEN").unwrap(),
+            &hash_value("abc123")
+        );
+        assert!(!snapshot.values().any(|v| v == "abc123"));
+    }
+
+    #[test]
+    fn test_write_snapshot_invalid_path() {
+        let result = snapshot_env().write_snapshot(
+            "/nonexistent-dir/snapshot.json",
+            SnapshotPolicy::IncludeValues,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let env = snapshot_env().clone_into::<RedactedConfig>().unwrap();
+        let json = env.to_json(RedactionPolicy::Mask);
+        assert_eq!(json["SNAPSHOT_REGION"].as_str(), Some("us-west-2"));
+        assert_eq!(json["SNAPSHOT_TOKEN"].as_str(), Some("<redacted>"));
+        let json = env.to_json(RedactionPolicy::Hash);
+        assert_eq!(
+            json["SNAPSHOT_TOKEN"].as_str(),
+            Some(hash_value("abc123").as_str())
+        );
+    }
+}
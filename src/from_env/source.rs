@@ -0,0 +1,285 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::hash::Hash;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+use std::sync::{PoisonError, RwLock};
+
+use crate::ConfigError;
+
+use super::overrides::lookup_override;
+use super::{ConflictingEnvVariableFile, FailedToReadEnvVariableFile, InvalidUtf8EnvVariable};
+
+// Config sources.
+// --------------------------------------------------
+
+// A ConfigSource provides the raw string value for a given variable name, or
+// None if the source doesn't have it. load_env reads from the process
+// environment (ProcessEnv), but any other source can be plugged into the same
+// typed validation by using load_from_source:
+//
+// let config: EnvVariables<EnvConfig> = load_from_source(&my_source)?;
+//
+// Returning an error (rather than None) signals that the source itself failed,
+// in which case loading is aborted.
+pub trait ConfigSource {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError>;
+
+    // The names the source has values for, if it can list them. Only used for
+    // hints when a key is missing (see suggest.rs).
+    fn keys(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // Recorded as the source of the loaded values (see metadata.rs).
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+}
+
+// Reads from the process environment.
+//
+// Follows the common container convention of VAR_FILE variables: if DB_PASSWORD
+// is not set but DB_PASSWORD_FILE is, the value is read from the file at that
+// path instead (ex. a Docker secret mounted under /run/secrets). Trailing
+// newlines are stripped. Setting both is ambiguous, and returns an error.
+//
+// Any active EnvOverrideScope takes precedence over the real environment.
+//
+// On wasm32-unknown-unknown, where the process environment is always empty,
+// the host can provide the variables instead (see set_env_provider).
+//
+// Values that aren't valid UTF-8 return an InvalidUtf8EnvVariable error (rather
+// than being reported as missing), unless the source is created with
+// ProcessEnv::lossy(), in which case invalid sequences are replaced by U+FFFD.
+//
+// The raw variables are read through an EnvReader, which defaults to the real
+// environment (StdEnv). Tests can swap in a hermetic fake instead, keeping the
+// rest of the behaviour above, without touching (or locking) the process-wide
+// environment:
+//
+// let fake_env = HashMap::from([("COGNITO_REGION", "us-west-2")]);
+// let config = load_env_with::<EnvConfig>(LoadOptions::new().reader(fake_env))?;
+pub trait EnvReader {
+    fn var_os(&self, key: &str) -> Option<OsString>;
+
+    fn var_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl<R: EnvReader + ?Sized> EnvReader for &R {
+    fn var_os(&self, key: &str) -> Option<OsString> {
+        (**self).var_os(key)
+    }
+
+    fn var_names(&self) -> Vec<String> {
+        (**self).var_names()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdEnv;
+
+impl EnvReader for StdEnv {
+    fn var_os(&self, key: &str) -> Option<OsString> {
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        if let Some(provider) = *ENV_PROVIDER.read().unwrap_or_else(PoisonError::into_inner) {
+            return provider(key).map(OsString::from);
+        }
+        std::env::var_os(key)
+    }
+
+    fn var_names(&self) -> Vec<String> {
+        std::env::vars_os()
+            .filter_map(|(key, _)| key.into_string().ok())
+            .collect()
+    }
+}
+
+impl<K: Borrow<str> + Hash + Eq, V: AsRef<OsStr>> EnvReader for HashMap<K, V> {
+    fn var_os(&self, key: &str) -> Option<OsString> {
+        self.get(key).map(|value| value.as_ref().to_os_string())
+    }
+
+    fn var_names(&self) -> Vec<String> {
+        self.keys().map(|key| key.borrow().to_string()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessEnv<R: EnvReader = StdEnv> {
+    reader: R,
+    lossy_utf8: bool,
+}
+
+impl ProcessEnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lossy() -> Self {
+        Self::new().with_lossy_utf8()
+    }
+}
+
+impl<R: EnvReader> ProcessEnv<R> {
+    pub fn with_reader(reader: R) -> Self {
+        Self {
+            reader,
+            lossy_utf8: false,
+        }
+    }
+
+    pub fn with_lossy_utf8(mut self) -> Self {
+        self.lossy_utf8 = true;
+        self
+    }
+
+    fn read_var(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        if let Some(value) = lookup_override(key) {
+            return Ok(value);
+        }
+        match self.reader.var_os(key) {
+            None => Ok(None),
+            Some(value) if self.lossy_utf8 => Ok(Some(value.to_string_lossy().into_owned())),
+            Some(value) => value
+                .into_string()
+                .map(Some)
+                .map_err(|_| InvalidUtf8EnvVariable::new(key)),
+        }
+    }
+}
+
+impl<R: EnvReader> ConfigSource for ProcessEnv<R> {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        let file_key = format!("{key}_FILE");
+        match (self.read_var(key)?, self.read_var(&file_key)?) {
+            (Some(_), Some(_)) => Err(ConflictingEnvVariableFile::new(key, &file_key)),
+            (Some(value), None) => Ok(Some(value)),
+            (None, Some(path)) => read_value_file(key, &path).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.reader.var_names()
+    }
+
+    fn name(&self) -> &'static str {
+        "env"
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+static ENV_PROVIDER: RwLock<Option<fn(&str) -> Option<String>>> = RwLock::new(None);
+
+// Plug the host's environment into ProcessEnv (and so load_env), ex. for
+// workers runtimes that pass bindings through JS:
+//
+// set_env_provider(|key| worker_env().get(key));
+//
+// Replaces any previously set provider.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub fn set_env_provider(provider: fn(&str) -> Option<String>) {
+    *ENV_PROVIDER.write().unwrap_or_else(PoisonError::into_inner) = Some(provider);
+}
+
+// Already-collected key-value pairs (see load_env_from_iter), read as-is.
+impl ConfigSource for HashMap<String, String> {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        Ok(HashMap::get(self, key).cloned())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        HashMap::keys(self).cloned().collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "map"
+    }
+}
+
+fn read_value_file(key: &str, path: &str) -> Result<String, ConfigError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| FailedToReadEnvVariableFile::with_source(key, path, e))?;
+    Ok(contents.trim_end_matches(['\r', '\n']).to_string())
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::env;
+
+    use super::{ConfigSource, ProcessEnv};
+
+    #[test]
+    fn test_process_env_direct_value() {
+        let source = ProcessEnv::with_reader(HashMap::from([("SOURCE_TEST_DIRECT", "value")]));
+        assert_eq!(
+            source.get("SOURCE_TEST_DIRECT").unwrap(),
+            Some(String::from("value"))
+        );
+    }
+
+    #[test]
+    fn test_process_env_missing() {
+        let source = ProcessEnv::with_reader(HashMap::<&str, &str>::new());
+        assert_eq!(source.get("SOURCE_TEST_MISSING").unwrap(), None);
+    }
+
+    #[test]
+    fn test_process_env_file_convention() {
+        let path = env::temp_dir().join("fractic_env_config_source_test_password");
+        std::fs::write(&path, "hunter2\n").unwrap();
+        let source = ProcessEnv::with_reader(HashMap::from([(
+            "SOURCE_TEST_PASSWORD_FILE",
+            path.as_os_str(),
+        )]));
+
+        assert_eq!(
+            source.get("SOURCE_TEST_PASSWORD").unwrap(),
+            Some(String::from("hunter2"))
+        );
+    }
+
+    #[test]
+    fn test_process_env_file_convention_unreadable() {
+        let source = ProcessEnv::with_reader(HashMap::from([(
+            "SOURCE_TEST_UNREADABLE_FILE",
+            "/nonexistent/secret",
+        )]));
+        assert!(source.get("SOURCE_TEST_UNREADABLE").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_env_invalid_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let fake_env = HashMap::from([("SOURCE_TEST_NON_UTF8", OsStr::from_bytes(b"abc\xff"))]);
+        assert!(ProcessEnv::with_reader(&fake_env)
+            .get("SOURCE_TEST_NON_UTF8")
+            .is_err());
+        assert_eq!(
+            ProcessEnv::with_reader(&fake_env)
+                .with_lossy_utf8()
+                .get("SOURCE_TEST_NON_UTF8")
+                .unwrap(),
+            Some(String::from("abc\u{FFFD}"))
+        );
+    }
+
+    #[test]
+    fn test_process_env_file_convention_conflict() {
+        let source = ProcessEnv::with_reader(HashMap::from([
+            ("SOURCE_TEST_CONFLICT", "value"),
+            ("SOURCE_TEST_CONFLICT_FILE", "/run/secrets/conflict"),
+        ]));
+        assert!(source.get("SOURCE_TEST_CONFLICT").is_err());
+    }
+}
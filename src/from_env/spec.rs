@@ -0,0 +1,513 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use regex::Regex;
+
+use crate::ConfigError;
+use crate::STAGE;
+
+use super::codec::Codec;
+use super::pem::parse_pem;
+use super::removal::check_removal;
+use super::stage::stage_from_source;
+use super::{
+    ConfigSource, EnvVariableFailedConstraint, InvalidEnvConstraint, PemKind, Stage, ValueCodec,
+};
+
+// Key specifications.
+// --------------------------------------------------
+
+// Each key of an EnvConfigEnum can optionally declare constraints on its value,
+// inline in the define_env_config! macro:
+//
+// define_env_config!(
+//     EnvConfig,
+//     CognitoRegion => COGNITO_REGION { one_of = ["us-west-2", "us-east-1"] },
+//     BucketName => BUCKET_NAME { matches = r"^[a-z0-9-]+$" },
+//     Port => PORT { range = 1..=65535 },
+//     TlsCert => TLS_CERT { pem = PemKind::Certificate },
+//     DynamoRegion => DYNAMO_REGION,
+// );
+//
+// Each option expands to the builder method of the same name on KeySpec, and
+// the resulting constraints are enforced by load_env, so that typos fail fast
+// at startup instead of somewhere deep in the code.
+//
+// Names are usually static, but can also be built at runtime for dynamic
+// schemas (see dynamic.rs).
+//
+// Keys can also be required only in some stages (see stage.rs), ex. an error
+// reporting DSN that is mandatory in prod but not locally:
+//
+// SentryDsn => SENTRY_DSN { required_in = [Stage::Prod] },
+//
+// Outside of those stages the key is optional, and can be read with
+// EnvVariables::get_optional. If the stage can't be determined (STAGE is not
+// set), the key is required.
+//
+// Keys holding credentials can be flagged sensitive, so their values are fully
+// masked in EnvVariables::masked_display (see display.rs):
+//
+// DatabaseUrl => DATABASE_URL { sensitive = true },
+//
+// Keys can also document themselves, and fall back to a default when missing.
+// Both show up in the generated --help section (see help.rs):
+//
+// LogLevel => LOG_LEVEL { description = "Minimum log level.", default = "info" },
+//
+// Defaults are validated against the constraints like any other value. They
+// can also be computed when the key is loaded, for defaults that depend on the
+// host:
+//
+// WorkerThreads => WORKER_THREADS { default_fn = || num_cpus::get().to_string() },
+//
+// Raw values can be transformed by custom codecs before validation (see
+// codec.rs).
+//
+// Keys being phased out can be given a removal date, after which setting them
+// fails the load (see removal.rs):
+//
+// LegacyApiUrl => LEGACY_API_URL { removed_after = "2025-06-01" },
+#[derive(Debug, Clone)]
+pub struct KeySpec {
+    name: Cow<'static, str>,
+    constraints: Vec<Constraint>,
+    required_in: Option<Vec<Stage>>,
+    sensitive: bool,
+    description: Option<&'static str>,
+    default: Option<DefaultValue>,
+    codecs: Vec<Codec>,
+    removed_after: Option<&'static str>,
+}
+
+#[derive(Clone)]
+enum DefaultValue {
+    Static(&'static str),
+    Computed(Arc<dyn Fn() -> String + Send + Sync>),
+}
+
+impl fmt::Debug for DefaultValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefaultValue::Static(value) => f.debug_tuple("Static").field(value).finish(),
+            DefaultValue::Computed(_) => f.write_str("Computed(..)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Constraint {
+    Matches(Pattern),
+    OneOf(Vec<&'static str>),
+    Range(Bound<i64>, Bound<i64>),
+    Pem(PemKind),
+}
+
+impl KeySpec {
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            constraints: Vec::new(),
+            required_in: None,
+            sensitive: false,
+            description: None,
+            default: None,
+            codecs: Vec::new(),
+            removed_after: None,
+        }
+    }
+
+    // The pattern is compiled here (once per process, since specs are rebuilt
+    // on every load). An invalid pattern fails every load of the key, whether
+    // or not it is set.
+    pub fn matches(mut self, pattern: &'static str) -> Self {
+        self.constraints
+            .push(Constraint::Matches(Pattern::new(pattern)));
+        self
+    }
+
+    pub fn one_of<const N: usize>(mut self, values: [&'static str; N]) -> Self {
+        self.constraints.push(Constraint::OneOf(values.to_vec()));
+        self
+    }
+
+    // Integer range, which also requires the value to parse as an integer.
+    // Usually paired with EnvVariables::get_parsed::<T>() to read the value.
+    pub fn range(mut self, range: impl RangeBounds<i64>) -> Self {
+        self.constraints.push(Constraint::Range(
+            range.start_bound().cloned(),
+            range.end_bound().cloned(),
+        ));
+        self
+    }
+
+    // PEM structure (see pem.rs). Failures report the malformed part instead
+    // of only naming the constraint.
+    pub fn pem(mut self, kind: PemKind) -> Self {
+        self.constraints.push(Constraint::Pem(kind));
+        self
+    }
+
+    pub fn required_in<const N: usize>(mut self, stages: [Stage; N]) -> Self {
+        self.required_in = Some(stages.to_vec());
+        self
+    }
+
+    pub fn sensitive(mut self, sensitive: bool) -> Self {
+        self.sensitive = sensitive;
+        self
+    }
+
+    pub fn is_sensitive(&self) -> bool {
+        self.sensitive
+    }
+
+    pub fn description(mut self, description: &'static str) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    pub fn default(mut self, value: &'static str) -> Self {
+        self.default = Some(DefaultValue::Static(value));
+        self
+    }
+
+    pub fn default_fn(mut self, default: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        self.default = Some(DefaultValue::Computed(Arc::new(default)));
+        self
+    }
+
+    pub fn codec(mut self, codec: impl ValueCodec + 'static) -> Self {
+        self.codecs.push(Codec(Arc::new(codec)));
+        self
+    }
+
+    // YYYY-MM-DD.
+    pub fn removed_after(mut self, date: &'static str) -> Self {
+        self.removed_after = Some(date);
+        self
+    }
+
+    pub fn removal_date(&self) -> Option<&'static str> {
+        self.removed_after
+    }
+
+    pub fn help_text(&self) -> Option<&'static str> {
+        self.description
+    }
+
+    // Only static defaults, since computed ones depend on the host.
+    pub fn default_value(&self) -> Option<&'static str> {
+        match self.default {
+            Some(DefaultValue::Static(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn has_default(&self) -> bool {
+        self.default.is_some()
+    }
+
+    // For generated docs, which show computed defaults as a placeholder.
+    pub(crate) fn default_text(&self) -> Option<&'static str> {
+        match self.default {
+            Some(DefaultValue::Static(value)) => Some(value),
+            Some(DefaultValue::Computed(_)) => Some("<computed>"),
+            None => None,
+        }
+    }
+
+    pub fn required_stages(&self) -> Option<&[Stage]> {
+        self.required_in.as_deref()
+    }
+
+    // Human-readable summary for generated docs: "yes", "no" (has a default),
+    // or the stages it is required in (ex. "in prod").
+    pub(crate) fn requirement(&self) -> String {
+        match (&self.default, &self.required_in) {
+            _ if self.removed_after.is_some() => "no".to_string(),
+            (Some(_), _) => "no".to_string(),
+            (None, Some(stages)) => format!(
+                "in {}",
+                stages
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            (None, None) => "yes".to_string(),
+        }
+    }
+
+    // Keys only required in some stages, or being removed.
+    pub fn is_conditional(&self) -> bool {
+        self.required_in.is_some() || self.removed_after.is_some()
+    }
+
+    // Whether the key is required in the given stage (None if unknown).
+    pub fn is_required_in(&self, stage: Option<&Stage>) -> bool {
+        match (&self.required_in, stage) {
+            (Some(stages), Some(stage)) => stages.contains(stage),
+            _ => true,
+        }
+    }
+
+    // Whether the key may be missing from the source, reading the current
+    // stage from the same source.
+    pub(crate) fn may_be_missing(&self, source: &impl ConfigSource) -> bool {
+        self.removed_after.is_some()
+            || (self.required_in.is_some()
+                && !self.is_required_in(stage_from_source(STAGE, source).ok().as_ref()))
+    }
+
+    // The key's value in the source (decoded by its codecs), or its default if
+    // missing.
+    pub(crate) fn read(&self, source: &impl ConfigSource) -> Result<Option<String>, ConfigError> {
+        self.check_constraints()?;
+        let Some(mut value) = source.get(&self.name)? else {
+            return Ok(self.default.as_ref().map(|default| match default {
+                DefaultValue::Static(value) => value.to_string(),
+                DefaultValue::Computed(compute) => compute(),
+            }));
+        };
+        if let Some(removed_after) = self.removed_after {
+            check_removal(&self.name, removed_after)?;
+        }
+        for codec in &self.codecs {
+            value = codec.0.decode(&self.name, &value)?;
+        }
+        Ok(Some(value))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    // Fails on the first constraint that was declared invalid.
+    fn check_constraints(&self) -> Result<(), ConfigError> {
+        for constraint in &self.constraints {
+            if let Constraint::Matches(pattern) = constraint {
+                pattern.regex(&self.name)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Check the value against all constraints, returning an error naming the
+    // first constraint that failed.
+    pub(crate) fn validate(&self, value: &str) -> Result<(), ConfigError> {
+        for constraint in &self.constraints {
+            if !constraint.is_satisfied_by(&self.name, value)? {
+                return Err(EnvVariableFailedConstraint::new(
+                    &self.name,
+                    &constraint.to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Constraint {
+    fn is_satisfied_by(&self, name: &str, value: &str) -> Result<bool, ConfigError> {
+        match self {
+            Constraint::Matches(pattern) => Ok(pattern.regex(name)?.is_match(value)),
+            Constraint::OneOf(values) => Ok(values.contains(&value)),
+            Constraint::Range(start, end) => Ok(value
+                .parse::<i64>()
+                .is_ok_and(|n| (*start, *end).contains(&n))),
+            Constraint::Pem(kind) => parse_pem(name, value, *kind).map(|_| true),
+        }
+    }
+}
+
+// Pattern of a matches constraint, compiled when the constraint is declared.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: &'static str,
+    regex: Result<Regex, regex::Error>,
+}
+
+impl Pattern {
+    fn new(source: &'static str) -> Self {
+        static COMPILED: OnceLock<Mutex<HashMap<&'static str, Result<Regex, regex::Error>>>> =
+            OnceLock::new();
+        let regex = COMPILED
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(source)
+            .or_insert_with(|| Regex::new(source))
+            .clone();
+        Self { source, regex }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.source
+    }
+
+    fn regex(&self, name: &str) -> Result<&Regex, ConfigError> {
+        self.regex.as_ref().map_err(|e| {
+            InvalidEnvConstraint::with_source(
+                name,
+                &Constraint::Matches(self.clone()).to_string(),
+                e.clone(),
+            )
+        })
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Pattern {}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.source)
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constraint::Matches(pattern) => write!(f, "matches = r\"{pattern}\""),
+            Constraint::OneOf(values) => write!(f, "one_of = {values:?}"),
+            Constraint::Range(start, end) => {
+                write!(f, "range = ")?;
+                match start {
+                    Bound::Included(n) | Bound::Excluded(n) => write!(f, "{n}")?,
+                    Bound::Unbounded => {}
+                }
+                match end {
+                    Bound::Included(n) => write!(f, "..={n}"),
+                    Bound::Excluded(n) => write!(f, "..{n}"),
+                    Bound::Unbounded => write!(f, ".."),
+                }
+            }
+            Constraint::Pem(kind) => write!(f, "pem = PemKind::{kind:?}"),
+        }
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::{FileSource, Format, InvalidEnvConstraint, Stage};
+
+    use super::{Constraint, KeySpec};
+
+    #[test]
+    fn test_no_constraints() {
+        let spec = KeySpec::new("TEST_VAR");
+        assert!(spec.validate("anything").is_ok());
+    }
+
+    #[test]
+    fn test_matches_constraint() {
+        let spec = KeySpec::new("TEST_VAR").matches(r"^[a-z0-9-]+$");
+        assert!(spec.validate("my-bucket-1").is_ok());
+        assert!(spec.validate("My_Bucket").is_err());
+    }
+
+    #[test]
+    fn test_matches_invalid_pattern() {
+        let spec = KeySpec::new("TEST_VAR").matches(r"[a-z");
+        assert!(spec.validate("abc").is_err());
+
+        // Rejected even when the key isn't set.
+        let source = FileSource::parse("{}", Format::Json).unwrap();
+        let error = spec.read(&source).unwrap_err();
+        assert_eq!(error.code(), Some(InvalidEnvConstraint::CODE));
+    }
+
+    #[test]
+    fn test_one_of_constraint() {
+        let spec = KeySpec::new("TEST_VAR").one_of(["us-west-2", "us-east-1"]);
+        assert!(spec.validate("us-east-1").is_ok());
+        assert!(spec.validate("us-west-1").is_err());
+    }
+
+    #[test]
+    fn test_range_constraint() {
+        let spec = KeySpec::new("TEST_VAR").range(1..=65535);
+        assert!(spec.validate("1").is_ok());
+        assert!(spec.validate("65535").is_ok());
+        assert!(spec.validate("0").is_err());
+        assert!(spec.validate("65536").is_err());
+        assert!(spec.validate("not-a-number").is_err());
+
+        let spec = KeySpec::new("TEST_VAR").range(..10);
+        assert!(spec.validate("-5").is_ok());
+        assert!(spec.validate("10").is_err());
+    }
+
+    #[test]
+    fn test_multiple_constraints() {
+        let spec = KeySpec::new("TEST_VAR")
+            .matches(r"^us-")
+            .one_of(["us-west-2", "eu-west-1"]);
+        assert_eq!(spec.constraints().len(), 2);
+        assert!(spec.validate("us-west-2").is_ok());
+        assert!(spec.validate("eu-west-1").is_err());
+    }
+
+    #[test]
+    fn test_constraint_display() {
+        assert_eq!(
+            KeySpec::new("TEST_VAR").matches(r"^[a-z]+$").constraints()[0].to_string(),
+            r#"matches = r"^[a-z]+$""#
+        );
+        assert_eq!(
+            Constraint::OneOf(vec!["a", "b"]).to_string(),
+            r#"one_of = ["a", "b"]"#
+        );
+        assert_eq!(
+            KeySpec::new("TEST_VAR").range(1..=65535).constraints()[0].to_string(),
+            "range = 1..=65535"
+        );
+        assert_eq!(
+            KeySpec::new("TEST_VAR").range(5..).constraints()[0].to_string(),
+            "range = 5.."
+        );
+    }
+
+    #[test]
+    fn test_required_in() {
+        let spec = KeySpec::new("TEST_VAR");
+        assert!(!spec.is_conditional());
+        assert!(spec.is_required_in(Some(&Stage::Dev)));
+
+        let spec = KeySpec::new("TEST_VAR").required_in([Stage::Prod, Stage::Staging]);
+        assert!(spec.is_conditional());
+        assert!(spec.is_required_in(Some(&Stage::Prod)));
+        assert!(!spec.is_required_in(Some(&Stage::Dev)));
+        assert!(spec.is_required_in(None));
+    }
+
+    #[test]
+    fn test_default() {
+        let source = FileSource::parse(r#"{ "SET_VAR": "debug" }"#, Format::Json).unwrap();
+        let spec = KeySpec::new("SET_VAR").default("info");
+        assert_eq!(spec.read(&source).unwrap().as_deref(), Some("debug"));
+        let spec = KeySpec::new("UNSET_VAR").default("info");
+        assert_eq!(spec.read(&source).unwrap().as_deref(), Some("info"));
+        let spec = KeySpec::new("UNSET_VAR").default_fn(|| 2.to_string());
+        assert_eq!(spec.read(&source).unwrap().as_deref(), Some("2"));
+        assert_eq!(spec.default_value(), None);
+        assert!(spec.has_default());
+        assert_eq!(KeySpec::new("UNSET_VAR").read(&source).unwrap(), None);
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ConfigError;
+use crate::STAGE;
+
+use super::{ConfigSource, MissingEnvVariableError, ProcessEnv};
+
+// Deployment stage.
+// --------------------------------------------------
+
+// Most services need to know which stage they're running in, usually to relax
+// defaults or validation outside of prod:
+//
+// let stage = load_stage()?;
+// let log_level = stage.select("info", "debug");
+// if stage.is_prod() { ... }
+//
+// load_stage() reads the STAGE variable, and load_stage_from() any other.
+// Common spellings are recognized case-insensitively (ex. "production",
+// "prod"), anything else is kept as Stage::Custom.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Dev,
+    Staging,
+    Prod,
+    Custom(String),
+}
+
+impl Stage {
+    pub fn is_prod(&self) -> bool {
+        *self == Stage::Prod
+    }
+
+    pub fn is_dev(&self) -> bool {
+        *self == Stage::Dev
+    }
+
+    // Pick a value depending on whether this is prod.
+    pub fn select<T>(&self, prod: T, non_prod: T) -> T {
+        if self.is_prod() {
+            prod
+        } else {
+            non_prod
+        }
+    }
+}
+
+impl FromStr for Stage {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim().to_ascii_lowercase().as_str() {
+            "dev" | "development" | "local" => Stage::Dev,
+            "staging" | "stage" => Stage::Staging,
+            "prod" | "production" => Stage::Prod,
+            _ => Stage::Custom(s.trim().to_string()),
+        })
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Dev => write!(f, "dev"),
+            Stage::Staging => write!(f, "staging"),
+            Stage::Prod => write!(f, "prod"),
+            Stage::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+pub fn load_stage() -> Result<Stage, ConfigError> {
+    load_stage_from(STAGE)
+}
+
+pub fn load_stage_from(var: &str) -> Result<Stage, ConfigError> {
+    stage_from_source(var, &ProcessEnv::new())
+}
+
+pub(crate) fn stage_from_source(
+    var: &str,
+    source: &impl ConfigSource,
+) -> Result<Stage, ConfigError> {
+    let value = source
+        .get(var)?
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| MissingEnvVariableError::new(var, ""))?;
+    Ok(value.parse().unwrap_or_else(|never| match never {}))
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::{FileSource, Format};
+
+    use super::{stage_from_source, Stage};
+
+    #[test]
+    fn test_parse_stage() {
+        assert_eq!("prod".parse::<Stage>().unwrap(), Stage::Prod);
+        assert_eq!("Production".parse::<Stage>().unwrap(), Stage::Prod);
+        assert_eq!("dev".parse::<Stage>().unwrap(), Stage::Dev);
+        assert_eq!("STAGING".parse::<Stage>().unwrap(), Stage::Staging);
+        assert_eq!(
+            "qa-2".parse::<Stage>().unwrap(),
+            Stage::Custom("qa-2".to_string())
+        );
+        assert_eq!(Stage::Custom("qa-2".to_string()).to_string(), "qa-2");
+    }
+
+    #[test]
+    fn test_stage_helpers() {
+        assert!(Stage::Prod.is_prod());
+        assert!(!Stage::Staging.is_prod());
+        assert_eq!(Stage::Prod.select("info", "debug"), "info");
+        assert_eq!(Stage::Dev.select("info", "debug"), "debug");
+    }
+
+    #[test]
+    fn test_stage_from_source() {
+        let source =
+            FileSource::parse(r#"{ "APP_ENV": "prod", "EMPTY": "" }"#, Format::Json).unwrap();
+        assert_eq!(stage_from_source("APP_ENV", &source).unwrap(), Stage::Prod);
+        assert!(stage_from_source("EMPTY", &source).is_err());
+        assert!(stage_from_source("STAGE", &source).is_err());
+    }
+}
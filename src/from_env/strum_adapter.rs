@@ -0,0 +1,84 @@
+use strum::{VariantArray, VariantNames};
+
+use super::EnvConfigEnum;
+
+// strum adapter.
+// --------------------------------------------------
+
+// Enums already deriving strum's VariantArray, VariantNames and IntoStaticStr
+// satisfy EnvConfigEnum without going through define_env_config!:
+//
+// #[derive(Debug, Clone, PartialEq, Eq, Hash, VariantArray, VariantNames, IntoStaticStr)]
+// #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+// enum EnvConfig {
+//     CognitoRegion,
+//     DynamoRegion,
+// }
+//
+// let config = load_env::<EnvConfig>()?;
+//
+// The derives provide static data (the variant slice, and the names as a slice
+// and per variant), so the keys are used as-is. Such enums use the default KeySpec (no
+// constraints) and no validate hook. Available with the "strum" feature.
+impl<T> EnvConfigEnum for T
+where
+    T: VariantArray
+        + VariantNames
+        + std::fmt::Debug
+        + Eq
+        + core::hash::Hash
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    for<'a> &'a T: Into<&'static str>,
+{
+    const KEY_IDS: &'static [&'static str] = <T as VariantNames>::VARIANTS;
+
+    fn as_str(&self) -> &'static str {
+        self.into()
+    }
+
+    fn value_list() -> &'static [Self] {
+        <T as VariantArray>::VARIANTS
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use strum::{IntoStaticStr, VariantArray, VariantNames};
+
+    use crate::{load_from_source, EnvConfigEnum, FileSource, Format};
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, VariantArray, VariantNames, IntoStaticStr)]
+    #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+    enum StrumConfig {
+        StrumRegion,
+        StrumPort,
+    }
+
+    #[test]
+    fn test_strum_enum_as_config() {
+        assert_eq!(StrumConfig::StrumRegion.as_str(), "STRUM_REGION");
+        assert_eq!(StrumConfig::COUNT, 2);
+        assert_eq!(
+            StrumConfig::value_list(),
+            [StrumConfig::StrumRegion, StrumConfig::StrumPort]
+        );
+
+        let source = FileSource::parse(
+            r#"{ "STRUM_REGION": "us-west-2", "STRUM_PORT": "8080" }"#,
+            Format::Json,
+        )
+        .unwrap();
+        let config = load_from_source::<StrumConfig>(&source).unwrap();
+        assert_eq!(config.get(&StrumConfig::StrumRegion).unwrap(), "us-west-2");
+        assert_eq!(
+            config.get_parsed::<u16>(&StrumConfig::StrumPort).unwrap(),
+            8080
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use super::ConfigSource;
+
+// Did-you-mean hints.
+// --------------------------------------------------
+
+// When a required variable is missing, the source is searched for a similarly
+// named one, so that a typo is reported as such:
+//
+// Missing environment variable 'COGNITO_REGION'. Did you mean 'COGNITO_REGOIN'?
+//
+// Names within an edit distance of 2 (ignoring case) are considered, and the
+// closest is suggested. Only sources that can list their keys (see
+// ConfigSource::keys) give hints.
+const MAX_DISTANCE: usize = 2;
+
+pub(crate) fn missing_hint(missing: &str, source: &impl ConfigSource) -> String {
+    let missing_upper = missing.to_ascii_uppercase();
+    let mut keys = source.keys();
+    keys.sort();
+    keys.into_iter()
+        .filter(|key| key != missing)
+        .map(|key| {
+            (
+                edit_distance(&missing_upper, &key.to_ascii_uppercase()),
+                key,
+            )
+        })
+        .filter(|(distance, _)| *distance <= MAX_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, key)| format!(" Did you mean '{key}'?"))
+        .unwrap_or_default()
+}
+
+// Levenshtein distance, over bytes (variable names are ASCII).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{define_env_config, define_env_variable, load_from_source, EnvConfigEnum};
+
+    use super::edit_distance;
+
+    define_env_variable!(SUGGEST_REGION);
+
+    define_env_config!(
+        SuggestConfig,
+        Region => SUGGEST_REGION,
+    );
+
+    #[test]
+    fn test_did_you_mean() {
+        assert_eq!(edit_distance("REGION", "REGOIN"), 2);
+        assert_eq!(edit_distance("", "ABC"), 3);
+
+        let source = HashMap::from([(String::from("SUGGEST_REGOIN"), String::from("x"))]);
+        let error = load_from_source::<SuggestConfig>(&source).unwrap_err();
+        assert!(error.to_string().contains("Did you mean 'SUGGEST_REGOIN'?"));
+
+        let source = HashMap::from([(String::from("UNRELATED"), String::from("x"))]);
+        let error = load_from_source::<SuggestConfig>(&source).unwrap_err();
+        assert!(!error.to_string().contains("Did you mean"));
+    }
+}
@@ -0,0 +1,130 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use super::{InvalidEnvVariableType, MissingEnvVariableError};
+use crate::ConfigError;
+
+// Typed values.
+// --------------------------------------------------
+
+// A plain struct of typed fields can be generated for a config, so application
+// code can destructure and move values instead of calling get with error
+// handling at every use site:
+//
+// define_env_values!(
+//     #[derive(Debug, Clone)]
+//     EnvValues from EnvConfig,
+//     cognito_region: String => CognitoRegion,
+//     port: u16 => Port,
+//     sentry_dsn: Option<String> => SentryDsn,
+// );
+//
+// let EnvValues { cognito_region, port, .. } = EnvValues::try_from(env)?;
+//
+// The conversion is a TryFrom (from both EnvVariables<EnvConfig> and a
+// reference to it), since values may still fail to parse as the field type.
+// Option fields are None when the variable is unset (ex. keys only required in
+// some stages).
+//
+// Field types implement FromEnvValue, which is provided for String, bool,
+// char, the numeric primitives, PathBuf and the std::net address types. Other
+// FromStr types can opt in with parse_env_value:
+//
+// impl FromEnvValue for LogLevel {
+//     fn from_env_value(var: &str, value: Option<&str>) -> Result<Self, ConfigError> {
+//         parse_env_value(var, value)
+//     }
+// }
+pub trait FromEnvValue: Sized {
+    fn from_env_value(var: &str, value: Option<&str>) -> Result<Self, ConfigError>;
+}
+
+pub fn parse_env_value<V: FromStr>(var: &str, value: Option<&str>) -> Result<V, ConfigError>
+where
+    V::Err: std::fmt::Debug,
+{
+    value
+        .ok_or_else(|| MissingEnvVariableError::new(var, ""))?
+        .parse::<V>()
+        .map_err(|e| InvalidEnvVariableType::with_debug(var, std::any::type_name::<V>(), &e))
+}
+
+impl<V: FromEnvValue> FromEnvValue for Option<V> {
+    fn from_env_value(var: &str, value: Option<&str>) -> Result<Self, ConfigError> {
+        value
+            .map(|value| V::from_env_value(var, Some(value)))
+            .transpose()
+    }
+}
+
+macro_rules! impl_from_env_value {
+    ($($V:ty),* $(,)?) => {
+        $(
+            impl FromEnvValue for $V {
+                fn from_env_value(var: &str, value: Option<&str>) -> Result<Self, ConfigError> {
+                    parse_env_value(var, value)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_env_value!(
+    String, bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64,
+    PathBuf, IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr,
+);
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        define_env_config, define_env_values, define_env_variable, EnvConfigEnum, EnvVariables,
+        Stage,
+    };
+
+    define_env_variable!(TYPED_TEST_REGION);
+    define_env_variable!(TYPED_TEST_PORT);
+    define_env_variable!(TYPED_TEST_DSN);
+
+    define_env_config!(
+        TypedConfig,
+        Region => TYPED_TEST_REGION,
+        Port => TYPED_TEST_PORT,
+        Dsn => TYPED_TEST_DSN { required_in = [Stage::Prod] },
+    );
+
+    define_env_values!(
+        #[derive(Debug, PartialEq)]
+        TypedValues from TypedConfig,
+        region: String => Region,
+        port: u16 => Port,
+        dsn: Option<String> => Dsn,
+    );
+
+    #[test]
+    fn test_env_values() {
+        let env: EnvVariables<TypedConfig> = EnvVariables::from(HashMap::from([
+            (TYPED_TEST_REGION, String::from("us-west-2")),
+            (TYPED_TEST_PORT, String::from("8080")),
+        ]));
+        assert_eq!(
+            TypedValues::try_from(env).unwrap(),
+            TypedValues {
+                region: String::from("us-west-2"),
+                port: 8080,
+                dsn: None,
+            }
+        );
+
+        let env: EnvVariables<TypedConfig> = EnvVariables::from(HashMap::from([
+            (TYPED_TEST_REGION, String::from("us-west-2")),
+            (TYPED_TEST_PORT, String::from("http")),
+        ]));
+        assert!(TypedValues::try_from(&env).is_err());
+    }
+}
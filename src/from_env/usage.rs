@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use super::warnings::{report_warning, ConfigWarning};
+use super::{EnvConfigEnum, EnvVariables};
+
+// Key usage.
+// --------------------------------------------------
+
+// Tracks which env variables are read over the lifetime of the process, so that
+// obsolete variables can be pruned from configs and deployment manifests.
+// Tracking is opt-in, so reads cost nothing unless it is enabled (at startup,
+// before the config is read):
+//
+// track_key_usage();
+// ...
+// let unused = config.unused_keys();
+//
+// or report them as a warning (see warnings.rs) when the service shuts down,
+// which also enables tracking:
+//
+// let _unused_report = config.warn_unused_on_drop();
+//
+// A key counts as used once any EnvVariables holding it has read it
+// (including windows cloned from it), through get, get_optional, or any of the
+// accessors built on them.
+static TRACKING: AtomicBool = AtomicBool::new(false);
+static READ_KEYS: Mutex<Option<HashSet<&'static str>>> = Mutex::new(None);
+
+pub fn track_key_usage() {
+    TRACKING.store(true, Ordering::Relaxed);
+}
+
+pub(crate) fn record_read(key: &'static str) {
+    if !TRACKING.load(Ordering::Relaxed) {
+        return;
+    }
+    READ_KEYS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_or_insert_with(HashSet::new)
+        .insert(key);
+}
+
+fn was_read(key: &str) -> bool {
+    READ_KEYS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .is_some_and(|keys| keys.contains(key))
+}
+
+fn unused_keys<T: EnvConfigEnum>() -> Vec<&'static str> {
+    T::value_list()
+        .iter()
+        .map(EnvConfigEnum::as_str)
+        .filter(|key| !was_read(key))
+        .collect()
+}
+
+impl<T: EnvConfigEnum> EnvVariables<T> {
+    pub fn unused_keys(&self) -> Vec<&'static str> {
+        unused_keys::<T>()
+    }
+
+    pub fn warn_unused_on_drop(&self) -> UnusedKeysGuard<T> {
+        track_key_usage();
+        UnusedKeysGuard(PhantomData)
+    }
+}
+
+#[must_use = "unused keys are reported as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct UnusedKeysGuard<T: EnvConfigEnum>(PhantomData<T>);
+
+impl<T: EnvConfigEnum> UnusedKeysGuard<T> {
+    pub fn unused_keys(&self) -> Vec<&'static str> {
+        unused_keys::<T>()
+    }
+}
+
+impl<T: EnvConfigEnum> Drop for UnusedKeysGuard<T> {
+    fn drop(&mut self) {
+        let unused = self.unused_keys();
+        if !unused.is_empty() {
+            report_warning(ConfigWarning::UnusedKeys {
+                config: std::any::type_name::<T>(),
+                keys: unused,
+            });
+        }
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::from_env::warnings::{reported_warnings, ConfigWarning};
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum, EnvVariables};
+
+    define_env_variable!(USAGE_TEST_READ);
+    define_env_variable!(USAGE_TEST_WINDOW_READ);
+    define_env_variable!(USAGE_TEST_UNREAD);
+
+    define_env_config!(
+        UsageConfig,
+        Read => USAGE_TEST_READ,
+        WindowRead => USAGE_TEST_WINDOW_READ,
+        Unread => USAGE_TEST_UNREAD,
+    );
+
+    define_env_config!(
+        UsageWindowConfig,
+        WindowRead => USAGE_TEST_WINDOW_READ,
+    );
+
+    #[test]
+    fn test_unused_keys() {
+        let config: EnvVariables<UsageConfig> = EnvVariables::from(HashMap::from([
+            (USAGE_TEST_READ, String::from("a")),
+            (USAGE_TEST_WINDOW_READ, String::from("b")),
+            (USAGE_TEST_UNREAD, String::from("c")),
+        ]));
+        let guard = config.warn_unused_on_drop();
+        assert_eq!(config.unused_keys().len(), 3);
+
+        config.get(&UsageConfig::Read).unwrap();
+        let window = config.clone_into::<UsageWindowConfig>().unwrap();
+        window.get(&UsageWindowConfig::WindowRead).unwrap();
+
+        assert_eq!(config.unused_keys(), vec![USAGE_TEST_UNREAD]);
+        assert_eq!(guard.unused_keys(), vec![USAGE_TEST_UNREAD]);
+
+        drop(guard);
+        assert!(reported_warnings().contains(&ConfigWarning::UnusedKeys {
+            config: std::any::type_name::<UsageConfig>(),
+            keys: vec![USAGE_TEST_UNREAD],
+        }));
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::{DecodeError, Engine};
+
+use super::{
+    EnvConfigEnum, EnvVariables, InvalidBase64Value, InvalidEnvMapEntry, InvalidEnvTableRow,
+};
+use crate::ConfigError;
+
+// Structured values.
+// --------------------------------------------------
+
+// Accessors parsing values with some internal structure, reporting malformed
+// values with the name of the variable.
+//
+// Maps are written as delimited key-value pairs, ex. FEATURE_FLAGS=a=1,b=0:
+//
+// let flags = config.get_map(&EnvConfig::FeatureFlags)?;
+// let flags = config.get_map_with(&EnvConfig::FeatureFlags, ';', ':')?;
+//
+// Whitespace around keys and values is trimmed and empty entries are skipped.
+// Entries without the pair delimiter, with an empty key, or repeating a key are
+// rejected.
+//
+// Small tables are written as rows of colon-separated fields, ex.
+// RATE_LIMITS=api:100,web:50. Rows of a name and a count are read with
+// get_table, and any other shape with a row parser:
+//
+// let limits: Vec<(String, u64)> = config.get_table(&EnvConfig::RateLimits)?;
+// let routes = config.get_table_with(&EnvConfig::Routes, |fields| match fields {
+//     [path, upstream, weight] => Ok(Route {
+//         path: path.to_string(),
+//         upstream: upstream.to_string(),
+//         weight: weight.parse().map_err(|_| "invalid weight".to_string())?,
+//     }),
+//     _ => Err("expected path:upstream:weight".to_string()),
+// })?;
+//
+// Rows keep their order, fields are trimmed, and empty rows are skipped. A row
+// the parser rejects fails with its reason.
+//
+// Binary values (ex. certificates, keys) are often transported base64-encoded
+// (standard alphabet, with padding):
+//
+// let key = config.get_base64(&EnvConfig::SigningKey)?;
+//
+// Surrounding whitespace is ignored, and decode failures report the offset of
+// the invalid character. Also available on SecretValues.
+impl<T: EnvConfigEnum> EnvVariables<T> {
+    pub fn get_map(&self, key: &T) -> Result<HashMap<String, String>, ConfigError> {
+        self.get_map_with(key, ',', '=')
+    }
+
+    pub fn get_map_with(
+        &self,
+        key: &T,
+        entry_delimiter: char,
+        pair_delimiter: char,
+    ) -> Result<HashMap<String, String>, ConfigError> {
+        parse_map(
+            key.as_str(),
+            self.get(key)?,
+            entry_delimiter,
+            pair_delimiter,
+        )
+    }
+
+    pub fn get_table(&self, key: &T) -> Result<Vec<(String, u64)>, ConfigError> {
+        self.get_table_with(key, |fields| match fields {
+            [name, count] => count
+                .parse()
+                .map(|count| (name.to_string(), count))
+                .map_err(|_| format!("'{count}' is not a non-negative integer")),
+            _ => Err("expected name:count".to_string()),
+        })
+    }
+
+    pub fn get_table_with<R>(
+        &self,
+        key: &T,
+        parse_row: impl Fn(&[&str]) -> Result<R, String>,
+    ) -> Result<Vec<R>, ConfigError> {
+        parse_table(key.as_str(), self.get(key)?, parse_row)
+    }
+
+    pub fn get_base64(&self, key: &T) -> Result<Vec<u8>, ConfigError> {
+        decode_base64(key.as_str(), self.get(key)?)
+    }
+}
+
+pub(crate) fn decode_base64(var: &str, value: &str) -> Result<Vec<u8>, ConfigError> {
+    STANDARD.decode(value.trim()).map_err(|e| {
+        let position = match e {
+            DecodeError::InvalidByte(offset, _) | DecodeError::InvalidLastSymbol(offset, _) => {
+                format!("symbol at offset {offset}")
+            }
+            DecodeError::InvalidLength(length) => format!("length {length}"),
+            DecodeError::InvalidPadding => "padding".to_string(),
+        };
+        InvalidBase64Value::with_source(var, &position, e)
+    })
+}
+
+fn parse_map(
+    var: &str,
+    value: &str,
+    entry_delimiter: char,
+    pair_delimiter: char,
+) -> Result<HashMap<String, String>, ConfigError> {
+    let mut map = HashMap::new();
+    for entry in value.split(entry_delimiter).map(str::trim) {
+        if entry.is_empty() {
+            continue;
+        }
+        let (k, v) = entry
+            .split_once(pair_delimiter)
+            .map(|(k, v)| (k.trim(), v.trim()))
+            .filter(|(k, _)| !k.is_empty())
+            .ok_or_else(|| InvalidEnvMapEntry::new(var, entry))?;
+        if map.insert(k.to_string(), v.to_string()).is_some() {
+            return Err(InvalidEnvMapEntry::new(var, entry));
+        }
+    }
+    Ok(map)
+}
+
+fn parse_table<R>(
+    var: &str,
+    value: &str,
+    parse_row: impl Fn(&[&str]) -> Result<R, String>,
+) -> Result<Vec<R>, ConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|row| !row.is_empty())
+        .map(|row| {
+            let fields = row.split(':').map(str::trim).collect::<Vec<_>>();
+            parse_row(&fields).map_err(|reason| InvalidEnvTableRow::new(var, row, &reason))
+        })
+        .collect()
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum, EnvVariables};
+
+    define_env_variable!(FEATURE_FLAGS);
+
+    define_env_config!(
+        ValuesConfig,
+        FeatureFlags => FEATURE_FLAGS,
+    );
+
+    fn config(value: &str) -> EnvVariables<ValuesConfig> {
+        EnvVariables::from(HashMap::from([(FEATURE_FLAGS, value.to_string())]))
+    }
+
+    #[test]
+    fn test_get_base64() {
+        assert_eq!(
+            config(" aGVsbG8= \n")
+                .get_base64(&ValuesConfig::FeatureFlags)
+                .unwrap(),
+            b"hello"
+        );
+        let error = config("aGV*bG8=")
+            .get_base64(&ValuesConfig::FeatureFlags)
+            .unwrap_err();
+        assert!(error.to_string().contains("offset 3"));
+    }
+
+    #[test]
+    fn test_get_map() {
+        let map = config("a=1, b = 0,,c=")
+            .get_map(&ValuesConfig::FeatureFlags)
+            .unwrap();
+        assert_eq!(
+            map,
+            HashMap::from([
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "0".to_string()),
+                ("c".to_string(), "".to_string()),
+            ])
+        );
+        assert!(config("")
+            .get_map(&ValuesConfig::FeatureFlags)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_get_table() {
+        let table = config("api:100, web : 50,")
+            .get_table(&ValuesConfig::FeatureFlags)
+            .unwrap();
+        assert_eq!(
+            table,
+            vec![("api".to_string(), 100), ("web".to_string(), 50)]
+        );
+        let error = config("api:100,web:-1")
+            .get_table(&ValuesConfig::FeatureFlags)
+            .unwrap_err();
+        assert!(error.to_string().contains("web:-1"));
+
+        let rows = config("a:1:x,b:2:y")
+            .get_table_with(&ValuesConfig::FeatureFlags, |fields| match fields {
+                [name, _, tag] => Ok(format!("{name}{tag}")),
+                _ => Err("expected 3 fields".to_string()),
+            })
+            .unwrap();
+        assert_eq!(rows, vec!["ax", "by"]);
+    }
+
+    #[test]
+    fn test_get_map_with_delimiters() {
+        let map = config("a:x=1;b:2")
+            .get_map_with(&ValuesConfig::FeatureFlags, ';', ':')
+            .unwrap();
+        assert_eq!(map["a"], "x=1");
+        assert_eq!(map["b"], "2");
+    }
+
+    #[test]
+    fn test_get_map_malformed() {
+        assert!(config("a=1,b")
+            .get_map(&ValuesConfig::FeatureFlags)
+            .is_err());
+        assert!(config("=1").get_map(&ValuesConfig::FeatureFlags).is_err());
+        assert!(config("a=1,a=2")
+            .get_map(&ValuesConfig::FeatureFlags)
+            .is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use super::{EnvNameCollision, PolicyViolation};
+
+// Config warnings.
+// --------------------------------------------------
+
+// Conditions worth an operator's attention that don't fail the load (ex. a
+// deprecated variable that is still set) are never printed by this crate.
+// Instead, they are passed to a process-wide reporter, to be forwarded to the
+// service's own logging:
+//
+// set_warning_reporter(Arc::new(|warning: &ConfigWarning| tracing::warn!("{warning}")));
+//
+// Without a reporter, warnings are dropped. The reporter is disabled again by
+// clear_warning_reporter.
+pub trait WarningReporter: Send + Sync {
+    fn report(&self, warning: &ConfigWarning);
+}
+
+impl<F: Fn(&ConfigWarning) + Send + Sync> WarningReporter for F {
+    fn report(&self, warning: &ConfigWarning) {
+        self(warning)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigWarning {
+    // A variable with a removal date that hasn't passed yet is still set (see
+    // removal.rs).
+    DeprecatedVariableSet {
+        var: String,
+        removed_after: String,
+    },
+    // A policy in PolicyMode::Warn was violated (see policy.rs).
+    PolicyViolation(PolicyViolation),
+    // A config with DivergenceMode::Warn was loaded again with different
+    // values for the keys (see divergence.rs).
+    ConfigDiverged {
+        config: &'static str,
+        keys: Vec<&'static str>,
+    },
+    // The process environment was mutated since it was frozen (see
+    // freeze.rs).
+    EnvironmentMutated {
+        vars: Vec<String>,
+    },
+    // Keys of a config never read by the time its UnusedKeysGuard was dropped
+    // (see usage.rs).
+    UnusedKeys {
+        config: &'static str,
+        keys: Vec<&'static str>,
+    },
+    // Variables of the same name were declared in several modules (see
+    // collision.rs).
+    VariableNameCollision(EnvNameCollision),
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigWarning::DeprecatedVariableSet { var, removed_after } => write!(
+                f,
+                "Environment variable '{var}' is deprecated, and will be rejected after {removed_after}. Delete it from the environment."
+            ),
+            ConfigWarning::PolicyViolation(violation) => {
+                write!(f, "Config policy violation: {violation}.")
+            }
+            ConfigWarning::ConfigDiverged { config, keys } => write!(
+                f,
+                "Config '{config}' was loaded again with different values for: {}.",
+                keys.join(", ")
+            ),
+            ConfigWarning::EnvironmentMutated { vars } => write!(
+                f,
+                "The process environment was mutated since the config was first loaded \
+                 (likely by a test calling std::env::set_var; prefer EnvOverrideScope): {}.",
+                vars.join(", ")
+            ),
+            ConfigWarning::UnusedKeys { config, keys } => write!(
+                f,
+                "Environment variables declared in {config} but never read: {}.",
+                keys.join(", ")
+            ),
+            ConfigWarning::VariableNameCollision(collision) => write!(
+                f,
+                "Environment variable '{}' is declared separately in several modules, which all read the same variable: {}.",
+                collision.name,
+                collision.modules.join(", ")
+            ),
+        }
+    }
+}
+
+static REPORTER: RwLock<Option<Arc<dyn WarningReporter>>> = RwLock::new(None);
+
+pub fn set_warning_reporter(reporter: Arc<dyn WarningReporter>) {
+    *REPORTER.write().unwrap_or_else(PoisonError::into_inner) = Some(reporter);
+}
+
+pub fn clear_warning_reporter() {
+    *REPORTER.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+pub(crate) fn report_warning(warning: ConfigWarning) {
+    #[cfg(test)]
+    REPORTED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(warning.clone());
+    let reporter = REPORTER.read().unwrap_or_else(PoisonError::into_inner);
+    if let Some(reporter) = reporter.as_ref() {
+        reporter.report(&warning);
+    }
+}
+
+// Every warning reported by the tests of this crate, which run in parallel (so
+// each test looks for the warnings it raised).
+#[cfg(test)]
+static REPORTED: std::sync::Mutex<Vec<ConfigWarning>> = std::sync::Mutex::new(Vec::new());
+
+#[cfg(test)]
+pub(crate) fn reported_warnings() -> Vec<ConfigWarning> {
+    REPORTED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
@@ -0,0 +1,19 @@
+use crate::error::define_config_error;
+
+define_config_error!(
+    FailedToConnectToK8s,
+    "K8S001",
+    "Failed to create a Kubernetes client from the in-cluster (or local kubeconfig) config."
+);
+define_config_error!(
+    FailedToFetchK8sObject,
+    "K8S002",
+    "Failed to fetch {kind} '{namespace}/{name}' from the Kubernetes API.",
+    { kind: &str, namespace: &str, name: &str }
+);
+define_config_error!(
+    InvalidK8sValue,
+    "K8S003",
+    "{kind} '{namespace}/{name}' key '{key}' is not valid UTF-8.",
+    { kind: &str, namespace: &str, name: &str, key: &str }
+);
@@ -0,0 +1,5 @@
+mod errors;
+mod source;
+
+pub use errors::*;
+pub use source::{K8sObject, K8sSource};
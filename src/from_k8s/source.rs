@@ -0,0 +1,263 @@
+use std::collections::{BTreeMap, HashMap};
+use std::pin::pin;
+
+use futures::StreamExt;
+use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+use k8s_openapi::ByteString;
+use kube::runtime::{watcher, WatchStreamExt};
+use kube::{Api, Client};
+use tokio::task::JoinHandle;
+
+use crate::ConfigError;
+use crate::ConfigSource;
+
+use super::errors::{FailedToConnectToK8s, FailedToFetchK8sObject, InvalidK8sValue};
+
+// Kubernetes API source.
+// --------------------------------------------------
+
+// Workloads that prefer API access over mounted files can read their config
+// from a ConfigMap or Secret through the Kubernetes API:
+//
+// let env = load_from_source::<EnvConfig>(
+//     &K8sSource::fetch(&K8sObject::config_map("my-service")).await?,
+// )?;
+// let secrets = load_secrets_from_source::<SecretsConfig>(
+//     &K8sSource::fetch(&K8sObject::secret("my-service").namespace("prod")).await?,
+// )?;
+//
+// Each key of the object's data is a variable. Secret values (and binary
+// ConfigMap data) must be valid UTF-8. Objects are read from the pod's own
+// namespace unless one is given, using the in-cluster config (or the local
+// kubeconfig outside a cluster). The service account needs get (and, to watch,
+// list and watch) access to the object.
+//
+// With K8sSource::watch, the object is watched and each new version is passed
+// to a callback, ex. to hot reload feature flags:
+//
+// let flags = Arc::new(FeatureFlags::<Flags>::load_from(&source)?);
+// K8sSource::watch(client, K8sObject::config_map("flags"), move |source| {
+//     if let Ok(source) = source {
+//         let _ = flags.reload_from(&source);
+//     }
+// });
+//
+// Available with the "k8s" feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum K8sKind {
+    ConfigMap,
+    Secret,
+}
+
+impl K8sKind {
+    fn name(&self) -> &'static str {
+        match self {
+            K8sKind::ConfigMap => "ConfigMap",
+            K8sKind::Secret => "Secret",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct K8sObject {
+    kind: K8sKind,
+    name: String,
+    namespace: Option<String>,
+}
+
+impl K8sObject {
+    pub fn config_map(name: impl Into<String>) -> Self {
+        Self {
+            kind: K8sKind::ConfigMap,
+            name: name.into(),
+            namespace: None,
+        }
+    }
+
+    pub fn secret(name: impl Into<String>) -> Self {
+        Self {
+            kind: K8sKind::Secret,
+            name: name.into(),
+            namespace: None,
+        }
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    fn resolve_namespace(&self, client: &Client) -> String {
+        self.namespace
+            .clone()
+            .unwrap_or_else(|| client.default_namespace().to_string())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct K8sSource(HashMap<String, String>);
+
+impl K8sSource {
+    pub async fn fetch(object: &K8sObject) -> Result<Self, ConfigError> {
+        Self::fetch_with(connect().await?, object).await
+    }
+
+    pub async fn fetch_with(client: Client, object: &K8sObject) -> Result<Self, ConfigError> {
+        let namespace = object.resolve_namespace(&client);
+        let fetch_error = |e: kube::Error| {
+            FailedToFetchK8sObject::with_source(object.kind.name(), &namespace, &object.name, e)
+        };
+        match object.kind {
+            K8sKind::ConfigMap => {
+                let config_map = Api::<ConfigMap>::namespaced(client, &namespace)
+                    .get(&object.name)
+                    .await
+                    .map_err(fetch_error)?;
+                Self::from_config_map(object, &namespace, config_map)
+            }
+            K8sKind::Secret => {
+                let secret = Api::<Secret>::namespaced(client, &namespace)
+                    .get(&object.name)
+                    .await
+                    .map_err(fetch_error)?;
+                Self::from_data(object, &namespace, BTreeMap::new(), secret.data)
+            }
+        }
+    }
+
+    // Spawns a task watching the object, calling on_change with each new
+    // version (and with any error, after which the watch is retried with
+    // backoff). Aborting the returned handle stops the watch.
+    pub fn watch(
+        client: Client,
+        object: K8sObject,
+        on_change: impl Fn(Result<K8sSource, ConfigError>) + Send + 'static,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let namespace = object.resolve_namespace(&client);
+            let config =
+                watcher::Config::default().fields(&format!("metadata.name={}", object.name));
+            let watch_error = |e: watcher::Error| {
+                FailedToFetchK8sObject::with_source(object.kind.name(), &namespace, &object.name, e)
+            };
+            match object.kind {
+                K8sKind::ConfigMap => {
+                    let api = Api::<ConfigMap>::namespaced(client, &namespace);
+                    let mut stream = pin!(watcher(api, config).default_backoff().applied_objects());
+                    while let Some(config_map) = stream.next().await {
+                        on_change(config_map.map_err(watch_error).and_then(|config_map| {
+                            Self::from_config_map(&object, &namespace, config_map)
+                        }));
+                    }
+                }
+                K8sKind::Secret => {
+                    let api = Api::<Secret>::namespaced(client, &namespace);
+                    let mut stream = pin!(watcher(api, config).default_backoff().applied_objects());
+                    while let Some(secret) = stream.next().await {
+                        on_change(secret.map_err(watch_error).and_then(|secret| {
+                            Self::from_data(&object, &namespace, BTreeMap::new(), secret.data)
+                        }));
+                    }
+                }
+            }
+        })
+    }
+
+    fn from_config_map(
+        object: &K8sObject,
+        namespace: &str,
+        config_map: ConfigMap,
+    ) -> Result<Self, ConfigError> {
+        Self::from_data(
+            object,
+            namespace,
+            config_map.data.unwrap_or_default(),
+            config_map.binary_data,
+        )
+    }
+
+    fn from_data(
+        object: &K8sObject,
+        namespace: &str,
+        data: BTreeMap<String, String>,
+        binary_data: Option<BTreeMap<String, ByteString>>,
+    ) -> Result<Self, ConfigError> {
+        let mut values = data.into_iter().collect::<HashMap<_, _>>();
+        for (key, ByteString(bytes)) in binary_data.unwrap_or_default() {
+            let value = String::from_utf8(bytes).map_err(|_| {
+                InvalidK8sValue::new(object.kind.name(), namespace, &object.name, &key)
+            })?;
+            values.insert(key, value);
+        }
+        Ok(Self(values))
+    }
+}
+
+async fn connect() -> Result<Client, ConfigError> {
+    Client::try_default()
+        .await
+        .map_err(FailedToConnectToK8s::with_source)
+}
+
+impl ConfigSource for K8sSource {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        Ok(self.0.get(key).cloned())
+    }
+
+    fn name(&self) -> &'static str {
+        "k8s"
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use k8s_openapi::ByteString;
+
+    use crate::{define_env_config, define_env_variable, load_from_source, EnvConfigEnum};
+
+    use super::{K8sObject, K8sSource};
+
+    define_env_variable!(K8S_TEST_REGION);
+    define_env_variable!(K8S_TEST_CERT);
+
+    define_env_config!(
+        K8sConfig,
+        Region => K8S_TEST_REGION,
+        Cert => K8S_TEST_CERT,
+    );
+
+    #[test]
+    fn test_k8s_source_from_config_map() {
+        let object = K8sObject::config_map("my-service").namespace("prod");
+        let config_map = ConfigMap {
+            data: Some(BTreeMap::from([(
+                String::from("K8S_TEST_REGION"),
+                String::from("us-west-2"),
+            )])),
+            binary_data: Some(BTreeMap::from([(
+                String::from("K8S_TEST_CERT"),
+                ByteString(b"cert".to_vec()),
+            )])),
+            ..Default::default()
+        };
+        let source = K8sSource::from_config_map(&object, "prod", config_map).unwrap();
+        let env = load_from_source::<K8sConfig>(&source).unwrap();
+        assert_eq!(env.get(&K8sConfig::Region).unwrap(), "us-west-2");
+        assert_eq!(env.get(&K8sConfig::Cert).unwrap(), "cert");
+
+        let config_map = ConfigMap {
+            binary_data: Some(BTreeMap::from([(
+                String::from("K8S_TEST_CERT"),
+                ByteString(vec![0xff]),
+            )])),
+            ..Default::default()
+        };
+        assert!(K8sSource::from_config_map(&object, "prod", config_map).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+use std::panic::Location;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::SystemTime;
+
+// Secret access auditing.
+// --------------------------------------------------
+
+// Opt-in record of each read of a secret value (SecretValues::get, and the
+// accessors built on it), for compliance requirements around tracing secret
+// usage. Either keep the most recent accesses in memory:
+//
+// let log = Arc::new(SecretAccessLog::new(1024));
+// set_secret_auditor(log.clone());
+// ...
+// for access in log.entries() { ... }
+//
+// or forward each access elsewhere with a callback:
+//
+// set_secret_auditor(Arc::new(|access: &SecretAccess| audit_sink.send(access)));
+//
+// Each access records the time, the key name, and the source location of the
+// call. Values are never recorded. Auditing is process-wide, and disabled
+// again by clear_secret_auditor.
+pub trait SecretAuditor: Send + Sync {
+    fn record(&self, access: &SecretAccess);
+}
+
+impl<F: Fn(&SecretAccess) + Send + Sync> SecretAuditor for F {
+    fn record(&self, access: &SecretAccess) {
+        self(access)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretAccess {
+    pub timestamp: SystemTime,
+    pub key: &'static str,
+    pub caller: &'static Location<'static>,
+}
+
+// Ring buffer of the most recent accesses, dropping the oldest once the
+// capacity is reached.
+#[derive(Debug)]
+pub struct SecretAccessLog {
+    capacity: usize,
+    entries: Mutex<VecDeque<SecretAccess>>,
+}
+
+impl SecretAccessLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    // Oldest first.
+    pub fn entries(&self) -> Vec<SecretAccess> {
+        self.lock_entries().iter().cloned().collect()
+    }
+
+    fn lock_entries(&self) -> std::sync::MutexGuard<'_, VecDeque<SecretAccess>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl SecretAuditor for SecretAccessLog {
+    fn record(&self, access: &SecretAccess) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.lock_entries();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(access.clone());
+    }
+}
+
+static AUDITOR: RwLock<Option<Arc<dyn SecretAuditor>>> = RwLock::new(None);
+
+pub fn set_secret_auditor(auditor: Arc<dyn SecretAuditor>) {
+    *AUDITOR.write().unwrap_or_else(PoisonError::into_inner) = Some(auditor);
+}
+
+pub fn clear_secret_auditor() {
+    *AUDITOR.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+pub(crate) fn record_access(key: &'static str, caller: &'static Location<'static>) {
+    let auditor = AUDITOR.read().unwrap_or_else(PoisonError::into_inner);
+    if let Some(auditor) = auditor.as_ref() {
+        auditor.record(&SecretAccess {
+            timestamp: SystemTime::now(),
+            key,
+            caller,
+        });
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use crate::{define_secret_key, define_secrets_config, SecretValues, SecretsConfigEnum};
+
+    use super::{clear_secret_auditor, set_secret_auditor, SecretAccessLog, SecretAuditor};
+
+    define_secret_key!(AUDIT_TEST_KEY);
+
+    define_secrets_config!(
+        AuditConfig,
+        AuditTestKey => AUDIT_TEST_KEY,
+    );
+
+    #[test]
+    fn test_access_log_ring_buffer() {
+        let log = SecretAccessLog::new(2);
+        for key in ["A", "B", "C"] {
+            log.record(&super::SecretAccess {
+                timestamp: std::time::SystemTime::now(),
+                key,
+                caller: std::panic::Location::caller(),
+            });
+        }
+        let keys = log.entries().iter().map(|a| a.key).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["B", "C"]);
+    }
+
+    #[test]
+    fn test_secret_access_is_audited() {
+        let secrets: SecretValues<AuditConfig> =
+            SecretValues::from(HashMap::from([(AUDIT_TEST_KEY, String::from("hunter2"))]));
+        let log = Arc::new(SecretAccessLog::new(1024));
+        set_secret_auditor(log.clone());
+
+        secrets.get(&AuditConfig::AuditTestKey).unwrap();
+        secrets.get_secret(&AuditConfig::AuditTestKey).unwrap();
+        clear_secret_auditor();
+        secrets.get(&AuditConfig::AuditTestKey).unwrap();
+
+        // Other tests may read secrets concurrently.
+        let accesses = log
+            .entries()
+            .into_iter()
+            .filter(|access| access.key == AUDIT_TEST_KEY)
+            .collect::<Vec<_>>();
+        assert_eq!(accesses.len(), 2);
+        assert!(accesses
+            .iter()
+            .all(|a| a.caller.file().ends_with("audit.rs")));
+    }
+}
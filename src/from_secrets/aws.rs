@@ -0,0 +1,121 @@
+use aws_config::{BehaviorVersion, SdkConfig};
+use aws_sdk_secretsmanager::config::{Builder, Region};
+use aws_sdk_secretsmanager::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_secretsmanager::operation::get_secret_value::GetSecretValueError;
+use aws_sdk_secretsmanager::Client;
+
+use crate::ConfigError;
+
+use super::errors::{
+    FailedToFetchSecretsJson, SecretNotFound, SecretsAccessDenied, SecretsNetworkError,
+    SecretsThrottled,
+};
+use super::fetcher::SecretsFetcher;
+
+// Amazon Secrets Manager.
+// --------------------------------------------------
+
+// The default fetcher, used by load_secrets. Requires the "aws" feature
+// (enabled by default); without it, secrets can still be loaded through
+// load_secrets_with and any other SecretsFetcher.
+//
+// Services that already share an SdkConfig across their SDK clients can reuse
+// it (and its cached credentials), rather than resolving credentials again:
+//
+// let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+// let secrets = load_secrets_with_sdk_config::<SecretsConfig>(env, &sdk_config).await?;
+#[derive(Debug, Clone)]
+pub struct AwsSecretsFetcher {
+    client: Client,
+    region: String,
+}
+
+impl AwsSecretsFetcher {
+    pub async fn new(region: &str) -> Self {
+        Self::with_behavior_version(region, BehaviorVersion::v2024_03_28()).await
+    }
+
+    pub async fn with_behavior_version(region: &str, behavior_version: BehaviorVersion) -> Self {
+        let shared_config = aws_config::defaults(behavior_version)
+            .region(Region::new(region.to_string()))
+            .load()
+            .await;
+        Self::from_sdk_config(region, &shared_config)
+    }
+
+    // The region of the SdkConfig is overridden by the given one (ex. read from
+    // SECRETS_REGION), everything else is kept as-is.
+    pub fn from_sdk_config(region: &str, sdk_config: &SdkConfig) -> Self {
+        let config = Builder::from(sdk_config)
+            .region(Region::new(region.to_string()))
+            .build();
+        Self {
+            client: Client::from_conf(config),
+            region: region.to_string(),
+        }
+    }
+}
+
+impl SecretsFetcher for AwsSecretsFetcher {
+    async fn fetch_secret_string(&self, secret_id: &str) -> Result<String, ConfigError> {
+        let secrets_output = self
+            .client
+            .get_secret_value()
+            .secret_id(secret_id)
+            .send()
+            .await
+            .map_err(|e| fetch_error(e, secret_id, &self.region))?;
+        secrets_output
+            .secret_string()
+            .map(str::to_string)
+            .ok_or_else(|| {
+                ConfigError::critical(&format!(
+                    "Could not parse secret value. SecretsId: {}; Region: {};",
+                    secret_id, self.region
+                ))
+            })
+    }
+
+    async fn fetch_secret_binary(&self, secret_id: &str) -> Result<Vec<u8>, ConfigError> {
+        let secrets_output = self
+            .client
+            .get_secret_value()
+            .secret_id(secret_id)
+            .send()
+            .await
+            .map_err(|e| fetch_error(e, secret_id, &self.region))?;
+        secrets_output
+            .secret_binary()
+            .map(|blob| blob.as_ref().to_vec())
+            .ok_or_else(|| {
+                ConfigError::critical(&format!(
+                    "Secret has no binary value. SecretsId: {}; Region: {};",
+                    secret_id, self.region
+                ))
+            })
+    }
+}
+
+// Distinguish misconfiguration (not found), permissions (access denied), and
+// transient faults (throttling, network), so that callers and alerts can react
+// differently. Anything else falls back to FailedToFetchSecretsJson.
+fn fetch_error(e: SdkError<GetSecretValueError>, secret_id: &str, region: &str) -> ConfigError {
+    type Constructor = fn(&str, &str, SdkError<GetSecretValueError>) -> ConfigError;
+    let constructor: Constructor = match e.as_service_error() {
+        Some(service_error) if service_error.is_resource_not_found_exception() => {
+            SecretNotFound::with_source
+        }
+        Some(service_error) => match service_error.code() {
+            Some("AccessDeniedException") => SecretsAccessDenied::with_source,
+            Some("ThrottlingException") => SecretsThrottled::with_source,
+            _ => FailedToFetchSecretsJson::with_source,
+        },
+        None => match &e {
+            SdkError::TimeoutError(_)
+            | SdkError::DispatchFailure(_)
+            | SdkError::ResponseError(_) => SecretsNetworkError::with_source,
+            _ => FailedToFetchSecretsJson::with_source,
+        },
+    };
+    constructor(secret_id, region, e)
+}
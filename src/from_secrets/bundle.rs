@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use serde_json::Value;
+use zip::ZipArchive;
+
+use super::errors::InvalidSecretBundle;
+use super::fetcher::SecretsFetcher;
+use crate::ConfigError;
+
+// Secret bundles.
+// --------------------------------------------------
+
+// Many keys can be stored cheaply as a single binary secret holding a ZIP of
+// JSON files:
+//
+// define_secrets_config!(
+//     SecretsConfig { bundle = true },
+//     OpenAIKey => OPENAI_KEY,
+// );
+//
+// load_secrets then fetches the SecretBinary of SECRETS_ID and merges the JSON
+// objects of all files in the archive (in archive order, later files taking
+// precedence on duplicate keys) into the map keys are looked up in, as if
+// they were a single secret string. Directories are skipped, and any other
+// file must contain a JSON object.
+//
+// Available with the "bundle" feature; declaring a bundle without it fails to
+// compile.
+pub(crate) async fn fetch_bundle(
+    fetcher: &impl SecretsFetcher,
+    secret_id: &str,
+    region: &str,
+) -> Result<HashMap<String, Value>, ConfigError> {
+    let bytes = fetcher.fetch_secret_binary(secret_id).await?;
+    unpack_bundle(&bytes).map_err(|reason| InvalidSecretBundle::new(secret_id, region, &reason))
+}
+
+fn unpack_bundle(bytes: &[u8]) -> Result<HashMap<String, Value>, String> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let mut merged = HashMap::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(|e| e.to_string())?;
+        if file.is_dir() {
+            continue;
+        }
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)
+            .map_err(|e| format!("file '{}' could not be read ({e})", file.name()))?;
+        let values = serde_json::from_str::<HashMap<String, Value>>(&contents)
+            .map_err(|_| format!("file '{}' is not a JSON object", file.name()))?;
+        merged.extend(values);
+    }
+    Ok(merged)
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::{Cursor, Write};
+
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use crate::{
+        define_secret_key, define_secrets_config, load_secrets_with, EnvVariables,
+        FakeSecretsBackend, SecretsConfigEnum, SecretsEnvConfig, SECRETS_ID, SECRETS_REGION,
+    };
+
+    define_secret_key!(BUNDLE_TEST_OPENAI_KEY);
+    define_secret_key!(BUNDLE_TEST_STRIPE_KEY);
+
+    define_secrets_config!(
+        BundleConfig { bundle = true },
+        OpenAIKey => BUNDLE_TEST_OPENAI_KEY,
+        StripeKey => BUNDLE_TEST_STRIPE_KEY,
+    );
+
+    fn zip_files(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_bundle() {
+        let env = EnvVariables::<SecretsEnvConfig>::from(HashMap::from([
+            (SECRETS_REGION, String::from("us-west-2")),
+            (SECRETS_ID, String::from("test-bundle")),
+        ]));
+        let backend = FakeSecretsBackend::new().with_binary_secret(
+            "test-bundle",
+            zip_files(&[
+                ("openai.json", r#"{"BUNDLE_TEST_OPENAI_KEY": "abc123"}"#),
+                ("stripe.json", r#"{"BUNDLE_TEST_STRIPE_KEY": "sk_test"}"#),
+            ]),
+        );
+        let secrets = load_secrets_with::<BundleConfig>(env.clone(), &backend)
+            .await
+            .unwrap();
+        assert_eq!(secrets.get(&BundleConfig::OpenAIKey).unwrap(), "abc123");
+        assert_eq!(secrets.get(&BundleConfig::StripeKey).unwrap(), "sk_test");
+
+        backend.set_binary_secret("test-bundle", zip_files(&[("keys.txt", "abc123")]));
+        let error = load_secrets_with::<BundleConfig>(env, &backend)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("keys.txt"));
+    }
+}
@@ -0,0 +1,751 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::watch;
+
+use crate::ConfigError;
+
+#[cfg(feature = "disk-cache")]
+use super::disk_cache::{DiskCache, DiskCacheKey};
+use super::errors::CoalescedSecretsFetchFailed;
+use super::fetcher::SecretsFetcher;
+
+// Secrets cache.
+// --------------------------------------------------
+
+// Wraps another SecretsFetcher, caching each fetched secret string for the
+// given TTL, so repeated loads (ex. per-request or on reload) don't all make a
+// round trip to Secrets Manager:
+//
+// let fetcher = CachedSecretsFetcher::new(AwsSecretsFetcher::new(region).await, ttl)
+//     .stale_while_revalidate(Duration::from_secs(600));
+// let secrets = load_secrets_with::<SecretsConfig>(env, &fetcher).await?;
+//
+// By default, an expired entry is refetched inline. With
+// stale_while_revalidate, an expired entry is instead still served while it is
+// refreshed in the background, and a fetch only blocks (and can fail) once the
+// entry has been stale for longer than the given maximum. Failed background
+// refreshes keep the stale entry, and are retried on the next fetch. They are
+// counted by failed_refreshes, and the most recent failure (until the next
+// successful refresh) is reported by last_refresh_error and ConfigHealth.
+//
+// Concurrent fetches of the same secret (ex. many tasks hitting an expired
+// entry at once) are coalesced into a single call to the inner fetcher, whose
+// result is shared by all waiters. coalesced_fetches counts the fetches that
+// were saved this way. refresh (ex. after a rotation) always starts a fetch of
+// its own, and fetches or background refreshes started before it are not
+// stored, so they can't overwrite the refreshed value.
+//
+// When many instances share the same TTL, their refreshes can be spread out
+// with ttl_jitter: each entry then expires at a pseudo-random point within the
+// given window before its TTL, derived from a per-process seed, the secret ID
+// and the fetch time. The TTL stays the maximum age of a served entry.
+//
+// Entries can also be persisted to an encrypted file with persist_to (see
+// disk_cache.rs).
+#[derive(Debug)]
+pub struct CachedSecretsFetcher<F: SecretsFetcher + 'static> {
+    fetcher: Arc<F>,
+    ttl: Duration,
+    jitter: Duration,
+    jitter_seed: RandomState,
+    max_stale: Option<Duration>,
+    entries: Arc<Mutex<HashMap<String, CacheEntry>>>,
+    generations: Arc<Mutex<HashMap<String, u64>>>,
+    in_flight: Mutex<HashMap<String, (u64, InFlightResult)>>,
+    coalesced: AtomicU64,
+    refresh_failures: Arc<RefreshFailures>,
+    #[cfg(feature = "disk-cache")]
+    disk: Option<Arc<DiskCache>>,
+}
+
+#[derive(Debug, Default)]
+struct RefreshFailures {
+    count: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl RefreshFailures {
+    fn record(&self, result: &Result<String, ConfigError>) {
+        let mut last_error = self
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match result {
+            Ok(_) => *last_error = None,
+            Err(e) => {
+                self.count.fetch_add(1, Ordering::Relaxed);
+                *last_error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    value: String,
+    fetched_at: SystemTime,
+    refreshing: bool,
+}
+
+// Result of an in-flight fetch, None until it completes. Errors are shared with
+// the waiters as-is (ConfigErrors share their source between clones).
+type InFlightResult = watch::Receiver<Option<Result<String, ConfigError>>>;
+
+// Removes the in-flight entry once the leading fetch completes, or is dropped
+// before completing (in which case waiters fall back to fetching themselves).
+// A leader started by a later refresh is left in place.
+struct InFlightGuard<'a> {
+    in_flight: &'a Mutex<HashMap<String, (u64, InFlightResult)>>,
+    secret_id: &'a str,
+    generation: u64,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if in_flight
+            .get(self.secret_id)
+            .is_some_and(|(generation, _)| *generation == self.generation)
+        {
+            in_flight.remove(self.secret_id);
+        }
+    }
+}
+
+impl<F: SecretsFetcher + 'static> CachedSecretsFetcher<F> {
+    pub fn new(fetcher: F, ttl: Duration) -> Self {
+        Self {
+            fetcher: Arc::new(fetcher),
+            ttl,
+            jitter: Duration::ZERO,
+            jitter_seed: RandomState::new(),
+            max_stale: None,
+            entries: Default::default(),
+            generations: Default::default(),
+            in_flight: Default::default(),
+            coalesced: AtomicU64::new(0),
+            refresh_failures: Default::default(),
+            #[cfg(feature = "disk-cache")]
+            disk: None,
+        }
+    }
+
+    pub fn ttl_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn stale_while_revalidate(mut self, max_stale: Duration) -> Self {
+        self.max_stale = Some(max_stale);
+        self
+    }
+
+    #[cfg(feature = "disk-cache")]
+    pub fn persist_to(mut self, path: impl Into<std::path::PathBuf>, key: DiskCacheKey) -> Self {
+        self.disk = Some(Arc::new(DiskCache::new(path.into(), key)));
+        self
+    }
+
+    pub fn fetcher(&self) -> &F {
+        &self.fetcher
+    }
+
+    // Time of the most recent successful fetch, across all cached secrets.
+    pub fn last_refresh(&self) -> Option<SystemTime> {
+        self.lock_entries()
+            .values()
+            .map(|entry| entry.fetched_at)
+            .max()
+    }
+
+    // Number of fetches that were served by waiting on a concurrent fetch of the
+    // same secret, rather than calling the inner fetcher.
+    pub fn coalesced_fetches(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+
+    // Number of background refreshes that failed (while the stale entry kept
+    // being served).
+    pub fn failed_refreshes(&self) -> u64 {
+        self.refresh_failures.count.load(Ordering::Relaxed)
+    }
+
+    // Error of the most recent background refresh, if it failed.
+    pub fn last_refresh_error(&self) -> Option<String> {
+        self.refresh_failures
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    // How long the stalest cached secret has been past its TTL (zero if all
+    // entries are fresh).
+    pub fn staleness(&self) -> Duration {
+        self.lock_entries()
+            .iter()
+            .map(|(secret_id, entry)| {
+                let age = entry.fetched_at.elapsed().unwrap_or_default();
+                age.saturating_sub(self.entry_ttl(secret_id, entry))
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    // IDs of the secrets currently cached.
+    pub fn cached_secret_ids(&self) -> Vec<String> {
+        self.lock_entries().keys().cloned().collect()
+    }
+
+    // Drop the cached copy of a secret, so the next fetch goes to the inner
+    // fetcher. Returns whether it was cached.
+    pub fn invalidate(&self, secret_id: &str) -> bool {
+        self.lock_entries().remove(secret_id).is_some()
+    }
+
+    // Invalidate and immediately refetch a secret (ex. after it was rotated,
+    // see rotation.rs). The refetch doesn't join a fetch that was already in
+    // flight (which may return the old value), and is the only fetch stored
+    // until it completes.
+    pub async fn refresh(&self, secret_id: &str) -> Result<(), ConfigError> {
+        // Bumped under the in-flight lock, so no fetch can start leading for
+        // the previous generation once the refresh has begun.
+        {
+            let _in_flight = self
+                .in_flight
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            *lock_generations(&self.generations)
+                .entry(secret_id.to_string())
+                .or_default() += 1;
+        }
+        self.invalidate(secret_id);
+        self.fetch_coalesced(secret_id).await.map(drop)
+    }
+
+    fn entry_ttl(&self, secret_id: &str, entry: &CacheEntry) -> Duration {
+        if self.jitter.is_zero() {
+            return self.ttl;
+        }
+        let hash = self.jitter_seed.hash_one((secret_id, entry.fetched_at));
+        let jitter = (u128::from(hash) % self.jitter.as_nanos()) as u64;
+        self.ttl.saturating_sub(Duration::from_nanos(jitter))
+    }
+
+    fn lock_entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, CacheEntry>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Return the cached value if it can still be served, kicking off a
+    // background refresh if it is stale. Outside of a Tokio runtime (ex. under
+    // another executor), where there is nothing to refresh in the background
+    // on, stale entries are refreshed inline instead.
+    fn cached(&self, secret_id: &str) -> Option<String> {
+        let mut entries = self.lock_entries();
+        let entry = entries.get_mut(secret_id)?;
+        let age = entry.fetched_at.elapsed().unwrap_or_default();
+        let ttl = self.entry_ttl(secret_id, entry);
+        if age < ttl {
+            return Some(entry.value.clone());
+        }
+        let max_stale = self.max_stale?;
+        if age >= ttl + max_stale {
+            return None;
+        }
+        if !entry.refreshing {
+            if !self.spawn_refresh(secret_id.to_string()) {
+                return None;
+            }
+            entry.refreshing = true;
+        }
+        Some(entry.value.clone())
+    }
+
+    fn spawn_refresh(&self, secret_id: String) -> bool {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return false;
+        };
+        let generation = current_generation(&self.generations, &secret_id);
+        let fetcher = self.fetcher.clone();
+        let entries = self.entries.clone();
+        let generations = self.generations.clone();
+        let refresh_failures = self.refresh_failures.clone();
+        #[cfg(feature = "disk-cache")]
+        let disk = self.disk.clone();
+        runtime.spawn(async move {
+            let result = fetcher.fetch_secret_string(&secret_id).await;
+            refresh_failures.record(&result);
+            {
+                let mut locked = entries.lock().unwrap_or_else(PoisonError::into_inner);
+                let Ok(value) = result else {
+                    if let Some(entry) = locked.get_mut(&secret_id) {
+                        entry.refreshing = false;
+                    }
+                    return;
+                };
+                if current_generation(&generations, &secret_id) != generation {
+                    return;
+                }
+                store(&mut locked, secret_id, value);
+            }
+            #[cfg(feature = "disk-cache")]
+            persist(disk.as_deref(), &entries).await;
+        });
+        true
+    }
+
+    async fn fetch_coalesced(&self, secret_id: &str) -> Result<String, ConfigError> {
+        let (generation, leader) = {
+            let mut in_flight = self
+                .in_flight
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let generation = current_generation(&self.generations, secret_id);
+            match in_flight.get(secret_id) {
+                Some((leader_generation, receiver)) if *leader_generation == generation => {
+                    (generation, Err(receiver.clone()))
+                }
+                // No fetch in flight, or only one started before a refresh.
+                _ => {
+                    let (sender, receiver) = watch::channel(None);
+                    in_flight.insert(secret_id.to_string(), (generation, receiver));
+                    (generation, Ok(sender))
+                }
+            }
+        };
+        match leader {
+            Ok(sender) => {
+                let _guard = InFlightGuard {
+                    in_flight: &self.in_flight,
+                    secret_id,
+                    generation,
+                };
+                let result = self.fetch_and_store(secret_id, generation).await;
+                let _ = sender.send(Some(result.clone()));
+                result
+            }
+            Err(mut receiver) => {
+                self.coalesced.fetch_add(1, Ordering::Relaxed);
+                let shared = receiver
+                    .wait_for(Option::is_some)
+                    .await
+                    .ok()
+                    .and_then(|result| result.clone());
+                match shared {
+                    Some(Ok(value)) => Ok(value),
+                    Some(Err(e)) => Err(CoalescedSecretsFetchFailed::with_source(
+                        secret_id,
+                        &e.to_string(),
+                        e,
+                    )),
+                    // The leading fetch was dropped before completing.
+                    None => self.fetch_and_store(secret_id, generation).await,
+                }
+            }
+        }
+    }
+
+    // Fetch a secret, storing it only if no refresh was started since the
+    // given generation.
+    async fn fetch_and_store(
+        &self,
+        secret_id: &str,
+        generation: u64,
+    ) -> Result<String, ConfigError> {
+        let value = self.fetcher.fetch_secret_string(secret_id).await?;
+        {
+            let mut entries = self.lock_entries();
+            if current_generation(&self.generations, secret_id) != generation {
+                return Ok(value);
+            }
+            store(&mut entries, secret_id.to_string(), value.clone());
+        }
+        #[cfg(feature = "disk-cache")]
+        persist(self.disk.as_deref(), &self.entries).await;
+        Ok(value)
+    }
+
+    #[cfg(feature = "disk-cache")]
+    async fn load_from_disk(&self) {
+        let Some(disk) = self.disk.as_ref().filter(|disk| disk.should_load()) else {
+            return;
+        };
+        if let Ok(Some(persisted)) = disk.load().await {
+            let mut entries = self.lock_entries();
+            for (secret_id, (value, fetched_at)) in persisted {
+                entries.entry(secret_id).or_insert(CacheEntry {
+                    value,
+                    fetched_at,
+                    refreshing: false,
+                });
+            }
+        }
+    }
+}
+
+// Persisting is best-effort, a failed write only costs a round trip on the next
+// cold start.
+#[cfg(feature = "disk-cache")]
+async fn persist(disk: Option<&DiskCache>, entries: &Mutex<HashMap<String, CacheEntry>>) {
+    let Some(disk) = disk else {
+        return;
+    };
+    let persisted = entries
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .map(|(secret_id, entry)| (secret_id.clone(), (entry.value.clone(), entry.fetched_at)))
+        .collect();
+    let _ = disk.save(&persisted).await;
+}
+
+fn lock_generations(
+    generations: &Mutex<HashMap<String, u64>>,
+) -> std::sync::MutexGuard<'_, HashMap<String, u64>> {
+    generations.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Number of refreshes started for a secret.
+fn current_generation(generations: &Mutex<HashMap<String, u64>>, secret_id: &str) -> u64 {
+    lock_generations(generations)
+        .get(secret_id)
+        .copied()
+        .unwrap_or_default()
+}
+
+fn store(entries: &mut HashMap<String, CacheEntry>, secret_id: String, value: String) {
+    entries.insert(
+        secret_id,
+        CacheEntry {
+            value,
+            fetched_at: SystemTime::now(),
+            refreshing: false,
+        },
+    );
+}
+
+impl<F: SecretsFetcher + 'static> SecretsFetcher for CachedSecretsFetcher<F> {
+    async fn fetch_secret_string(&self, secret_id: &str) -> Result<String, ConfigError> {
+        #[cfg(feature = "disk-cache")]
+        self.load_from_disk().await;
+        if let Some(value) = self.cached(secret_id) {
+            return Ok(value);
+        }
+        self.fetch_coalesced(secret_id).await
+    }
+
+    // Binary secrets are passed through uncached.
+    async fn fetch_secret_binary(&self, secret_id: &str) -> Result<Vec<u8>, ConfigError> {
+        self.fetcher.fetch_secret_binary(secret_id).await
+    }
+
+    fn ttl(&self) -> Option<Duration> {
+        Some(self.ttl)
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
+
+    use tokio::sync::watch;
+
+    use crate::{
+        ConfigError, ErrorCode, FailedToFetchSecretsJson, FakeSecretsBackend, SecretsFetcher,
+    };
+
+    use super::{store, CachedSecretsFetcher};
+
+    fn backend() -> FakeSecretsBackend {
+        FakeSecretsBackend::new().with_secret("test-secret", "v1")
+    }
+
+    // Expiry is tested by backdating entries rather than by waiting, and
+    // background refreshes by waiting for them to complete.
+    fn backdate<F: SecretsFetcher>(cache: &CachedSecretsFetcher<F>, secret_id: &str, by: Duration) {
+        cache.lock_entries().get_mut(secret_id).unwrap().fetched_at = SystemTime::now() - by;
+    }
+
+    async fn refreshed<F: SecretsFetcher>(cache: &CachedSecretsFetcher<F>, secret_id: &str) {
+        while cache
+            .lock_entries()
+            .get(secret_id)
+            .is_some_and(|entry| entry.refreshing)
+        {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    // Holds its first fetch, of the value at the time it started, in flight
+    // until released.
+    struct HeldFetcher {
+        backend: FakeSecretsBackend,
+        started: watch::Sender<bool>,
+        release: Mutex<Option<watch::Receiver<bool>>>,
+    }
+
+    impl SecretsFetcher for HeldFetcher {
+        async fn fetch_secret_string(&self, secret_id: &str) -> Result<String, ConfigError> {
+            let value = self.backend.fetch_secret_string(secret_id).await;
+            let release = self.release.lock().unwrap().take();
+            if let Some(mut release) = release {
+                self.started.send_replace(true);
+                let _ = release.wait_for(|released| *released).await;
+            }
+            value
+        }
+
+        async fn fetch_secret_binary(&self, secret_id: &str) -> Result<Vec<u8>, ConfigError> {
+            self.backend.fetch_secret_binary(secret_id).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_serves_fresh_entries() {
+        let cache = CachedSecretsFetcher::new(backend(), Duration::from_secs(60));
+
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+        cache.fetcher().set_secret("test-secret", "v2");
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+        assert_eq!(cache.fetcher().fetch_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_ttl_jitter() {
+        let ttl = Duration::from_secs(3600);
+        let cache = CachedSecretsFetcher::new(FakeSecretsBackend::new(), ttl)
+            .ttl_jitter(Duration::from_secs(600));
+        for i in 0..10 {
+            let secret_id = format!("secret-{i}");
+            cache.fetcher().set_secret(&secret_id, "v1");
+            cache.fetch_secret_string(&secret_id).await.unwrap();
+        }
+
+        let entries = cache.lock_entries();
+        let ttls = entries
+            .iter()
+            .map(|(secret_id, entry)| cache.entry_ttl(secret_id, entry))
+            .collect::<Vec<_>>();
+        assert!(ttls
+            .iter()
+            .all(|entry_ttl| *entry_ttl <= ttl && *entry_ttl > ttl - Duration::from_secs(600)));
+        assert!(ttls.iter().any(|entry_ttl| *entry_ttl != ttls[0]));
+    }
+
+    #[tokio::test]
+    async fn test_cache_refetches_expired_entries_inline() {
+        let cache = CachedSecretsFetcher::new(backend(), Duration::ZERO);
+
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+        cache.fetcher().set_secret("test-secret", "v2");
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v2"
+        );
+        assert_eq!(cache.fetcher().fetch_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_coalesces_concurrent_fetches() {
+        let cache = Arc::new(CachedSecretsFetcher::new(
+            backend().with_latency(Duration::from_millis(50)),
+            Duration::from_secs(60),
+        ));
+        let fetch_all = |cache: &Arc<CachedSecretsFetcher<FakeSecretsBackend>>| {
+            (0..3)
+                .map(|_| {
+                    let cache = cache.clone();
+                    tokio::spawn(async move { cache.fetch_secret_string("test-secret").await })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for handle in fetch_all(&cache) {
+            assert_eq!(handle.await.unwrap().unwrap(), "v1");
+        }
+        assert_eq!(cache.fetcher().fetch_count(), 1);
+        assert_eq!(cache.coalesced_fetches(), 2);
+
+        let cache = Arc::new(CachedSecretsFetcher::new(
+            backend().with_latency(Duration::from_millis(50)),
+            Duration::from_secs(60),
+        ));
+        cache.fetcher().fail_next(1);
+        for handle in fetch_all(&cache) {
+            // Waiters get the leader's error as the source of their own.
+            let error = handle.await.unwrap().unwrap_err();
+            let original = match error.source() {
+                Some(source) => source.downcast_ref::<ConfigError>().unwrap(),
+                None => &error,
+            };
+            assert_eq!(original.error_code(), Some(FailedToFetchSecretsJson::CODE));
+        }
+        assert_eq!(cache.fetcher().fetch_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_ignores_fetch_started_before_rotation() {
+        let (started, mut has_started) = watch::channel(false);
+        let (release, held) = watch::channel(false);
+        let cache = Arc::new(CachedSecretsFetcher::new(
+            HeldFetcher {
+                backend: backend(),
+                started,
+                release: Mutex::new(Some(held)),
+            },
+            Duration::from_secs(60),
+        ));
+        let held_fetch = {
+            let cache = cache.clone();
+            tokio::spawn(async move { cache.fetch_secret_string("test-secret").await })
+        };
+        has_started.wait_for(|started| *started).await.unwrap();
+
+        // Rotated while the fetch of v1 is still in flight.
+        cache.fetcher().backend.set_secret("test-secret", "v2");
+        tokio::time::timeout(Duration::from_secs(5), cache.refresh("test-secret"))
+            .await
+            .expect("refresh should not wait on the held fetch")
+            .unwrap();
+        release.send_replace(true);
+        assert_eq!(held_fetch.await.unwrap().unwrap(), "v1");
+
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v2"
+        );
+        assert_eq!(cache.fetcher().backend.fetch_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stale_while_revalidate_serves_stale_entry() {
+        let cache = CachedSecretsFetcher::new(backend(), Duration::ZERO)
+            .stale_while_revalidate(Duration::from_secs(60));
+
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+        cache.fetcher().set_secret("test-secret", "v2");
+        cache.fetcher().fail_next(1);
+
+        // Stale value is served, and the failed refresh doesn't surface.
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+        refreshed(&cache, "test-secret").await;
+        assert_eq!(cache.failed_refreshes(), 1);
+        assert!(cache.last_refresh_error().is_some());
+
+        // The next fetch retries the refresh in the background.
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+        refreshed(&cache, "test-secret").await;
+        assert_eq!(cache.fetcher().fetch_count(), 3);
+        assert_eq!(cache.last_refresh_error(), None);
+
+        let cache = CachedSecretsFetcher::new(backend(), Duration::from_secs(60))
+            .stale_while_revalidate(Duration::from_secs(60));
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+        cache.fetcher().set_secret("test-secret", "v2");
+        backdate(&cache, "test-secret", Duration::from_secs(90));
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+        refreshed(&cache, "test-secret").await;
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v2"
+        );
+    }
+
+    #[test]
+    fn test_stale_while_revalidate_outside_runtime() {
+        let cache = CachedSecretsFetcher::new(backend(), Duration::from_secs(60))
+            .stale_while_revalidate(Duration::from_secs(600));
+        store(
+            &mut cache.lock_entries(),
+            "test-secret".to_string(),
+            "v1".to_string(),
+        );
+        backdate(&cache, "test-secret", Duration::from_secs(120));
+
+        // No runtime to refresh on in the background: refetch inline.
+        assert_eq!(cache.cached("test-secret"), None);
+        assert!(!cache.lock_entries()["test-secret"].refreshing);
+    }
+
+    #[tokio::test]
+    async fn test_stale_while_revalidate_fails_beyond_max_stale() {
+        let cache = CachedSecretsFetcher::new(backend(), Duration::ZERO)
+            .stale_while_revalidate(Duration::ZERO);
+
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+        cache.fetcher().fail_next(1);
+        assert!(cache.fetch_secret_string("test-secret").await.is_err());
+    }
+
+    #[cfg(feature = "disk-cache")]
+    #[tokio::test]
+    async fn test_cache_persisted_to_disk() {
+        use crate::{DiskCacheKey, EnvOverrideScope};
+
+        let _guard = EnvOverrideScope::new()
+            .set(
+                "CACHE_DISK_TEST_KEY",
+                "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+            )
+            .apply();
+        let path = std::env::temp_dir().join("fractic_env_config_cache_persisted");
+        let _ = std::fs::remove_file(&path);
+        let key = DiskCacheKey::from_env("CACHE_DISK_TEST_KEY");
+
+        let cache = CachedSecretsFetcher::new(backend(), Duration::from_secs(60))
+            .persist_to(&path, key.clone());
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+
+        // A new cache (ex. after a restart) is served from disk.
+        let cache =
+            CachedSecretsFetcher::new(backend(), Duration::from_secs(60)).persist_to(&path, key);
+        assert_eq!(
+            cache.fetch_secret_string("test-secret").await.unwrap(),
+            "v1"
+        );
+        assert_eq!(cache.fetcher().fetch_count(), 0);
+    }
+}
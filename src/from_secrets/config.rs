@@ -1,14 +1,30 @@
-use aws_config::BehaviorVersion;
-use aws_sdk_secretsmanager::{config::Region, Client};
-use fractic_server_error::{CriticalError, ServerError};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt;
 use std::marker::PhantomData;
+use std::panic::Location;
+use std::time::SystemTime;
 
-use crate::{define_env_config, EnvConfigEnum, EnvVariables, SECRETS_ID, SECRETS_REGION};
+use crate::from_env::metadata::LoadMetadata;
+use crate::from_env::values::decode_base64;
+use crate::ConfigError;
+use crate::{
+    define_env_config, load_from_source, ConfigSource, EnvConfigEnum, EnvVariables, ProcessEnv,
+    Secret, SECRETS_ID, SECRETS_REGION,
+};
 
+use super::audit::record_access;
+#[cfg(feature = "aws")]
+use super::aws::AwsSecretsFetcher;
+#[cfg(feature = "bundle")]
+use super::bundle::fetch_bundle;
 use super::errors::{
-    FailedToFetchSecretsJson, InvalidSecretsCloneInto, MissingSecretKey, SecretsInvalidJson,
+    InvalidSecretValueType, InvalidSecretsCloneInto, MissingSecretKey, MissingSecretValue,
+    UnresolvedSecretsIdPlaceholder,
 };
+use super::fetcher::SecretsFetcher;
+use super::format::{decode_secret_string, SecretFormat};
+use super::spec::SecretKeySpec;
 
 define_env_config!(
     SecretsEnvConfig,
@@ -16,6 +32,40 @@ define_env_config!(
     SecretsId => SECRETS_ID,
 );
 
+// The secrets are located by the SECRETS_REGION and SECRETS_ID variables.
+// Services sharing a host (and so its environment) can each read their own
+// variables instead, by passing their own config to load_secrets:
+//
+// define_env_config!(
+//     BillingSecretsEnv,
+//     Region => BILLING_SECRETS_REGION,
+//     Id => BILLING_SECRETS_ID,
+// );
+//
+// impl SecretsEnvKeys for BillingSecretsEnv {
+//     fn region() -> Self {
+//         Self::Region
+//     }
+//     fn id() -> Self {
+//         Self::Id
+//     }
+// }
+//
+// let secrets = load_secrets::<SecretsConfig>(load_env::<BillingSecretsEnv>()?).await?;
+pub trait SecretsEnvKeys: EnvConfigEnum {
+    fn region() -> Self;
+    fn id() -> Self;
+}
+
+impl SecretsEnvKeys for SecretsEnvConfig {
+    fn region() -> Self {
+        SecretsEnvConfig::SecretsRegion
+    }
+    fn id() -> Self {
+        SecretsEnvConfig::SecretsId
+    }
+}
+
 // Secrets configuration.
 // --------------------------------------------------
 
@@ -27,11 +77,64 @@ define_env_config!(
 //     SecretsConfig,
 //     OpenAIKey => OPENAI_KEY,
 // );
+//
+// Keys of structured secrets can be declared by their dotted path instead, and
+// are resolved through the nested JSON objects of the secret payload:
+//
+// DbPassword => "database.credentials.password",
+//
+// If the payload has a top-level key literally containing the dots, it is used
+// instead.
+//
+// Keys can declare options on how their value is read (see spec.rs).
+//
+// Secrets Manager limits secret values to 64KB, so large payloads can be
+// sharded across several secrets named <SECRETS_ID>-part1 to -partN:
+//
+// define_secrets_config!(
+//     SecretsConfig { parts = 3 },
+//     OpenAIKey => OPENAI_KEY,
+// );
+//
+// load_secrets then fetches all parts. If the first part is a complete JSON
+// object, each part is expected to be one and they are merged (later parts
+// taking precedence on duplicate keys). Otherwise the parts are treated as
+// chunks of a single payload, and concatenated before parsing.
+//
+// Secrets that aren't JSON objects can declare their format instead (see
+// format.rs):
+//
+// define_secrets_config!(
+//     SecretsConfig { format = Plaintext },
+//     DbPassword => DB_PASSWORD,
+// );
 pub trait SecretsConfigEnum:
     std::fmt::Debug + PartialEq + Eq + core::hash::Hash + Clone + Send + Sync
 {
     fn as_str(&self) -> &'static str;
     fn value_list() -> Vec<Self>;
+
+    fn spec(&self) -> SecretKeySpec {
+        SecretKeySpec::new(self.as_str())
+    }
+
+    fn parts() -> Option<usize> {
+        None
+    }
+
+    fn format() -> SecretFormat {
+        SecretFormat::Json
+    }
+
+    #[cfg(feature = "bundle")]
+    fn bundle() -> bool {
+        false
+    }
+
+    // See dual_key.rs.
+    fn rotation_pairs() -> Vec<(Self, Self)> {
+        Vec::new()
+    }
 }
 
 // Similar to EnvVariables, fetch all secret values by running:
@@ -41,62 +144,349 @@ pub trait SecretsConfigEnum:
 // The SecretValues object owns the data.
 //
 // This object is now guaranteed to have all the secret values for all keys in
-// the SecretsConfig.
-#[derive(Debug, Clone)]
-pub struct SecretValues<T: SecretsConfigEnum>(HashMap<&'static str, String>, PhantomData<T>);
+// the SecretsConfig, until lockdown is called (see below).
+//
+// With the "hardened" feature, the values are additionally kept in locked,
+// non-swappable memory pages (see hardened.rs). Debug output only lists the
+// keys.
+pub struct SecretValues<T: SecretsConfigEnum>(
+    pub(crate) HashMap<&'static str, String>,
+    PhantomData<T>,
+    pub(crate) SecretState,
+    pub(crate) LoadMetadata,
+);
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SecretState {
+    pub(crate) locked_down: bool,
+    #[cfg(feature = "hardened")]
+    pub(crate) memory_locked: bool,
+}
 impl<T: SecretsConfigEnum> SecretValues<T> {
-    pub fn get(&self, key: &T) -> Result<&String, ServerError> {
+    fn new(map: HashMap<&'static str, String>) -> Self {
+        let secrets = SecretValues(
+            map,
+            PhantomData,
+            SecretState::default(),
+            LoadMetadata::new("map"),
+        );
+        #[cfg(feature = "hardened")]
+        let secrets = secrets.with_locked_memory();
+        secrets
+    }
+    // Reads are recorded by the secret auditor, if one is set (see audit.rs).
+    #[track_caller]
+    pub fn get(&self, key: &T) -> Result<&str, ConfigError> {
+        record_access(key.as_str(), Location::caller());
+        self.get_raw(key.as_str()).map(String::as_str)
+    }
+    #[track_caller]
+    pub fn get_cloned(&self, key: &T) -> Result<String, ConfigError> {
+        self.get(key).map(str::to_string)
+    }
+    #[track_caller]
+    #[deprecated(note = "use get, which returns &str")]
+    pub fn get_string(&self, key: &T) -> Result<&String, ConfigError> {
+        record_access(key.as_str(), Location::caller());
         self.get_raw(key.as_str())
     }
-    fn get_raw(&self, key: &str) -> Result<&String, ServerError> {
-        self.0.get(key).ok_or(CriticalError::new(
+    #[track_caller]
+    pub fn get_secret(&self, key: &T) -> Result<Secret<String>, ConfigError> {
+        self.get_cloned(key).map(Secret::new)
+    }
+    #[track_caller]
+    pub fn get_base64(&self, key: &T) -> Result<Vec<u8>, ConfigError> {
+        decode_base64(key.as_str(), self.get(key)?)
+    }
+    // For services that derive session material at startup, and want to
+    // guarantee the raw secrets can't be touched afterwards:
+    //
+    // let signer = Signer::new(secrets.get(&SecretsConfig::SigningKey)?);
+    // secrets.lockdown();
+    //
+    // The values are overwritten and dropped, and any later access (get,
+    // clone_into, etc.) panics. Irreversible. Clones made before the lockdown
+    // are unaffected.
+    pub fn lockdown(&mut self) {
+        #[cfg(feature = "hardened")]
+        self.unlock_memory();
+        for (_, value) in self.0.drain() {
+            let mut bytes = value.into_bytes();
+            bytes.fill(0);
+            std::hint::black_box(&bytes);
+        }
+        self.2.locked_down = true;
+    }
+    pub fn is_locked_down(&self) -> bool {
+        self.2.locked_down
+    }
+    // See metadata.rs.
+    pub fn loaded_at(&self) -> SystemTime {
+        self.3.loaded_at
+    }
+    pub fn source(&self) -> &str {
+        &self.3.source
+    }
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.3.expires_at
+    }
+    pub(crate) fn with_metadata(mut self, metadata: LoadMetadata) -> Self {
+        self.3 = metadata;
+        self
+    }
+    pub(super) fn get_raw(&self, key: &str) -> Result<&String, ConfigError> {
+        if self.2.locked_down {
+            panic!("Secret '{key}' accessed after SecretValues::lockdown.");
+        }
+        self.0.get(key).ok_or(ConfigError::critical(
             &format!("Should be guaranteed any secret key SecretsConfig::key is present in SecretValues<SecretsConfig>, but SecretsConfig::{key} is missing."),
         ))
     }
 }
+// Clones hold their own copies of the values, which are locked separately.
+impl<T: SecretsConfigEnum> Clone for SecretValues<T> {
+    fn clone(&self) -> Self {
+        let mut clone = SecretValues::new(self.0.clone()).with_metadata(self.3.clone());
+        clone.2.locked_down = self.2.locked_down;
+        clone
+    }
+}
+impl<T: SecretsConfigEnum> fmt::Debug for SecretValues<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut keys = self.0.keys().collect::<Vec<_>>();
+        keys.sort();
+        f.debug_struct("SecretValues")
+            .field("keys", &keys)
+            .field("state", &self.2)
+            .field("metadata", &self.3)
+            .finish()
+    }
+}
+#[cfg(feature = "aws")]
 pub async fn load_secrets<T: SecretsConfigEnum>(
-    env: EnvVariables<SecretsEnvConfig>,
-) -> Result<SecretValues<T>, ServerError> {
-    let region_str = env.get(&SecretsEnvConfig::SecretsRegion)?;
-    let region = Region::new(region_str.clone());
-    let shared_config = aws_config::defaults(BehaviorVersion::v2024_03_28())
-        .region(region)
-        .load()
-        .await;
-    let client = Client::new(&shared_config);
+    env: EnvVariables<impl SecretsEnvKeys>,
+) -> Result<SecretValues<T>, ConfigError> {
+    let fetcher = AwsSecretsFetcher::new(secrets_region(&env)?).await;
+    load_secrets_with(env, &fetcher).await
+}
+#[cfg(feature = "aws")]
+pub async fn load_secrets_with_sdk_config<T: SecretsConfigEnum>(
+    env: EnvVariables<impl SecretsEnvKeys>,
+    sdk_config: &aws_config::SdkConfig,
+) -> Result<SecretValues<T>, ConfigError> {
+    let fetcher = AwsSecretsFetcher::from_sdk_config(secrets_region(&env)?, sdk_config);
+    load_secrets_with(env, &fetcher).await
+}
+pub async fn load_secrets_with<T: SecretsConfigEnum>(
+    env: EnvVariables<impl SecretsEnvKeys>,
+    fetcher: &impl SecretsFetcher,
+) -> Result<SecretValues<T>, ConfigError> {
+    load_secrets_resolving(&env, fetcher, &ProcessEnv::new()).await
+}
+// Same as load_secrets_with, with the secrets region and ID read from the given
+// source rather than from the process environment (ex. for embedders that
+// collect the environment themselves):
+//
+// let secrets =
+//     load_secrets_with_env_source::<SecretsConfig, SecretsEnvConfig>(&source, &fetcher).await?;
+//
+// Placeholders in the secret ID are resolved from the same source.
+pub async fn load_secrets_with_env_source<T: SecretsConfigEnum, K: SecretsEnvKeys>(
+    source: &impl ConfigSource,
+    fetcher: &impl SecretsFetcher,
+) -> Result<SecretValues<T>, ConfigError> {
+    let env = load_from_source::<K>(source)?;
+    load_secrets_resolving(&env, fetcher, source).await
+}
+async fn load_secrets_resolving<T: SecretsConfigEnum, K: SecretsEnvKeys>(
+    env: &EnvVariables<K>,
+    fetcher: &impl SecretsFetcher,
+    placeholders: &impl ConfigSource,
+) -> Result<SecretValues<T>, ConfigError> {
+    let mut map = HashMap::new();
+    let secrets_id = read_secrets::<T, _>(env, fetcher, placeholders, |key, value| {
+        map.insert(key, value);
+    })
+    .await?;
+    let metadata = LoadMetadata::new(format!("secrets:{secrets_id}")).with_ttl(fetcher.ttl());
+    Ok(SecretValues::new(map).with_metadata(metadata))
+}
+// Dry run of load_secrets, for pre-flight checks (ex. admission webhooks or CI
+// deploy gates) where the secrets shouldn't be resident in the checking
+// process:
+//
+// validate_secrets::<SecretsConfig>(env).await?;
+//
+// Checks that the secret can be fetched and has every key, with a value of the
+// right type. Each value is discarded as soon as it has been checked, and no
+// SecretValues is created.
+#[cfg(feature = "aws")]
+pub async fn validate_secrets<T: SecretsConfigEnum>(
+    env: EnvVariables<impl SecretsEnvKeys>,
+) -> Result<(), ConfigError> {
+    let fetcher = AwsSecretsFetcher::new(secrets_region(&env)?).await;
+    validate_secrets_with::<T>(env, &fetcher).await
+}
+pub async fn validate_secrets_with<T: SecretsConfigEnum>(
+    env: EnvVariables<impl SecretsEnvKeys>,
+    fetcher: &impl SecretsFetcher,
+) -> Result<(), ConfigError> {
+    read_secrets::<T, _>(&env, fetcher, &ProcessEnv::new(), |_, _| {}).await?;
+    Ok(())
+}
+#[cfg(feature = "aws")]
+fn secrets_region<K: SecretsEnvKeys>(env: &EnvVariables<K>) -> Result<&str, ConfigError> {
+    env.get(&K::region())
+}
+// Returns the resolved secrets ID.
+async fn read_secrets<T: SecretsConfigEnum, K: SecretsEnvKeys>(
+    env: &EnvVariables<K>,
+    fetcher: &impl SecretsFetcher,
+    placeholders: &impl ConfigSource,
+    mut store: impl FnMut(&'static str, String),
+) -> Result<String, ConfigError> {
+    let region_str = env.get(&K::region())?;
 
     // Fetch secrets JSON.
-    let secrets_id = env.get(&SecretsEnvConfig::SecretsId)?;
-    let secrets_output = client
-        .get_secret_value()
-        .secret_id(secrets_id)
-        .send()
-        .await
-        .map_err(|e| FailedToFetchSecretsJson::with_debug(secrets_id, region_str, &e))?;
-    let secrets_string = secrets_output.secret_string().ok_or_else(|| {
-        CriticalError::new(&format!(
-            "Could not parse secret value. SecretsId: {}; Region: {};",
-            secrets_id, region_str
-        ))
-    })?;
-    let secrets_json = serde_json::from_str::<HashMap<String, String>>(secrets_string)
-        .map_err(|e| SecretsInvalidJson::with_debug(secrets_id, region_str, &e))?;
+    let secrets_id = &resolve_placeholders(env.get(&K::id())?, placeholders)?;
+    #[cfg(any(test, feature = "testing"))]
+    if let Some(delay) = crate::chaos::fetch_delay() {
+        tokio::time::sleep(delay).await;
+    }
+    #[cfg(feature = "bundle")]
+    let secrets_json = if T::bundle() {
+        fetch_bundle(fetcher, secrets_id, region_str).await?
+    } else {
+        fetch_secrets_json::<T>(fetcher, secrets_id, region_str).await?
+    };
+    #[cfg(not(feature = "bundle"))]
+    let secrets_json = fetch_secrets_json::<T>(fetcher, secrets_id, region_str).await?;
 
     // Fetch required keys from JSON.
-    let mut map = HashMap::new();
     for field in T::value_list() {
-        let secret_value = secrets_json
-            .get(field.as_str())
-            .ok_or(MissingSecretKey::new(
+        #[cfg(any(test, feature = "testing"))]
+        if crate::chaos::is_key_failing(field.as_str()) {
+            return Err(crate::InjectedConfigFailure::new(field.as_str()));
+        }
+        let secret_value = lookup_secret_path(&secrets_json, field.as_str()).ok_or(
+            MissingSecretKey::new(secrets_id, region_str, field.as_str()),
+        )?;
+        let secret_value = secret_value_to_string(&field.spec(), secret_value).map_err(|kind| {
+            InvalidSecretValueType::new(secrets_id, region_str, field.as_str(), kind)
+        })?;
+        store(field.as_str(), secret_value);
+    }
+    Ok(secrets_id.clone())
+}
+
+async fn fetch_secrets_json<T: SecretsConfigEnum>(
+    fetcher: &impl SecretsFetcher,
+    secrets_id: &str,
+    region: &str,
+) -> Result<HashMap<String, Value>, ConfigError> {
+    let parse = |secret_id: &str, secrets_string: &str| {
+        decode_secret_string::<T>(secret_id, region, secrets_string)
+    };
+    let Some(parts) = T::parts() else {
+        return parse(secrets_id, &fetcher.fetch_secret_string(secrets_id).await?);
+    };
+
+    let mut part_strings = Vec::with_capacity(parts);
+    for part in 1..=parts {
+        let part_id = format!("{secrets_id}-part{part}");
+        part_strings.push((fetcher.fetch_secret_string(&part_id).await?, part_id));
+    }
+    let first_is_object = T::format() == SecretFormat::Json
+        && part_strings.first().is_some_and(|(first, _)| {
+            serde_json::from_str::<HashMap<String, Value>>(first).is_ok()
+        });
+    if first_is_object {
+        let mut merged = HashMap::new();
+        for (part_string, part_id) in &part_strings {
+            merged.extend(parse(part_id, part_string)?);
+        }
+        Ok(merged)
+    } else {
+        let concatenated = part_strings
+            .iter()
+            .map(|(part_string, _)| part_string.as_str())
+            .collect::<String>();
+        parse(secrets_id, &concatenated)
+    }
+}
+
+fn lookup_secret_path<'a>(json: &'a HashMap<String, Value>, key: &str) -> Option<&'a Value> {
+    if let Some(value) = json.get(key) {
+        return Some(value);
+    }
+    let mut segments = key.split('.');
+    let mut value = json.get(segments.next()?)?;
+    for segment in segments {
+        value = value.as_object()?.get(segment)?;
+    }
+    Some(value)
+}
+
+// Scalars are read as their JSON text, objects and arrays only if the key is
+// declared JSON-typed. Otherwise returns the kind of the offending value.
+fn secret_value_to_string(spec: &SecretKeySpec, value: &Value) -> Result<String, &'static str> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        Value::Null => Err("null"),
+        Value::Object(_) | Value::Array(_) if spec.is_json() => Ok(value.to_string()),
+        Value::Object(_) => Err("an object"),
+        Value::Array(_) => Err("an array"),
+    }
+}
+
+// SECRETS_ID can contain placeholders resolved from other env variables, so a
+// single SecretsEnvConfig works across stages:
+//
+// SECRETS_ID=myapp/${STAGE}/main
+fn resolve_placeholders(
+    secrets_id: &str,
+    source: &impl ConfigSource,
+) -> Result<String, ConfigError> {
+    let mut resolved = String::new();
+    let mut rest = secrets_id;
+    while let Some(start) = rest.find("${") {
+        resolved.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err(UnresolvedSecretsIdPlaceholder::new(
                 secrets_id,
-                region_str,
-                field.as_str(),
-            ))?
-            .as_str()
-            .into();
+                &rest[start..],
+            ));
+        };
+        let var = &rest[start + 2..start + end];
+        let value = source
+            .get(var)?
+            .ok_or_else(|| UnresolvedSecretsIdPlaceholder::new(secrets_id, var))?;
+        resolved.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+// Secrets can also be read from any ConfigSource (see from_env/source.rs), with
+// each secret key looked up by name. Used for sources holding individual
+// secrets rather than a single JSON blob, such as the OS keychain.
+pub fn load_secrets_from_source<T: SecretsConfigEnum>(
+    source: &impl ConfigSource,
+) -> Result<SecretValues<T>, ConfigError> {
+    let mut map = HashMap::new();
+    for field in T::value_list() {
+        #[cfg(any(test, feature = "testing"))]
+        if crate::chaos::is_key_failing(field.as_str()) {
+            return Err(crate::InjectedConfigFailure::new(field.as_str()));
+        }
+        let secret_value = source
+            .get(field.as_str())?
+            .ok_or_else(|| MissingSecretValue::new(field.as_str()))?;
         map.insert(field.as_str(), secret_value);
     }
-    Ok(SecretValues(map.into(), PhantomData))
+    Ok(SecretValues::new(map).with_metadata(LoadMetadata::new(source.name())))
 }
 
 // For tests, let a SecretValues structure be easily made from a HashMap.
@@ -109,7 +499,7 @@ where
     U: Into<HashMap<&'static str, String>>,
 {
     fn from(map: U) -> Self {
-        SecretValues(map.into(), PhantomData)
+        SecretValues::new(map.into())
     }
 }
 
@@ -118,7 +508,7 @@ where
 impl<ParentConfig: SecretsConfigEnum> SecretValues<ParentConfig> {
     pub fn clone_into<ChildConfig: SecretsConfigEnum>(
         &self,
-    ) -> Result<SecretValues<ChildConfig>, ServerError> {
+    ) -> Result<SecretValues<ChildConfig>, ConfigError> {
         let mut map = HashMap::new();
         for value in ChildConfig::value_list() {
             let key_as_str = value.as_str();
@@ -133,11 +523,404 @@ impl<ParentConfig: SecretsConfigEnum> SecretValues<ParentConfig> {
             })?;
             map.insert(key_as_str, secret_value.clone());
         }
-        Ok(SecretValues(map, PhantomData))
+        Ok(SecretValues::new(map).with_metadata(self.3.clone()))
     }
 }
 
 // Tests.
 // --------------------------------------------------
 
-// TODO
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        define_env_config, define_env_variable, define_secret_key, define_secrets_config,
+        from_secrets::config::{
+            load_secrets_from_source, load_secrets_with, load_secrets_with_env_source,
+            validate_secrets_with,
+        },
+        ChaosScope, EnvConfigEnum, EnvVariables, FakeSecretsBackend, FileSource, Format,
+        SecretValues, SecretsConfigEnum, SECRETS_ID, SECRETS_REGION,
+    };
+
+    use super::{resolve_placeholders, SecretsEnvConfig, SecretsEnvKeys};
+
+    define_secret_key!(OPENAI_KEY);
+    define_secret_key!(STRIPE_KEY);
+
+    define_secrets_config!(
+        AllSecretsConfig,
+        OpenAIKey => OPENAI_KEY,
+        StripeKey => STRIPE_KEY,
+    );
+
+    define_secrets_config!(
+        OpenAIOnlyConfig,
+        OpenAIKey => OPENAI_KEY,
+    );
+
+    define_secret_key!(DB_PORT);
+    define_secret_key!(OAUTH_CLIENT);
+
+    define_secrets_config!(
+        TypedSecretsConfig,
+        DbPort => DB_PORT,
+        OAuthClient => OAUTH_CLIENT { json = true },
+    );
+
+    fn secrets_env() -> EnvVariables<SecretsEnvConfig> {
+        let map: HashMap<&'static str, String> = [
+            (SECRETS_REGION, String::from("us-west-2")),
+            (SECRETS_ID, String::from("test-secret")),
+        ]
+        .into();
+        EnvVariables::from(map)
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_valid() {
+        let backend = FakeSecretsBackend::new().with_secret(
+            "test-secret",
+            r#"{"OPENAI_KEY": "abc123", "STRIPE_KEY": "sk_test"}"#,
+        );
+
+        let secrets = load_secrets_with::<AllSecretsConfig>(secrets_env(), &backend)
+            .await
+            .unwrap();
+        assert_eq!(secrets.get(&AllSecretsConfig::OpenAIKey).unwrap(), "abc123");
+        assert_eq!(
+            secrets.get(&AllSecretsConfig::StripeKey).unwrap(),
+            "sk_test"
+        );
+        assert_eq!(backend.fetch_count(), 1);
+        assert_eq!(secrets.source(), "secrets:test-secret");
+        assert_eq!(secrets.expires_at(), None);
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_custom_env_keys() {
+        define_env_variable!(BILLING_SECRETS_REGION);
+        define_env_variable!(BILLING_SECRETS_ID);
+        define_env_config!(
+            BillingSecretsEnv,
+            Region => BILLING_SECRETS_REGION,
+            Id => BILLING_SECRETS_ID,
+        );
+        impl SecretsEnvKeys for BillingSecretsEnv {
+            fn region() -> Self {
+                Self::Region
+            }
+            fn id() -> Self {
+                Self::Id
+            }
+        }
+
+        let backend =
+            FakeSecretsBackend::new().with_secret("billing-secret", r#"{"OPENAI_KEY": "abc123"}"#);
+        let env = EnvVariables::<BillingSecretsEnv>::from(HashMap::from([
+            (BILLING_SECRETS_REGION, String::from("us-west-2")),
+            (BILLING_SECRETS_ID, String::from("billing-secret")),
+        ]));
+        let secrets = load_secrets_with::<OpenAIOnlyConfig>(env, &backend)
+            .await
+            .unwrap();
+        assert_eq!(secrets.get(&OpenAIOnlyConfig::OpenAIKey).unwrap(), "abc123");
+        assert_eq!(secrets.source(), "secrets:billing-secret");
+
+        // Placeholders are resolved from the source the keys were read from.
+        let source = HashMap::from([
+            (
+                String::from("BILLING_SECRETS_REGION"),
+                String::from("us-west-2"),
+            ),
+            (
+                String::from("BILLING_SECRETS_ID"),
+                String::from("${BILLING_STAGE}-secret"),
+            ),
+            (String::from("BILLING_STAGE"), String::from("billing")),
+        ]);
+        let secrets =
+            load_secrets_with_env_source::<OpenAIOnlyConfig, BillingSecretsEnv>(&source, &backend)
+                .await
+                .unwrap();
+        assert_eq!(secrets.source(), "secrets:billing-secret");
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_missing_key() {
+        let backend =
+            FakeSecretsBackend::new().with_secret("test-secret", r#"{"OPENAI_KEY": "abc123"}"#);
+
+        assert!(
+            load_secrets_with::<OpenAIOnlyConfig>(secrets_env(), &backend)
+                .await
+                .is_ok()
+        );
+        assert!(
+            load_secrets_with::<AllSecretsConfig>(secrets_env(), &backend)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_missing_secret() {
+        let backend = FakeSecretsBackend::new();
+        let result = load_secrets_with::<OpenAIOnlyConfig>(secrets_env(), &backend).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_non_string_values() {
+        let backend = FakeSecretsBackend::new().with_secret(
+            "test-secret",
+            r#"{"DB_PORT": 5432, "OAUTH_CLIENT": {"id": "abc"}, "OPENAI_KEY": ["sk-1"]}"#,
+        );
+
+        let secrets = load_secrets_with::<TypedSecretsConfig>(secrets_env(), &backend)
+            .await
+            .unwrap();
+        assert_eq!(secrets.get(&TypedSecretsConfig::DbPort).unwrap(), "5432");
+        assert_eq!(
+            secrets.get(&TypedSecretsConfig::OAuthClient).unwrap(),
+            r#"{"id":"abc"}"#
+        );
+
+        let error = load_secrets_with::<OpenAIOnlyConfig>(secrets_env(), &backend)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("OPENAI_KEY"));
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_dotted_path() {
+        define_secrets_config!(
+            NestedSecretsConfig,
+            DbPassword => "database.credentials.password",
+            FlatKey => "flat.key",
+        );
+
+        let backend = FakeSecretsBackend::new().with_secret(
+            "test-secret",
+            r#"{"database": {"credentials": {"password": "hunter2"}}, "flat.key": "abc"}"#,
+        );
+        let secrets = load_secrets_with::<NestedSecretsConfig>(secrets_env(), &backend)
+            .await
+            .unwrap();
+        assert_eq!(
+            secrets.get(&NestedSecretsConfig::DbPassword).unwrap(),
+            "hunter2"
+        );
+        assert_eq!(secrets.get(&NestedSecretsConfig::FlatKey).unwrap(), "abc");
+
+        let backend = FakeSecretsBackend::new()
+            .with_secret("test-secret", r#"{"database": {"credentials": "hunter2"}}"#);
+        let error = load_secrets_with::<NestedSecretsConfig>(secrets_env(), &backend)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("database.credentials.password"));
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_parts() {
+        define_secrets_config!(
+            ShardedSecretsConfig { parts = 2 },
+            OpenAIKey => OPENAI_KEY,
+            StripeKey => STRIPE_KEY,
+        );
+
+        let backend = FakeSecretsBackend::new()
+            .with_secret("test-secret-part1", r#"{"OPENAI_KEY": "abc123"}"#)
+            .with_secret("test-secret-part2", r#"{"STRIPE_KEY": "sk_test"}"#);
+        let secrets = load_secrets_with::<ShardedSecretsConfig>(secrets_env(), &backend)
+            .await
+            .unwrap();
+        assert_eq!(
+            secrets.get(&ShardedSecretsConfig::OpenAIKey).unwrap(),
+            "abc123"
+        );
+        assert_eq!(
+            secrets.get(&ShardedSecretsConfig::StripeKey).unwrap(),
+            "sk_test"
+        );
+        assert_eq!(backend.fetch_count(), 2);
+
+        let backend = FakeSecretsBackend::new()
+            .with_secret("test-secret-part1", r#"{"OPENAI_KEY": "abc"#)
+            .with_secret("test-secret-part2", r#"123", "STRIPE_KEY": "sk_test"}"#);
+        let secrets = load_secrets_with::<ShardedSecretsConfig>(secrets_env(), &backend)
+            .await
+            .unwrap();
+        assert_eq!(
+            secrets.get(&ShardedSecretsConfig::OpenAIKey).unwrap(),
+            "abc123"
+        );
+
+        let backend = FakeSecretsBackend::new()
+            .with_secret("test-secret-part1", r#"{"OPENAI_KEY": "abc123"}"#);
+        assert!(
+            load_secrets_with::<ShardedSecretsConfig>(secrets_env(), &backend)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_secrets() {
+        let backend =
+            FakeSecretsBackend::new().with_secret("test-secret", r#"{"OPENAI_KEY": "abc123"}"#);
+
+        assert!(
+            validate_secrets_with::<OpenAIOnlyConfig>(secrets_env(), &backend)
+                .await
+                .is_ok()
+        );
+        assert!(
+            validate_secrets_with::<AllSecretsConfig>(secrets_env(), &backend)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_invalid_json() {
+        let backend = FakeSecretsBackend::new().with_secret("test-secret", "not json");
+        let result = load_secrets_with::<OpenAIOnlyConfig>(secrets_env(), &backend).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_injected_failures() {
+        let backend =
+            FakeSecretsBackend::new().with_secret("test-secret", r#"{"OPENAI_KEY": "abc123"}"#);
+        backend.fail_next(2);
+
+        for _ in 0..2 {
+            assert!(
+                load_secrets_with::<OpenAIOnlyConfig>(secrets_env(), &backend)
+                    .await
+                    .is_err()
+            );
+        }
+        assert!(
+            load_secrets_with::<OpenAIOnlyConfig>(secrets_env(), &backend)
+                .await
+                .is_ok()
+        );
+        assert_eq!(backend.fetch_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_chaos_failing_key() {
+        define_secret_key!(CHAOS_TEST_KEY);
+        define_secrets_config!(
+            ChaosSecretsConfig,
+            OpenAIKey => OPENAI_KEY,
+            ChaosKey => CHAOS_TEST_KEY,
+        );
+
+        let backend = FakeSecretsBackend::new().with_secret(
+            "test-secret",
+            r#"{"OPENAI_KEY": "abc123", "CHAOS_TEST_KEY": "value"}"#,
+        );
+        assert!(
+            load_secrets_with::<ChaosSecretsConfig>(secrets_env(), &backend)
+                .await
+                .is_ok()
+        );
+
+        let _guard = ChaosScope::new().fail_key("CHAOS_TEST_KEY").apply();
+        assert!(
+            load_secrets_with::<OpenAIOnlyConfig>(secrets_env(), &backend)
+                .await
+                .is_ok()
+        );
+        assert!(
+            load_secrets_with::<ChaosSecretsConfig>(secrets_env(), &backend)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_load_secrets_from_source() {
+        let source = FileSource::parse(r#"{"OPENAI_KEY": "abc123"}"#, Format::Json).unwrap();
+
+        let secrets = load_secrets_from_source::<OpenAIOnlyConfig>(&source).unwrap();
+        assert_eq!(secrets.get(&OpenAIOnlyConfig::OpenAIKey).unwrap(), "abc123");
+        assert!(load_secrets_from_source::<AllSecretsConfig>(&source).is_err());
+    }
+
+    #[test]
+    fn test_subset_valid() {
+        let input_map: HashMap<&'static str, String> = [
+            (OPENAI_KEY, String::from("abc123")),
+            (STRIPE_KEY, String::from("sk_test")),
+        ]
+        .into();
+
+        let secrets: SecretValues<AllSecretsConfig> = SecretValues::from(input_map);
+        let debug = format!("{secrets:?}");
+        assert!(debug.contains(OPENAI_KEY) && !debug.contains("abc123"));
+        let subset: SecretValues<OpenAIOnlyConfig> = secrets.clone_into().unwrap();
+        assert_eq!(subset.get(&OpenAIOnlyConfig::OpenAIKey).unwrap(), "abc123");
+        assert_eq!(
+            subset
+                .get_secret(&OpenAIOnlyConfig::OpenAIKey)
+                .unwrap()
+                .expose(),
+            "abc123"
+        );
+    }
+
+    #[test]
+    fn test_get_base64() {
+        let input_map: HashMap<&'static str, String> =
+            [(OPENAI_KEY, String::from("c2stdGVzdA=="))].into();
+
+        let secrets: SecretValues<OpenAIOnlyConfig> = SecretValues::from(input_map);
+        assert_eq!(
+            secrets.get_base64(&OpenAIOnlyConfig::OpenAIKey).unwrap(),
+            b"sk-test"
+        );
+    }
+
+    #[test]
+    fn test_subset_invalid() {
+        let input_map: HashMap<&'static str, String> =
+            [(STRIPE_KEY, String::from("sk_test"))].into();
+
+        let secrets: SecretValues<AllSecretsConfig> = SecretValues::from(input_map);
+        assert!(secrets.clone_into::<OpenAIOnlyConfig>().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "accessed after SecretValues::lockdown")]
+    fn test_lockdown() {
+        let input_map: HashMap<&'static str, String> =
+            [(OPENAI_KEY, String::from("sk-test"))].into();
+
+        let mut secrets: SecretValues<OpenAIOnlyConfig> = SecretValues::from(input_map);
+        secrets.get(&OpenAIOnlyConfig::OpenAIKey).unwrap();
+        secrets.lockdown();
+        assert!(secrets.is_locked_down());
+        let _ = secrets.get(&OpenAIOnlyConfig::OpenAIKey);
+    }
+
+    #[test]
+    fn test_resolve_placeholders() {
+        let source =
+            FileSource::parse(r#"{ "STAGE": "prod", "APP": "myapp" }"#, Format::Json).unwrap();
+        assert_eq!(
+            resolve_placeholders("${APP}/${STAGE}/main", &source).unwrap(),
+            "myapp/prod/main"
+        );
+        assert_eq!(
+            resolve_placeholders("myapp/main", &source).unwrap(),
+            "myapp/main"
+        );
+        assert!(resolve_placeholders("myapp/${REGION}/main", &source).is_err());
+        assert!(resolve_placeholders("myapp/${STAGE", &source).is_err());
+    }
+}
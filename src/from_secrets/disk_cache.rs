@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use aws_config::BehaviorVersion;
+use aws_sdk_kms::config::Region;
+use aws_sdk_kms::primitives::Blob;
+use aws_sdk_kms::types::DataKeySpec;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{Map, Value};
+
+use crate::from_env::snapshot::MASKED_VALUE;
+use crate::ConfigError;
+use crate::{ConfigSource, MissingEnvVariableError, ProcessEnv};
+
+use super::errors::{FailedToReadSecretsCache, FailedToWriteSecretsCache};
+
+// Disk cache.
+// --------------------------------------------------
+
+// A CachedSecretsFetcher can persist its entries to an encrypted file, so that
+// a restarted container on the same host can skip the Secrets Manager round
+// trip while the cached copy is still fresh:
+//
+// let fetcher = CachedSecretsFetcher::new(AwsSecretsFetcher::new(region).await, ttl)
+//     .persist_to("/var/cache/my-service/secrets", DiskCacheKey::from_env(CACHE_KEY));
+//
+// The file is encrypted with AES-256-GCM, using either a base64 key read from
+// an env variable, or a data key generated by KMS (stored wrapped alongside the
+// ciphertext, and unwrapped by KMS when the file is read). The disk cache is
+// best-effort: unreadable files are ignored and overwritten on the next fetch.
+// Available with the "disk-cache" feature.
+#[derive(Debug, Clone)]
+pub enum DiskCacheKey {
+    Env(&'static str),
+    Kms { key_id: String, region: String },
+}
+
+impl DiskCacheKey {
+    pub fn from_env(var: &'static str) -> Self {
+        DiskCacheKey::Env(var)
+    }
+
+    pub fn kms(key_id: impl Into<String>, region: impl Into<String>) -> Self {
+        DiskCacheKey::Kms {
+            key_id: key_id.into(),
+            region: region.into(),
+        }
+    }
+}
+
+pub(crate) type PersistedEntries = HashMap<String, (String, SystemTime)>;
+
+#[derive(Debug)]
+pub(crate) struct DiskCache {
+    path: PathBuf,
+    key: DiskCacheKey,
+    data_key: OnceLock<DataKey>,
+    loaded: AtomicBool,
+}
+
+#[derive(Clone)]
+struct DataKey {
+    plaintext: Vec<u8>,
+    wrapped: Option<Vec<u8>>,
+}
+
+impl fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(MASKED_VALUE)
+    }
+}
+
+impl DiskCache {
+    pub(crate) fn new(path: PathBuf, key: DiskCacheKey) -> Self {
+        Self {
+            path,
+            key,
+            data_key: OnceLock::new(),
+            loaded: AtomicBool::new(false),
+        }
+    }
+
+    // Returns true only the first time it is called, so that the file is read
+    // once, on the first fetch.
+    pub(crate) fn should_load(&self) -> bool {
+        !self.loaded.swap(true, Ordering::SeqCst)
+    }
+
+    pub(crate) async fn load(&self) -> Result<Option<PersistedEntries>, ConfigError> {
+        let path_str = self.path.display().to_string();
+        let read_error = |e: &dyn fmt::Debug| FailedToReadSecretsCache::with_debug(&path_str, &e);
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(&self.path)
+            .map_err(|e| FailedToReadSecretsCache::with_source(&path_str, e))?;
+        let file = serde_json::from_str::<Value>(&contents)
+            .map_err(|e| FailedToReadSecretsCache::with_source(&path_str, e))?;
+        let decode = |field: &str| -> Result<Option<Vec<u8>>, ConfigError> {
+            file.get(field)
+                .and_then(Value::as_str)
+                .map(|encoded| {
+                    STANDARD
+                        .decode(encoded)
+                        .map_err(|e| FailedToReadSecretsCache::with_source(&path_str, e))
+                })
+                .transpose()
+        };
+        let wrapped = decode("key")?;
+        let (Some(nonce), Some(ciphertext)) = (decode("nonce")?, decode("ciphertext")?) else {
+            return Err(read_error(&"missing nonce or ciphertext"));
+        };
+
+        let data_key = self.data_key(wrapped.as_deref()).await?;
+        let plaintext = Aes256Gcm::new_from_slice(&data_key.plaintext)
+            .map_err(|e| read_error(&e))?
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| read_error(&"authentication failed"))?;
+        let entries = serde_json::from_slice::<Value>(&plaintext)
+            .map_err(|e| FailedToReadSecretsCache::with_source(&path_str, e))?;
+
+        Ok(Some(
+            entries
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter_map(|(secret_id, entry)| {
+                    let value = entry.get("value")?.as_str()?.to_string();
+                    let fetched_at =
+                        UNIX_EPOCH + Duration::from_secs(entry.get("fetched_at")?.as_u64()?);
+                    Some((secret_id.clone(), (value, fetched_at)))
+                })
+                .collect(),
+        ))
+    }
+
+    pub(crate) async fn save(&self, entries: &PersistedEntries) -> Result<(), ConfigError> {
+        let path_str = self.path.display().to_string();
+        let write_error = |e: &dyn fmt::Debug| FailedToWriteSecretsCache::with_debug(&path_str, &e);
+        let plaintext = entries
+            .iter()
+            .map(|(secret_id, (value, fetched_at))| {
+                let fetched_at = fetched_at
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let mut entry = Map::new();
+                entry.insert("value".to_string(), Value::from(value.as_str()));
+                entry.insert("fetched_at".to_string(), Value::from(fetched_at));
+                (secret_id.clone(), Value::Object(entry))
+            })
+            .collect::<Map<String, Value>>();
+        let plaintext = serde_json::to_string(&Value::Object(plaintext))
+            .map_err(|e| FailedToWriteSecretsCache::with_source(&path_str, e))?;
+
+        let data_key = self.data_key(None).await?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = Aes256Gcm::new_from_slice(&data_key.plaintext)
+            .map_err(|e| write_error(&e))?
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| write_error(&"encryption failed"))?;
+
+        let mut file = Map::new();
+        if let Some(wrapped) = &data_key.wrapped {
+            file.insert("key".to_string(), Value::from(STANDARD.encode(wrapped)));
+        }
+        file.insert(
+            "nonce".to_string(),
+            Value::from(STANDARD.encode(nonce.as_slice())),
+        );
+        file.insert(
+            "ciphertext".to_string(),
+            Value::from(STANDARD.encode(ciphertext)),
+        );
+        let contents = serde_json::to_string(&Value::Object(file))
+            .map_err(|e| FailedToWriteSecretsCache::with_source(&path_str, e))?;
+
+        // Write to a temporary file first, so a crash mid-write can't leave a
+        // truncated cache behind.
+        let tmp_path = self.path.with_extension("tmp");
+        std::fs::write(&tmp_path, contents)
+            .map_err(|e| FailedToWriteSecretsCache::with_source(&path_str, e))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(|e| FailedToWriteSecretsCache::with_source(&path_str, e))
+    }
+
+    // Resolve the data key, reusing the previously resolved key unless the file
+    // was written with a different wrapped key.
+    async fn data_key(&self, wrapped: Option<&[u8]>) -> Result<DataKey, ConfigError> {
+        if let Some(key) = self.data_key.get() {
+            if wrapped.is_none() || key.wrapped.as_deref() == wrapped {
+                return Ok(key.clone());
+            }
+        }
+        let key = match &self.key {
+            DiskCacheKey::Env(var) => {
+                let encoded = ProcessEnv::new()
+                    .get(var)?
+                    .ok_or_else(|| MissingEnvVariableError::new(var, ""))?;
+                let plaintext = STANDARD.decode(encoded.trim()).map_err(|e| {
+                    FailedToReadSecretsCache::with_source(&self.path.display().to_string(), e)
+                })?;
+                DataKey {
+                    plaintext,
+                    wrapped: None,
+                }
+            }
+            DiskCacheKey::Kms { key_id, region } => {
+                self.kms_data_key(key_id, region, wrapped).await?
+            }
+        };
+        let _ = self.data_key.set(key.clone());
+        Ok(key)
+    }
+
+    async fn kms_data_key(
+        &self,
+        key_id: &str,
+        region: &str,
+        wrapped: Option<&[u8]>,
+    ) -> Result<DataKey, ConfigError> {
+        let path_str = self.path.display().to_string();
+        let config = aws_config::defaults(BehaviorVersion::v2024_03_28())
+            .region(Region::new(region.to_string()))
+            .load()
+            .await;
+        let client = aws_sdk_kms::Client::new(&config);
+        match wrapped {
+            Some(wrapped) => {
+                let output = client
+                    .decrypt()
+                    .key_id(key_id)
+                    .ciphertext_blob(Blob::new(wrapped))
+                    .send()
+                    .await
+                    .map_err(|e| FailedToReadSecretsCache::with_source(&path_str, e))?;
+                let plaintext = output.plaintext().ok_or_else(|| {
+                    FailedToReadSecretsCache::with_debug(&path_str, &"no plaintext returned by KMS")
+                })?;
+                Ok(DataKey {
+                    plaintext: plaintext.as_ref().to_vec(),
+                    wrapped: Some(wrapped.to_vec()),
+                })
+            }
+            None => {
+                let output = client
+                    .generate_data_key()
+                    .key_id(key_id)
+                    .key_spec(DataKeySpec::Aes256)
+                    .send()
+                    .await
+                    .map_err(|e| FailedToWriteSecretsCache::with_source(&path_str, e))?;
+                let (Some(plaintext), Some(wrapped)) =
+                    (output.plaintext(), output.ciphertext_blob())
+                else {
+                    return Err(FailedToWriteSecretsCache::with_debug(
+                        &path_str,
+                        &"no data key returned by KMS",
+                    ));
+                };
+                Ok(DataKey {
+                    plaintext: plaintext.as_ref().to_vec(),
+                    wrapped: Some(wrapped.as_ref().to_vec()),
+                })
+            }
+        }
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::EnvOverrideScope;
+
+    use super::{DiskCache, DiskCacheKey};
+
+    // 32 zero bytes, base64-encoded.
+    static TEST_KEY: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+
+    #[tokio::test]
+    async fn test_disk_cache_round_trip() {
+        let _guard = EnvOverrideScope::new()
+            .set("DISK_CACHE_TEST_KEY", TEST_KEY)
+            .apply();
+        let path = std::env::temp_dir().join("fractic_env_config_disk_cache_round_trip");
+        let _ = std::fs::remove_file(&path);
+        let fetched_at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let cache = DiskCache::new(path.clone(), DiskCacheKey::from_env("DISK_CACHE_TEST_KEY"));
+        assert!(cache.load().await.unwrap().is_none());
+        cache
+            .save(&HashMap::from([(
+                "test-secret".to_string(),
+                ("v1".to_string(), fetched_at),
+            )]))
+            .await
+            .unwrap();
+
+        assert!(!std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("test-secret"));
+        let cache = DiskCache::new(path.clone(), DiskCacheKey::from_env("DISK_CACHE_TEST_KEY"));
+        let entries = cache.load().await.unwrap().unwrap();
+        assert_eq!(entries["test-secret"], ("v1".to_string(), fetched_at));
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_missing_key() {
+        let path = std::env::temp_dir().join("fractic_env_config_disk_cache_missing_key");
+        let cache = DiskCache::new(path, DiskCacheKey::from_env("DISK_CACHE_UNSET_KEY"));
+        assert!(cache
+            .save(&HashMap::from([(
+                "test-secret".to_string(),
+                ("v1".to_string(), SystemTime::now()),
+            )]))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use super::{SecretNotInRotationGroup, SecretValues, SecretsConfigEnum};
+use crate::ConfigError;
+
+// Dual-key access.
+// --------------------------------------------------
+
+// While an API key is being rotated, both the new and the old key may be
+// accepted (or only one of them, depending on how far the rotation got on the
+// other side). The two keys can be declared as a rotation group, as a
+// (current, previous) pair:
+//
+// define_secrets_config!(
+//     SecretsConfig {
+//         rotation = [(SecretsConfig::ApiKeyCurrent, SecretsConfig::ApiKeyPrevious)]
+//     },
+//     ApiKeyCurrent => API_KEY_CURRENT,
+//     ApiKeyPrevious => API_KEY_PREVIOUS,
+// );
+//
+// let response = secrets.try_with_fallback(
+//     &SecretsConfig::ApiKeyCurrent,
+//     |e: &ApiError| e.status() == 401,
+//     |key| client.call(key),
+// )?;
+//
+// try_with_fallback calls the closure with the current key, and again with the
+// previous key only if it fails with an auth failure. Any other error (or a
+// key outside of a rotation group) is returned as-is, without retrying. Either
+// key of the pair may be passed. For async calls, get_rotation_pair returns
+// both values instead.
+impl<T: SecretsConfigEnum> SecretValues<T> {
+    // Returns the (current, previous) values of the key's rotation group.
+    #[track_caller]
+    pub fn get_rotation_pair(&self, key: &T) -> Result<(&str, &str), ConfigError> {
+        let (current, previous) =
+            rotation_pair(key).ok_or_else(|| SecretNotInRotationGroup::new(key.as_str()))?;
+        Ok((self.get(&current)?, self.get(&previous)?))
+    }
+
+    #[track_caller]
+    pub fn try_with_fallback<R, E: From<ConfigError>>(
+        &self,
+        key: &T,
+        is_auth_failure: impl Fn(&E) -> bool,
+        mut call: impl FnMut(&str) -> Result<R, E>,
+    ) -> Result<R, E> {
+        let Some((current, previous)) = rotation_pair(key) else {
+            return call(self.get(key)?);
+        };
+        match call(self.get(&current)?) {
+            Err(e) if is_auth_failure(&e) => call(self.get(&previous)?),
+            result => result,
+        }
+    }
+}
+
+fn rotation_pair<T: SecretsConfigEnum>(key: &T) -> Option<(T, T)> {
+    T::rotation_pairs()
+        .into_iter()
+        .find(|(current, previous)| current == key || previous == key)
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    use crate::ConfigError;
+
+    use crate::{define_secret_key, define_secrets_config, SecretValues, SecretsConfigEnum};
+
+    define_secret_key!(DUAL_API_KEY_CURRENT);
+    define_secret_key!(DUAL_API_KEY_PREVIOUS);
+    define_secret_key!(DUAL_SIGNING_KEY);
+
+    define_secrets_config!(
+        DualKeyConfig {
+            rotation = [(DualKeyConfig::ApiKeyCurrent, DualKeyConfig::ApiKeyPrevious)]
+        },
+        ApiKeyCurrent => DUAL_API_KEY_CURRENT,
+        ApiKeyPrevious => DUAL_API_KEY_PREVIOUS,
+        SigningKey => DUAL_SIGNING_KEY,
+    );
+
+    fn call<'a>(
+        tried: &'a RefCell<Vec<String>>,
+        accepted: &'a str,
+    ) -> impl FnMut(&str) -> Result<String, ConfigError> + 'a {
+        move |key| {
+            tried.borrow_mut().push(key.to_string());
+            match key == accepted {
+                true => Ok(key.to_string()),
+                false => Err(ConfigError::critical("401 Unauthorized")),
+            }
+        }
+    }
+
+    fn is_auth_failure(e: &ConfigError) -> bool {
+        e.to_string().contains("401")
+    }
+
+    #[test]
+    fn test_try_with_fallback() {
+        let secrets = SecretValues::<DualKeyConfig>::from(HashMap::from([
+            (DUAL_API_KEY_CURRENT, String::from("new")),
+            (DUAL_API_KEY_PREVIOUS, String::from("old")),
+            (DUAL_SIGNING_KEY, String::from("sig")),
+        ]));
+        let pair = secrets.get_rotation_pair(&DualKeyConfig::ApiKeyPrevious);
+        assert_eq!(pair.unwrap(), ("new", "old"));
+        assert!(secrets
+            .get_rotation_pair(&DualKeyConfig::SigningKey)
+            .is_err());
+
+        for (key, accepted, expected) in [
+            (DualKeyConfig::ApiKeyCurrent, "new", vec!["new"]),
+            (DualKeyConfig::ApiKeyCurrent, "old", vec!["new", "old"]),
+            (DualKeyConfig::SigningKey, "old", vec!["sig"]),
+        ] {
+            let tried = RefCell::new(Vec::new());
+            let result = secrets.try_with_fallback(&key, is_auth_failure, call(&tried, accepted));
+            assert_eq!(result.is_ok(), expected.last() == Some(&accepted));
+            assert_eq!(*tried.borrow(), expected);
+        }
+    }
+}
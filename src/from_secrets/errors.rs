@@ -1,22 +1,182 @@
-use fractic_server_error::define_internal_error;
+use crate::error::define_config_error;
 
-define_internal_error!(
+define_config_error!(
     FailedToFetchSecretsJson,
+    "SEC001",
     "Failed to fetch secret '{secret_id}' (region '{region}') from Amazon Secrets Manager.",
     { secret_id: &str, region: &str }
 );
-define_internal_error!(
+define_config_error!(
+    SecretNotFound,
+    "SEC002",
+    "Secret '{secret_id}' (region '{region}') does not exist in Amazon Secrets Manager.",
+    { secret_id: &str, region: &str }
+);
+define_config_error!(
+    SecretsAccessDenied,
+    "SEC003",
+    "Access denied to secret '{secret_id}' (region '{region}') in Amazon Secrets Manager.",
+    { secret_id: &str, region: &str }
+);
+define_config_error!(
+    SecretsThrottled,
+    "SEC004",
+    "Request for secret '{secret_id}' (region '{region}') was throttled by Amazon Secrets Manager.",
+    { secret_id: &str, region: &str }
+);
+define_config_error!(
+    SecretsNetworkError,
+    "SEC005",
+    "Failed to reach Amazon Secrets Manager for secret '{secret_id}' (region '{region}').",
+    { secret_id: &str, region: &str }
+);
+define_config_error!(
     MissingSecretKey,
+    "SEC006",
     "Secret '{secret_id}' (region '{region}') missing key '{missing_key}'.",
     { secret_id: &str, region: &str, missing_key: &str }
 );
-define_internal_error!(
+define_config_error!(
+    MissingSecretValue,
+    "SEC007",
+    "Missing secret '{missing_key}'.",
+    { missing_key: &str }
+);
+define_config_error!(
+    FailedToReadKeychain,
+    "SEC008",
+    "Failed to read secret '{key}' (service '{service}') from the OS keychain.",
+    { service: &str, key: &str }
+);
+define_config_error!(
+    InvalidSopsFile,
+    "SEC009",
+    "SOPS file '{path}' is invalid: {reason}.",
+    { path: &str, reason: &str }
+);
+define_config_error!(
+    FailedToDecryptSopsDataKey,
+    "SEC010",
+    "Failed to decrypt the data key of SOPS file '{path}' with any of its age or KMS keys.",
+    { path: &str }
+);
+define_config_error!(
+    FailedToDecryptSopsValue,
+    "SEC011",
+    "Failed to decrypt value '{key}' of SOPS file '{path}'.",
+    { path: &str, key: &str }
+);
+define_config_error!(
+    CoalescedSecretsFetchFailed,
+    "SEC012",
+    "Failed to fetch secret '{secret_id}' (shared with a concurrent fetch): {reason}",
+    { secret_id: &str, reason: &str }
+);
+define_config_error!(
+    FailedToReadSecretsCache,
+    "SEC013",
+    "Failed to read secrets cache file '{path}'.",
+    { path: &str }
+);
+define_config_error!(
+    FailedToWriteSecretsCache,
+    "SEC014",
+    "Failed to write secrets cache file '{path}'.",
+    { path: &str }
+);
+define_config_error!(
+    UnresolvedSecretsIdPlaceholder,
+    "SEC015",
+    "Could not resolve placeholder '{placeholder}' in secret ID '{secret_id}'.",
+    { secret_id: &str, placeholder: &str }
+);
+define_config_error!(
+    InvalidTenantId,
+    "SEC016",
+    "Invalid tenant ID '{tenant_id}'. Only alphanumeric characters, '-' and '_' are allowed.",
+    { tenant_id: &str }
+);
+define_config_error!(
     SecretsInvalidJson,
+    "SEC017",
     "Secret '{secret_id}' (region '{region}')'s value is not valid JSON.",
     { secret_id: &str, region: &str }
 );
-define_internal_error!(
+define_config_error!(
+    InvalidSecretValueType,
+    "SEC018",
+    "Secret '{secret_id}' (region '{region}') key '{key}' is {kind}, expected a string, number or boolean (or declare the key with json = true).",
+    { secret_id: &str, region: &str, key: &str, kind: &str }
+);
+define_config_error!(
+    SecretBinaryNotSupported,
+    "SEC019",
+    "Secret '{secret_id}' was requested as binary, which its SecretsFetcher does not support.",
+    { secret_id: &str }
+);
+define_config_error!(
+    InvalidSecretBundle,
+    "SEC020",
+    "Secret '{secret_id}' (region '{region}') is not a valid bundle: {reason}.",
+    { secret_id: &str, region: &str, reason: &str }
+);
+define_config_error!(
+    InvalidSecretFormat,
+    "SEC021",
+    "Secret '{secret_id}' (region '{region}')'s value is not valid {format}: {reason}.",
+    { secret_id: &str, region: &str, format: &str, reason: &str }
+);
+define_config_error!(
+    InvalidRotationNotification,
+    "SEC022",
+    "Invalid secret rotation notification: {reason}.",
+    { reason: &str }
+);
+define_config_error!(
     InvalidSecretsCloneInto,
+    "SEC023",
     "Invalid clone_into(...). Parent config missing secret '{missing_secret}'.",
     { missing_secret: &str }
 );
+define_config_error!(
+    SecretNotInRotationGroup,
+    "SEC024",
+    "Secret '{key}' is not part of a rotation group.",
+    { key: &str }
+);
+define_config_error!(
+    FailedToGenerateRdsAuthToken,
+    "SEC025",
+    "Failed to generate an RDS IAM auth token for '{host}': {reason}.",
+    { host: &str, reason: &str }
+);
+define_config_error!(
+    FailedToAssumeRole,
+    "SEC026",
+    "Failed to assume role '{role_arn}': {reason}.",
+    { role_arn: &str, reason: &str }
+);
+define_config_error!(
+    SecretsConfigNotRegistered,
+    "SEC027",
+    "Secrets config '{config}' is not registered in the SecretsManagerRegistry.",
+    { config: &str }
+);
+define_config_error!(
+    InvalidTenantTemplate,
+    "SEC028",
+    "Secret ID template '{template}' doesn't contain the tenant placeholder.",
+    { template: &str }
+);
+define_config_error!(
+    FailedToServeRotationWebhook,
+    "SEC029",
+    "Failed to serve rotation notifications on '{addr}'.",
+    { addr: &str }
+);
+define_config_error!(
+    RotationWebhookOutsideRuntime,
+    "SEC030",
+    "The rotation webhook on '{addr}' must be started from within a tokio runtime.",
+    { addr: &str }
+);
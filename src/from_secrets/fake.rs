@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use super::errors::{FailedToFetchSecretsJson, SecretNotFound};
+use super::fetcher::SecretsFetcher;
+use crate::ConfigError;
+
+// Fake secrets backend.
+// --------------------------------------------------
+
+// Deterministic in-memory SecretsFetcher, so the retry, timeout, and missing-key
+// paths of code using this crate can be tested without AWS:
+//
+// let backend = FakeSecretsBackend::new()
+//     .with_secret("my-secret", r#"{"OPENAI_KEY": "abc123"}"#)
+//     .with_latency(Duration::from_millis(50));
+// backend.fail_next(2);
+//
+// let secrets = load_secrets_with::<SecretsConfig>(env, &backend).await;
+//
+// The backend can also be modified while in use (ex. to simulate a rotation),
+// and counts the fetches it served. Binary secrets are set separately with
+// with_binary_secret. Available with the "testing" feature.
+#[derive(Debug, Default)]
+pub struct FakeSecretsBackend {
+    secrets: Mutex<HashMap<String, String>>,
+    binary_secrets: Mutex<HashMap<String, Vec<u8>>>,
+    latency: Mutex<Duration>,
+    failures_remaining: AtomicUsize,
+    fetch_count: AtomicUsize,
+}
+
+static FAKE_REGION: &str = "fake";
+
+impl FakeSecretsBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_secret(self, secret_id: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_secret(secret_id, value);
+        self
+    }
+
+    pub fn with_binary_secret(
+        self,
+        secret_id: impl Into<String>,
+        value: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.set_binary_secret(secret_id, value);
+        self
+    }
+
+    pub fn with_latency(self, latency: Duration) -> Self {
+        self.set_latency(latency);
+        self
+    }
+
+    pub fn set_secret(&self, secret_id: impl Into<String>, value: impl Into<String>) {
+        self.secrets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(secret_id.into(), value.into());
+    }
+
+    pub fn set_binary_secret(&self, secret_id: impl Into<String>, value: impl Into<Vec<u8>>) {
+        self.binary_secrets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(secret_id.into(), value.into());
+    }
+
+    pub fn remove_secret(&self, secret_id: &str) {
+        self.secrets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(secret_id);
+        self.binary_secrets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(secret_id);
+    }
+
+    pub fn set_latency(&self, latency: Duration) {
+        *self.latency.lock().unwrap_or_else(PoisonError::into_inner) = latency;
+    }
+
+    // Make the next n fetches fail, regardless of the secret ID.
+    pub fn fail_next(&self, n: usize) {
+        self.failures_remaining.store(n, Ordering::SeqCst);
+    }
+
+    pub fn fetch_count(&self) -> usize {
+        self.fetch_count.load(Ordering::SeqCst)
+    }
+
+    // Applies the configured latency and injected failures.
+    async fn begin_fetch(&self, secret_id: &str) -> Result<(), ConfigError> {
+        self.fetch_count.fetch_add(1, Ordering::SeqCst);
+        let latency = *self.latency.lock().unwrap_or_else(PoisonError::into_inner);
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        let should_fail = self
+            .failures_remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+        if should_fail {
+            return Err(FailedToFetchSecretsJson::new(secret_id, FAKE_REGION));
+        }
+        Ok(())
+    }
+}
+
+impl SecretsFetcher for FakeSecretsBackend {
+    async fn fetch_secret_string(&self, secret_id: &str) -> Result<String, ConfigError> {
+        self.begin_fetch(secret_id).await?;
+        self.secrets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(secret_id)
+            .cloned()
+            .ok_or_else(|| SecretNotFound::new(secret_id, FAKE_REGION))
+    }
+
+    async fn fetch_secret_binary(&self, secret_id: &str) -> Result<Vec<u8>, ConfigError> {
+        self.begin_fetch(secret_id).await?;
+        self.binary_secrets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(secret_id)
+            .cloned()
+            .ok_or_else(|| SecretNotFound::new(secret_id, FAKE_REGION))
+    }
+}
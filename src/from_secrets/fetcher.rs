@@ -0,0 +1,39 @@
+use std::future::Future;
+use std::time::Duration;
+
+use super::errors::SecretBinaryNotSupported;
+use crate::ConfigError;
+
+// Secrets fetchers.
+// --------------------------------------------------
+
+// A SecretsFetcher retrieves the raw secret string for a given secret ID.
+// load_secrets uses AwsSecretsFetcher (Amazon Secrets Manager, see aws.rs), but
+// any other fetcher can be used through load_secrets_with, for example the
+// in-memory FakeSecretsBackend in tests:
+//
+// let secrets = load_secrets_with::<SecretsConfig>(env, &fetcher).await?;
+//
+// Fetchers of backends with binary secrets (ex. SecretBinary in Secrets
+// Manager) also implement fetch_secret_binary, used for bundles (see
+// bundle.rs).
+pub trait SecretsFetcher: Send + Sync {
+    fn fetch_secret_string(
+        &self,
+        secret_id: &str,
+    ) -> impl Future<Output = Result<String, ConfigError>> + Send;
+
+    fn fetch_secret_binary(
+        &self,
+        secret_id: &str,
+    ) -> impl Future<Output = Result<Vec<u8>, ConfigError>> + Send {
+        let error = SecretBinaryNotSupported::new(secret_id);
+        async move { Err(error) }
+    }
+
+    // How long fetched values may be served before being refetched, for
+    // fetchers that cache them (see metadata.rs).
+    fn ttl(&self) -> Option<Duration> {
+        None
+    }
+}
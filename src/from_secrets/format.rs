@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::ConfigError;
+
+use super::config::SecretsConfigEnum;
+use super::errors::{InvalidSecretFormat, SecretsInvalidJson};
+
+// Secret formats.
+// --------------------------------------------------
+
+// Not every secret is a JSON map. The format of the secret string can be
+// declared on the config:
+//
+// define_secrets_config!(
+//     SecretsConfig { format = Plaintext },
+//     DbPassword => DB_PASSWORD,
+// );
+//
+// - Json (the default): a JSON object, with keys looked up by name or dotted
+//   path.
+// - Plaintext: the whole secret string is the value of the config's single
+//   key. Configs declaring more than one key are rejected.
+// - DotenvLines: KEY=VALUE lines, as in a .env file. Blank lines and lines
+//   starting with '#' are skipped, an "export " prefix is allowed, and values
+//   wrapped in matching single or double quotes are unquoted.
+//
+// Sharded secrets (see config.rs) are concatenated before being decoded in the
+// Plaintext and DotenvLines formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SecretFormat {
+    #[default]
+    Json,
+    Plaintext,
+    DotenvLines,
+}
+
+impl SecretFormat {
+    fn name(&self) -> &'static str {
+        match self {
+            SecretFormat::Json => "JSON",
+            SecretFormat::Plaintext => "plaintext",
+            SecretFormat::DotenvLines => "KEY=VALUE lines",
+        }
+    }
+}
+
+// Decodes a secret string into the map keys are looked up in, as if it were
+// a JSON object.
+pub(crate) fn decode_secret_string<T: SecretsConfigEnum>(
+    secret_id: &str,
+    region: &str,
+    secret_string: &str,
+) -> Result<HashMap<String, Value>, ConfigError> {
+    let format = T::format();
+    match format {
+        SecretFormat::Json => serde_json::from_str::<HashMap<String, Value>>(secret_string)
+            .map_err(|e| SecretsInvalidJson::with_source(secret_id, region, e)),
+        SecretFormat::Plaintext => match T::value_list().as_slice() {
+            [key] => Ok(HashMap::from([(
+                key.as_str().to_string(),
+                Value::String(secret_string.to_string()),
+            )])),
+            _ => Err(InvalidSecretFormat::new(
+                secret_id,
+                region,
+                format.name(),
+                "the config must declare exactly one key",
+            )),
+        },
+        SecretFormat::DotenvLines => parse_dotenv_lines(secret_string)
+            .map_err(|reason| InvalidSecretFormat::new(secret_id, region, format.name(), &reason)),
+    }
+}
+
+fn parse_dotenv_lines(secret_string: &str) -> Result<HashMap<String, Value>, String> {
+    let mut values = HashMap::new();
+    for (number, line) in secret_string.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {} is not KEY=VALUE", number + 1));
+        };
+        let value = value.trim();
+        let value = ['"', '\'']
+            .into_iter()
+            .find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote))
+            .unwrap_or(value);
+        values.insert(key.trim().to_string(), Value::String(value.to_string()));
+    }
+    Ok(values)
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        define_secret_key, define_secrets_config, load_secrets_with, EnvVariables,
+        FakeSecretsBackend, SecretsConfigEnum, SecretsEnvConfig, SECRETS_ID, SECRETS_REGION,
+    };
+
+    define_secret_key!(FORMAT_TEST_DB_PASSWORD);
+    define_secret_key!(FORMAT_TEST_OPENAI_KEY);
+
+    define_secrets_config!(
+        PlaintextConfig { format = Plaintext },
+        DbPassword => FORMAT_TEST_DB_PASSWORD,
+    );
+
+    define_secrets_config!(
+        DotenvConfig { format = DotenvLines },
+        DbPassword => FORMAT_TEST_DB_PASSWORD,
+        OpenAIKey => FORMAT_TEST_OPENAI_KEY,
+    );
+
+    #[tokio::test]
+    async fn test_load_secrets_formats() {
+        let env = EnvVariables::<SecretsEnvConfig>::from(HashMap::from([
+            (SECRETS_REGION, String::from("us-west-2")),
+            (SECRETS_ID, String::from("test-format")),
+        ]));
+
+        let backend = FakeSecretsBackend::new().with_secret("test-format", "hunter2");
+        let secrets = load_secrets_with::<PlaintextConfig>(env.clone(), &backend)
+            .await
+            .unwrap();
+        assert_eq!(
+            secrets.get(&PlaintextConfig::DbPassword).unwrap(),
+            "hunter2"
+        );
+
+        backend.set_secret(
+            "test-format",
+            "# Database.\nexport FORMAT_TEST_DB_PASSWORD=\"hunter2\"\n\nFORMAT_TEST_OPENAI_KEY=abc123\n",
+        );
+        let secrets = load_secrets_with::<DotenvConfig>(env.clone(), &backend)
+            .await
+            .unwrap();
+        assert_eq!(secrets.get(&DotenvConfig::DbPassword).unwrap(), "hunter2");
+        assert_eq!(secrets.get(&DotenvConfig::OpenAIKey).unwrap(), "abc123");
+
+        backend.set_secret("test-format", "FORMAT_TEST_DB_PASSWORD hunter2");
+        let error = load_secrets_with::<DotenvConfig>(env, &backend)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("line 1"));
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use super::{SecretValues, SecretsConfigEnum};
+
+// Memory protection.
+// --------------------------------------------------
+
+// With the "hardened" feature, each secret value is mlock'ed as soon as the
+// SecretValues is constructed, so the pages holding it are never written to
+// swap, and zeroed and unlocked again when it is dropped (or locked down).
+//
+// let secrets = load_secrets::<SecretsConfig>(env).await?;
+// assert!(secrets.is_memory_locked());
+//
+// Locking is best-effort: it can fail if the process exceeds its locked
+// memory limit (RLIMIT_MEMLOCK), in which case the values are still usable but
+// is_memory_locked returns false. Intermediate buffers used while fetching (ex.
+// the raw secrets JSON) are not covered.
+impl<T: SecretsConfigEnum> SecretValues<T> {
+    pub fn is_memory_locked(&self) -> bool {
+        self.2.memory_locked
+    }
+
+    pub(crate) fn with_locked_memory(mut self) -> Self {
+        let mut all_locked = true;
+        for value in self.0.values_mut() {
+            if value.capacity() == 0 {
+                continue;
+            }
+            all_locked &= lock_pages(value.as_ptr() as usize, value.capacity());
+        }
+        self.2.memory_locked = all_locked;
+        self
+    }
+
+    pub(crate) fn unlock_memory(&mut self) {
+        for value in self.0.values_mut() {
+            if value.capacity() == 0 {
+                continue;
+            }
+            // SAFETY: The range is exactly the value's heap allocation (values
+            // are never modified in place), including its unused capacity.
+            unsafe { memsec::memzero(value.as_mut_ptr(), value.capacity()) };
+            unlock_pages(value.as_ptr() as usize, value.capacity());
+            // Released right away, so that the pages aren't unlocked twice
+            // (ex. on lockdown, then on drop).
+            drop(std::mem::take(value));
+        }
+        self.2.memory_locked = false;
+    }
+}
+
+impl<T: SecretsConfigEnum> Drop for SecretValues<T> {
+    fn drop(&mut self) {
+        self.unlock_memory();
+    }
+}
+
+// Locks apply to whole pages, and don't nest: a single munlock unlocks a page,
+// however many times it was locked. Since small values share pages with each
+// other (and with unrelated allocations), the pages are locked through a
+// process-wide count of the values on each, and only unlocked once the last of
+// them is released.
+#[derive(Default)]
+struct PageLock {
+    values: usize,
+    locked: bool,
+}
+
+static LOCKED_PAGES: Mutex<Option<HashMap<usize, PageLock>>> = Mutex::new(None);
+
+fn lock_pages(address: usize, len: usize) -> bool {
+    let mut pages = LOCKED_PAGES.lock().unwrap_or_else(PoisonError::into_inner);
+    let pages = pages.get_or_insert_with(HashMap::new);
+    let mut all_locked = true;
+    for page in page_range(address, len) {
+        let lock = pages.entry(page).or_default();
+        lock.values += 1;
+        if !lock.locked {
+            // SAFETY: The page holds part of a live allocation, so is mapped.
+            lock.locked = unsafe { memsec::mlock(page as *mut u8, page_size()) };
+        }
+        all_locked &= lock.locked;
+    }
+    all_locked
+}
+
+fn unlock_pages(address: usize, len: usize) {
+    let mut pages = LOCKED_PAGES.lock().unwrap_or_else(PoisonError::into_inner);
+    let Some(pages) = pages.as_mut() else {
+        return;
+    };
+    for page in page_range(address, len) {
+        let Some(lock) = pages.get_mut(&page) else {
+            continue;
+        };
+        lock.values -= 1;
+        if lock.values == 0 {
+            if lock.locked {
+                // SAFETY: As above.
+                unsafe { memsec::munlock(page as *mut u8, page_size()) };
+            }
+            pages.remove(&page);
+        }
+    }
+}
+
+fn page_range(address: usize, len: usize) -> impl Iterator<Item = usize> {
+    let size = page_size();
+    (address / size * size..address + len).step_by(size)
+}
+
+fn page_size() -> usize {
+    static PAGE_SIZE: OnceLock<usize> = OnceLock::new();
+    *PAGE_SIZE.get_or_init(|| {
+        #[cfg(unix)]
+        {
+            // SAFETY: sysconf has no preconditions.
+            let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+            if size > 0 {
+                return size as usize;
+            }
+        }
+        4096
+    })
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{define_secret_key, define_secrets_config, SecretValues, SecretsConfigEnum};
+
+    use super::{lock_pages, page_range, page_size, unlock_pages};
+
+    define_secret_key!(HARDENED_TEST_KEY);
+
+    define_secrets_config!(
+        HardenedConfig,
+        HardenedTestKey => HARDENED_TEST_KEY,
+    );
+
+    fn secrets(value: &str) -> SecretValues<HardenedConfig> {
+        SecretValues::from(HashMap::from([(HARDENED_TEST_KEY, String::from(value))]))
+    }
+
+    fn value(secrets: &SecretValues<HardenedConfig>) -> &str {
+        secrets.get(&HardenedConfig::HardenedTestKey).unwrap()
+    }
+
+    // Whether the kernel has the page holding the address locked, from the
+    // flags of its mapping ("lo" for VM_LOCKED; mlock splits mappings at the
+    // locked range).
+    #[cfg(target_os = "linux")]
+    fn is_page_locked(address: usize) -> bool {
+        let smaps = std::fs::read_to_string("/proc/self/smaps").unwrap();
+        let mut in_mapping = false;
+        for line in smaps.lines() {
+            let first = line.split_whitespace().next().unwrap_or_default();
+            if let Some((start, end)) = first.split_once('-') {
+                if let (Ok(start), Ok(end)) = (
+                    usize::from_str_radix(start, 16),
+                    usize::from_str_radix(end, 16),
+                ) {
+                    in_mapping = (start..end).contains(&address);
+                    continue;
+                }
+            }
+            if in_mapping {
+                if let Some(flags) = line.strip_prefix("VmFlags:") {
+                    return flags.split_whitespace().any(|flag| flag == "lo");
+                }
+            }
+        }
+        false
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_memory_locked() {
+        let original = secrets("hunter2");
+        assert!(original.is_memory_locked());
+        assert!(is_page_locked(value(&original).as_ptr() as usize));
+        let clone = original.clone();
+        drop(original);
+        assert!(is_page_locked(value(&clone).as_ptr() as usize));
+        assert_eq!(value(&clone), "hunter2");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_shared_page() {
+        // A page of its own, so that no other test locks it.
+        let buffer = vec![0u8; 2 * page_size()];
+        let page = page_range(buffer.as_ptr() as usize + page_size(), 1)
+            .next()
+            .unwrap();
+
+        assert!(lock_pages(page, 16));
+        assert!(lock_pages(page + 16, 16));
+        unlock_pages(page, 16);
+        assert!(is_page_locked(page));
+        unlock_pages(page + 16, 16);
+        assert!(!is_page_locked(page));
+    }
+}
@@ -0,0 +1,55 @@
+use keyring::Entry;
+
+use crate::ConfigError;
+use crate::ConfigSource;
+
+use super::errors::FailedToReadKeychain;
+use super::{load_secrets_from_source, SecretValues, SecretsConfigEnum};
+
+// OS keychain source.
+// --------------------------------------------------
+
+// For local development, secrets can be read from the OS keychain (macOS
+// Keychain, Windows Credential Manager, or the Linux secret-service) instead of
+// being kept in plaintext .env files:
+//
+// let secrets = load_secrets_from_keychain::<SecretsConfig>("my-service")?;
+//
+// Each secret key is stored as a separate entry, with the given service name
+// and the key name as the account (ex. `security add-generic-password -s
+// my-service -a OPENAI_KEY -w` on macOS). Available with the "keychain"
+// feature.
+#[derive(Debug, Clone)]
+pub struct KeychainSource {
+    service: String,
+}
+
+impl KeychainSource {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+}
+
+impl ConfigSource for KeychainSource {
+    fn get(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        let entry = Entry::new(&self.service, key)
+            .map_err(|e| FailedToReadKeychain::with_source(&self.service, key, e))?;
+        match entry.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(FailedToReadKeychain::with_source(&self.service, key, e)),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "keychain"
+    }
+}
+
+pub fn load_secrets_from_keychain<T: SecretsConfigEnum>(
+    service: &str,
+) -> Result<SecretValues<T>, ConfigError> {
+    load_secrets_from_source(&KeychainSource::new(service))
+}
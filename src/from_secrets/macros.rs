@@ -7,7 +7,10 @@ macro_rules! define_secret_key {
 
 #[macro_export]
 macro_rules! define_secrets_config {
-    ($T:ident, $($k:ident => $v:ident),* $(,)?) => {
+    (
+        $T:ident $({ $($copt:ident = $cval:tt),* $(,)? })?,
+        $($k:ident => $v:tt $({ $($opt:ident = $val:expr),* $(,)? })?),* $(,)?
+    ) => {
         #[derive(Debug, PartialEq, Eq, Hash, Clone)]
         pub enum $T {
             $($k),*
@@ -23,8 +26,101 @@ macro_rules! define_secrets_config {
             fn value_list() -> Vec<Self> {
                 [$($T::$k),*].to_vec()
             }
+
+            fn spec(&self) -> $crate::SecretKeySpec {
+                match *self {
+                    $($T::$k => $crate::SecretKeySpec::new($v) $($(.$opt($val))*)?),*
+                }
+            }
+
+            $($($crate::__secrets_config_item!($copt, $cval);)*)?
+        }
+
+        $crate::__secrets_config_option!(@all $T, [$($k),*] $($(, $copt = $cval)*)?);
+    };
+}
+
+// Config-level options of define_secrets_config!, either mapping to an item of
+// the SecretsConfigEnum impl (__secrets_config_item!), or generating items of
+// their own (__secrets_config_option!).
+//
+// With { parts = N }, the secret is fetched as N shards (see config.rs). With
+// { bundle = true }, it is fetched as a ZIP of JSON files (see bundle.rs). With
+// { format = Plaintext } or { format = DotenvLines }, the secret string is
+// decoded as such instead of as JSON (see format.rs). With
+// { rotation = [(Current, Previous)] }, pairs of keys are declared as rotation
+// groups (see dual_key.rs).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __secrets_config_item {
+    (parts, $parts:tt) => {
+        fn parts() -> Option<usize> {
+            Some($parts)
+        }
+    };
+    (bundle, $bundle:tt) => {
+        fn bundle() -> bool {
+            $bundle
+        }
+    };
+    (format, $format:tt) => {
+        fn format() -> $crate::SecretFormat {
+            $crate::SecretFormat::$format
+        }
+    };
+    (rotation, $pairs:tt) => {
+        fn rotation_pairs() -> Vec<(Self, Self)> {
+            $pairs.to_vec()
+        }
+    };
+    ($copt:ident, $cval:tt) => {};
+}
+
+// With { mock = SecretsConfigMock }, a fixture struct of that name is
+// generated, with one field per key, to be passed to the config's mock
+// constructor:
+//
+// define_secrets_config!(
+//     SecretsConfig { mock = SecretsConfigMock },
+//     OpenAIKey => OPENAI_KEY,
+//     StripeKey => STRIPE_KEY,
+// );
+//
+// let secrets = SecretsConfig::mock(SecretsConfigMock {
+//     OpenAIKey: "sk-test",
+//     StripeKey: "sk_test",
+// });
+//
+// Since every field is required, fixtures stop compiling when a key is added
+// to the config, instead of failing at runtime.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __secrets_config_option {
+    (@all $T:ident, $keys:tt $(, $copt:ident = $cval:tt)*) => {
+        $($crate::__secrets_config_option!($T, $keys, $copt, $cval);)*
+    };
+    ($T:ident, $keys:tt, mock, false) => {};
+    ($T:ident, [$($k:ident),*], mock, $Mock:ident) => {
+        #[allow(non_snake_case)]
+        pub struct $Mock<'a> {
+            $(pub $k: &'a str),*
+        }
+
+        impl $T {
+            pub fn mock(values: $Mock<'_>) -> $crate::SecretValues<Self> {
+                let map: ::std::collections::HashMap<&'static str, ::std::string::String> =
+                    ::std::collections::HashMap::from([$((
+                        <Self as $crate::SecretsConfigEnum>::as_str(&$T::$k),
+                        values.$k.to_string(),
+                    )),*]);
+                $crate::SecretValues::from(map)
+            }
         }
     };
+    ($T:ident, $keys:tt, parts, $parts:tt) => {};
+    ($T:ident, $keys:tt, bundle, $bundle:tt) => {};
+    ($T:ident, $keys:tt, format, $format:tt) => {};
+    ($T:ident, $keys:tt, rotation, $pairs:tt) => {};
 }
 
 #[cfg(test)]
@@ -77,6 +173,41 @@ mod macro_tests {
         assert_eq!(env_variables.get(&TestConfig::TestVar2).unwrap(), "value2");
     }
 
+    #[test]
+    fn test_define_secrets_config_dotted_path() {
+        define_secret_key!(TEST_SECRET_VAR_1);
+
+        define_secrets_config!(
+            TestConfig,
+            TestVar1 => TEST_SECRET_VAR_1,
+            DbPassword => "database.credentials.password",
+        );
+
+        assert_eq!(
+            TestConfig::DbPassword.as_str(),
+            "database.credentials.password"
+        );
+    }
+
+    #[test]
+    fn test_mock() {
+        define_secret_key!(TEST_SECRET_VAR_1);
+        define_secret_key!(TEST_SECRET_VAR_2);
+
+        define_secrets_config!(
+            TestConfig { mock = TestConfigMock },
+            TestVar1 => TEST_SECRET_VAR_1,
+            TestVar2 => TEST_SECRET_VAR_2,
+        );
+
+        let secrets = TestConfig::mock(TestConfigMock {
+            TestVar1: "value1",
+            TestVar2: "value2",
+        });
+        assert_eq!(secrets.get(&TestConfig::TestVar1).unwrap(), "value1");
+        assert_eq!(secrets.get(&TestConfig::TestVar2).unwrap(), "value2");
+    }
+
     #[test]
     fn test_define_env_config_empty() {
         define_secrets_config!(EmptyConfig,);
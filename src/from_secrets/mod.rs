@@ -1,5 +1,67 @@
+mod audit;
+#[cfg(feature = "aws")]
+mod aws;
+#[cfg(feature = "bundle")]
+mod bundle;
+#[cfg(feature = "aws")]
+mod cache;
 mod config;
-pub mod errors;
-pub mod macros;
+#[cfg(feature = "disk-cache")]
+mod disk_cache;
+mod dual_key;
+mod errors;
+#[cfg(any(test, feature = "testing"))]
+mod fake;
+mod fetcher;
+mod format;
+#[cfg(feature = "hardened")]
+mod hardened;
+#[cfg(feature = "keychain")]
+mod keychain;
+mod macros;
+#[cfg(feature = "aws")]
+mod registry;
+#[cfg(feature = "aws")]
+mod rotation;
+mod snapshot;
+#[cfg(feature = "sops")]
+mod sops;
+mod spec;
+mod tenant;
+#[cfg(feature = "rotation-webhook")]
+mod webhook;
+mod window;
 
-pub use config::{load_secrets, SecretValues, SecretsConfigEnum};
+pub use audit::{
+    clear_secret_auditor, set_secret_auditor, SecretAccess, SecretAccessLog, SecretAuditor,
+};
+#[cfg(feature = "aws")]
+pub use aws::AwsSecretsFetcher;
+#[cfg(feature = "aws")]
+pub use cache::CachedSecretsFetcher;
+#[cfg(feature = "aws")]
+pub use config::{load_secrets, load_secrets_with_sdk_config, validate_secrets};
+pub use config::{
+    load_secrets_from_source, load_secrets_with, load_secrets_with_env_source,
+    validate_secrets_with, SecretValues, SecretsConfigEnum, SecretsEnvConfig, SecretsEnvKeys,
+};
+#[cfg(feature = "disk-cache")]
+pub use disk_cache::DiskCacheKey;
+pub use errors::*;
+#[cfg(any(test, feature = "testing"))]
+pub use fake::FakeSecretsBackend;
+pub use fetcher::SecretsFetcher;
+pub use format::SecretFormat;
+#[cfg(feature = "keychain")]
+pub use keychain::{load_secrets_from_keychain, KeychainSource};
+#[cfg(feature = "aws")]
+pub use registry::SecretsManagerRegistry;
+#[cfg(feature = "aws")]
+pub use rotation::handle_rotation_notification;
+#[cfg(feature = "sops")]
+pub use sops::load_secrets_from_sops;
+pub use spec::SecretKeySpec;
+pub use tenant::TenantSecrets;
+#[cfg(feature = "rotation-webhook")]
+pub use webhook::{serve_rotation_webhook, RotationWebhook};
+pub use window::SecretsWindow;
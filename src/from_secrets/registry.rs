@@ -0,0 +1,193 @@
+use std::any::{type_name, TypeId};
+use std::collections::HashMap;
+
+use crate::ConfigError;
+use crate::{EnvVariables, SecretsEnvConfig, SECRETS_ID, SECRETS_REGION};
+
+use super::cache::CachedSecretsFetcher;
+use super::config::{load_secrets_with, SecretValues, SecretsConfigEnum, SecretsEnvKeys};
+use super::errors::SecretsConfigNotRegistered;
+use super::fetcher::SecretsFetcher;
+
+// Secrets registry.
+// --------------------------------------------------
+
+// For modular monoliths, where each domain owns its own secret (and so its own
+// SecretsConfig), the secrets configs can be registered centrally, each with
+// its own secret ID and its own cache:
+//
+// let registry = SecretsManagerRegistry::new()
+//     .register::<BillingSecrets>(
+//         load_env::<BillingSecretsEnv>()?,
+//         CachedSecretsFetcher::new(AwsSecretsFetcher::new(region).await, Duration::from_secs(300)),
+//     )
+//     .register::<SearchSecrets>(
+//         load_env::<SearchSecretsEnv>()?,
+//         CachedSecretsFetcher::new(AwsSecretsFetcher::new(region).await, Duration::from_secs(3600))
+//             .stale_while_revalidate(Duration::from_secs(600)),
+//     );
+//
+// let billing = registry.load::<BillingSecrets>().await?;
+//
+// Since the caches are separate, each config keeps its own TTL and refresh
+// settings, and refreshing (or invalidating) one domain's secrets never
+// affects another's. Registering a config again replaces its entry.
+#[derive(Debug)]
+pub struct SecretsManagerRegistry<F: SecretsFetcher + 'static> {
+    domains: HashMap<TypeId, Domain<F>>,
+}
+
+#[derive(Debug)]
+struct Domain<F: SecretsFetcher + 'static> {
+    env: EnvVariables<SecretsEnvConfig>,
+    fetcher: CachedSecretsFetcher<F>,
+}
+
+impl<F: SecretsFetcher + 'static> SecretsManagerRegistry<F> {
+    pub fn new() -> Self {
+        Self {
+            domains: HashMap::new(),
+        }
+    }
+
+    // A missing region or secret ID is reported when the config is loaded.
+    pub fn register<S: SecretsConfigEnum + 'static>(
+        mut self,
+        env: EnvVariables<impl SecretsEnvKeys>,
+        fetcher: CachedSecretsFetcher<F>,
+    ) -> Self {
+        let env = default_keys_env(&env);
+        self.domains
+            .insert(TypeId::of::<S>(), Domain { env, fetcher });
+        self
+    }
+
+    pub async fn load<S: SecretsConfigEnum + 'static>(
+        &self,
+    ) -> Result<SecretValues<S>, ConfigError> {
+        let domain = self.domain::<S>()?;
+        load_secrets_with::<S>(domain.env.clone(), &domain.fetcher).await
+    }
+
+    pub fn fetcher<S: SecretsConfigEnum + 'static>(
+        &self,
+    ) -> Result<&CachedSecretsFetcher<F>, ConfigError> {
+        Ok(&self.domain::<S>()?.fetcher)
+    }
+
+    // Refetch the cached secrets of one config (ex. after its secret was
+    // rotated), leaving the other configs' caches as-is.
+    pub async fn refresh<S: SecretsConfigEnum + 'static>(&self) -> Result<(), ConfigError> {
+        let fetcher = self.fetcher::<S>()?;
+        for secret_id in fetcher.cached_secret_ids() {
+            fetcher.refresh(&secret_id).await?;
+        }
+        Ok(())
+    }
+
+    pub fn invalidate<S: SecretsConfigEnum + 'static>(&self) -> Result<(), ConfigError> {
+        let fetcher = self.fetcher::<S>()?;
+        for secret_id in fetcher.cached_secret_ids() {
+            fetcher.invalidate(&secret_id);
+        }
+        Ok(())
+    }
+
+    fn domain<S: SecretsConfigEnum + 'static>(&self) -> Result<&Domain<F>, ConfigError> {
+        self.domains
+            .get(&TypeId::of::<S>())
+            .ok_or_else(|| SecretsConfigNotRegistered::new(type_name::<S>()))
+    }
+}
+
+fn default_keys_env<K: SecretsEnvKeys>(env: &EnvVariables<K>) -> EnvVariables<SecretsEnvConfig> {
+    EnvVariables::from(
+        [
+            (SECRETS_REGION, env.get_optional(&K::region())),
+            (SECRETS_ID, env.get_optional(&K::id())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?.to_string())))
+        .collect::<HashMap<_, _>>(),
+    )
+}
+
+impl<F: SecretsFetcher + 'static> Default for SecretsManagerRegistry<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use crate::{
+        define_secret_key, define_secrets_config, CachedSecretsFetcher, EnvVariables,
+        FakeSecretsBackend, SecretsConfigEnum, SecretsEnvConfig, SECRETS_ID, SECRETS_REGION,
+    };
+
+    use super::SecretsManagerRegistry;
+
+    define_secret_key!(REGISTRY_BILLING_KEY);
+    define_secret_key!(REGISTRY_SEARCH_KEY);
+
+    define_secrets_config!(
+        BillingSecrets,
+        BillingKey => REGISTRY_BILLING_KEY,
+    );
+    define_secrets_config!(
+        SearchSecrets,
+        SearchKey => REGISTRY_SEARCH_KEY,
+    );
+    define_secrets_config!(
+        UnregisteredSecrets,
+        SearchKey => REGISTRY_SEARCH_KEY,
+    );
+
+    fn secrets_env(secret_id: &str) -> EnvVariables<SecretsEnvConfig> {
+        EnvVariables::from(HashMap::from([
+            (SECRETS_REGION, String::from("us-west-2")),
+            (SECRETS_ID, String::from(secret_id)),
+        ]))
+    }
+
+    fn cached(secret_id: &str, value: &str) -> CachedSecretsFetcher<FakeSecretsBackend> {
+        CachedSecretsFetcher::new(
+            FakeSecretsBackend::new().with_secret(secret_id, value),
+            Duration::from_secs(300),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_registry() {
+        let registry = SecretsManagerRegistry::new()
+            .register::<BillingSecrets>(
+                secrets_env("billing"),
+                cached("billing", r#"{"REGISTRY_BILLING_KEY": "sk_billing"}"#),
+            )
+            .register::<SearchSecrets>(
+                secrets_env("search"),
+                cached("search", r#"{"REGISTRY_SEARCH_KEY": "sk_search"}"#),
+            );
+
+        let billing = registry.load::<BillingSecrets>().await.unwrap();
+        assert_eq!(
+            billing.get(&BillingSecrets::BillingKey).unwrap(),
+            "sk_billing"
+        );
+        let search = registry.load::<SearchSecrets>().await.unwrap();
+        assert_eq!(search.get(&SearchSecrets::SearchKey).unwrap(), "sk_search");
+        assert!(registry.load::<UnregisteredSecrets>().await.is_err());
+
+        registry.invalidate::<BillingSecrets>().unwrap();
+        let billing_cache = registry.fetcher::<BillingSecrets>().unwrap();
+        assert!(billing_cache.cached_secret_ids().is_empty());
+        let search_cache = registry.fetcher::<SearchSecrets>().unwrap();
+        assert_eq!(search_cache.cached_secret_ids(), ["search"]);
+    }
+}
@@ -0,0 +1,150 @@
+use serde_json::Value;
+
+use super::cache::CachedSecretsFetcher;
+use super::errors::InvalidRotationNotification;
+use super::fetcher::SecretsFetcher;
+use crate::ConfigError;
+
+// Rotation notifications.
+// --------------------------------------------------
+
+// Closes the loop with Secrets Manager rotation: when notified that a secret
+// changed, the affected entries of a CachedSecretsFetcher are refetched right
+// away instead of when their TTL expires:
+//
+// // In an SQS consumer, a Lambda handler, or an HTTP endpoint:
+// let refreshed = handle_rotation_notification(&fetcher, &body).await?;
+// if !refreshed.is_empty() {
+//     secrets = load_secrets_with::<SecretsConfig>(env, &fetcher).await?;
+// }
+//
+// The body can be any of:
+// - a plain {"SecretId": "..."} payload (ex. posted by a rotation Lambda),
+// - an EventBridge event from aws.secretsmanager (ex. RotationSucceeded, or
+//   PutSecretValue through CloudTrail),
+// - any of the above wrapped in an SNS notification, or in the Records of an
+//   SQS or SNS Lambda event.
+//
+// Secrets may be identified by name or by ARN, and are matched against the
+// cached secret IDs either way. Returns the IDs of the cached secrets that were
+// refreshed. Subscribing to the topic or queue is left to the caller, so no
+// SNS or SQS dependency is needed (a minimal HTTP endpoint is available with
+// the "rotation-webhook" feature, see webhook.rs).
+pub async fn handle_rotation_notification<F: SecretsFetcher + 'static>(
+    cache: &CachedSecretsFetcher<F>,
+    body: &str,
+) -> Result<Vec<String>, ConfigError> {
+    let notified = rotated_secret_ids(body)?;
+    let affected = cache
+        .cached_secret_ids()
+        .into_iter()
+        .filter(|cached| notified.iter().any(|id| is_same_secret(cached, id)))
+        .collect::<Vec<_>>();
+    for secret_id in &affected {
+        cache.refresh(secret_id).await?;
+    }
+    Ok(affected)
+}
+
+fn rotated_secret_ids(body: &str) -> Result<Vec<String>, ConfigError> {
+    let json = serde_json::from_str::<Value>(body)
+        .map_err(|_| InvalidRotationNotification::new("body is not valid JSON"))?;
+    let mut ids = Vec::new();
+    collect_secret_ids(&json, &mut ids);
+    if ids.is_empty() {
+        return Err(InvalidRotationNotification::new("no secret ID found"));
+    }
+    Ok(ids)
+}
+
+fn collect_secret_ids(json: &Value, ids: &mut Vec<String>) {
+    // SNS notifications, and SQS messages, carry the payload as a string.
+    for wrapped in [&json["Message"], &json["body"], &json["Sns"]["Message"]] {
+        if let Some(inner) = wrapped
+            .as_str()
+            .and_then(|inner| serde_json::from_str::<Value>(inner).ok())
+        {
+            collect_secret_ids(&inner, ids);
+        }
+    }
+    if let Some(records) = json["Records"].as_array() {
+        for record in records {
+            collect_secret_ids(record, ids);
+        }
+    }
+    let detail = &json["detail"];
+    for id in [
+        &json["SecretId"],
+        &detail["additionalEventData"]["SecretId"],
+        &detail["requestParameters"]["secretId"],
+    ] {
+        if let Some(id) = id.as_str() {
+            ids.push(id.to_string());
+        }
+    }
+}
+
+// Secret ARNs end in the secret name followed by a random 6-character suffix,
+// ex. arn:aws:secretsmanager:us-west-2:123456789012:secret:myapp/main-a1b2c3.
+fn is_same_secret(cached: &str, notified: &str) -> bool {
+    cached == notified || secret_name(cached) == secret_name(notified)
+}
+
+fn secret_name(secret_id: &str) -> &str {
+    let Some((_, name)) = secret_id.split_once(":secret:") else {
+        return secret_id;
+    };
+    match name.rsplit_once('-') {
+        Some((base, suffix)) if suffix.len() == 6 => base,
+        _ => name,
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{CachedSecretsFetcher, FakeSecretsBackend, SecretsFetcher};
+
+    use super::{handle_rotation_notification, rotated_secret_ids};
+
+    #[test]
+    fn test_rotated_secret_ids() {
+        assert_eq!(
+            rotated_secret_ids(r#"{"SecretId": "myapp/main"}"#).unwrap(),
+            vec!["myapp/main"]
+        );
+        let sqs_of_sns_of_eventbridge = r#"{"Records": [{"body": "{\"Type\": \"Notification\", \"Message\": \"{\\\"source\\\": \\\"aws.secretsmanager\\\", \\\"detail\\\": {\\\"additionalEventData\\\": {\\\"SecretId\\\": \\\"arn:aws:secretsmanager:us-west-2:123456789012:secret:myapp/main-a1b2c3\\\"}}}\"}"}]}"#;
+        assert_eq!(
+            rotated_secret_ids(sqs_of_sns_of_eventbridge).unwrap(),
+            vec!["arn:aws:secretsmanager:us-west-2:123456789012:secret:myapp/main-a1b2c3"]
+        );
+        assert!(rotated_secret_ids(r#"{"Type": "Notification"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_rotation_notification() {
+        let backend = FakeSecretsBackend::new().with_secret("myapp/main", "v1");
+        let cache = CachedSecretsFetcher::new(backend, Duration::from_secs(3600));
+        cache.fetch_secret_string("myapp/main").await.unwrap();
+
+        cache.fetcher().set_secret("myapp/main", "v2");
+        let refreshed = handle_rotation_notification(
+            &cache,
+            r#"{"SecretId": "arn:aws:secretsmanager:us-west-2:123456789012:secret:myapp/main-a1b2c3"}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(refreshed, vec!["myapp/main"]);
+        assert_eq!(cache.fetch_secret_string("myapp/main").await.unwrap(), "v2");
+        assert_eq!(cache.fetcher().fetch_count(), 2);
+
+        let refreshed = handle_rotation_notification(&cache, r#"{"SecretId": "other"}"#)
+            .await
+            .unwrap();
+        assert!(refreshed.is_empty());
+    }
+}
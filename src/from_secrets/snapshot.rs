@@ -0,0 +1,22 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::from_env::snapshot::{write_snapshot_file, MASKED_VALUE};
+use crate::ConfigError;
+
+use super::{SecretValues, SecretsConfigEnum};
+
+// Unlike EnvVariables, secret values are never written to snapshots. Only the
+// key names are included, so postmortems can still confirm which secrets were
+// loaded.
+impl<T: SecretsConfigEnum> SecretValues<T> {
+    pub fn write_snapshot(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        let snapshot = self
+            .0
+            .keys()
+            .map(|key| (key.to_string(), Value::String(MASKED_VALUE.to_string())))
+            .collect();
+        write_snapshot_file(path.as_ref(), snapshot)
+    }
+}
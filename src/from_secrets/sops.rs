@@ -0,0 +1,296 @@
+use std::io::Read;
+use std::path::Path;
+
+use aes_gcm::aead::consts::U32;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::aes::Aes256;
+use aes_gcm::{AesGcm, Nonce};
+use aws_config::BehaviorVersion;
+use aws_sdk_kms::config::Region;
+use aws_sdk_kms::primitives::Blob;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::Value;
+
+use crate::error_chain::ErrorChain;
+use crate::from_env::file::parse_root;
+use crate::ConfigError;
+use crate::{FailedToReadConfigFile, FileSource, Format};
+
+use super::errors::{FailedToDecryptSopsDataKey, FailedToDecryptSopsValue, InvalidSopsFile};
+use super::{load_secrets_from_source, SecretValues, SecretsConfigEnum};
+
+// SOPS-encrypted files.
+// --------------------------------------------------
+
+// Secrets committed to the repo as a SOPS-encrypted JSON or YAML file can be
+// decrypted at startup, instead of out-of-band before the process starts:
+//
+// let secrets = load_secrets_from_sops::<SecretsConfig>("secrets.enc.yaml", Format::Yaml).await?;
+//
+// The file's data key is decrypted with the first matching age identity (read
+// from SOPS_AGE_KEY or SOPS_AGE_KEY_FILE, as with the sops CLI), falling back
+// to the file's KMS keys using the default AWS credentials. Values are then
+// decrypted in place, and nested keys are matched by their dotted path, as
+// with load_from_file.
+//
+// The file's MAC is not verified. Each value is still authenticated against
+// its own key path, so values can't be swapped between keys, but whole keys
+// could be removed without being detected. Available with the "sops" feature.
+pub async fn load_secrets_from_sops<T: SecretsConfigEnum>(
+    path: impl AsRef<Path>,
+    format: Format,
+) -> Result<SecretValues<T>, ConfigError> {
+    let path_str = path.as_ref().display().to_string();
+    let contents = std::fs::read_to_string(path.as_ref())
+        .map_err(|e| FailedToReadConfigFile::with_source(&path_str, e))?;
+    let mut root = parse_root(&contents, format, &path_str)?;
+    let metadata = root
+        .as_object_mut()
+        .and_then(|map| map.remove("sops"))
+        .ok_or_else(|| InvalidSopsFile::new(&path_str, "missing 'sops' metadata"))?;
+    let data_key = decrypt_data_key(&metadata, &path_str).await?;
+    decrypt_tree(&mut root, &data_key, &mut Vec::new(), &path_str)?;
+    load_secrets_from_source(&FileSource::from_value(root))
+}
+
+// Data key.
+// --------------------------------------------------
+
+async fn decrypt_data_key(metadata: &Value, path: &str) -> Result<Vec<u8>, ConfigError> {
+    let mut failures = Vec::new();
+
+    let age_entries = metadata.get("age").and_then(Value::as_array);
+    if let Some(entries) = age_entries.filter(|entries| !entries.is_empty()) {
+        let identities = load_age_identities(path)?;
+        for enc in entries
+            .iter()
+            .filter_map(|e| e.get("enc").and_then(Value::as_str))
+        {
+            match decrypt_age(enc, &identities) {
+                Ok(key) => return Ok(key),
+                Err(reason) => failures.push(format!("age: {reason}")),
+            }
+        }
+    }
+
+    let kms_entries = metadata.get("kms").and_then(Value::as_array);
+    for entry in kms_entries.into_iter().flatten() {
+        let (Some(arn), Some(enc)) = (
+            entry.get("arn").and_then(Value::as_str),
+            entry.get("enc").and_then(Value::as_str),
+        ) else {
+            continue;
+        };
+        match decrypt_kms(arn, enc).await {
+            Ok(key) => return Ok(key),
+            Err(reason) => failures.push(format!("kms '{arn}': {reason}")),
+        }
+    }
+
+    Err(FailedToDecryptSopsDataKey::with_debug(path, &failures))
+}
+
+fn load_age_identities(path: &str) -> Result<Vec<age::x25519::Identity>, ConfigError> {
+    let keys = match (
+        std::env::var("SOPS_AGE_KEY"),
+        std::env::var("SOPS_AGE_KEY_FILE"),
+    ) {
+        (Ok(keys), _) => keys,
+        (Err(_), Ok(key_file)) => std::fs::read_to_string(&key_file)
+            .map_err(|e| FailedToReadConfigFile::with_source(&key_file, e))?,
+        (Err(_), Err(_)) => return Ok(Vec::new()),
+    };
+    keys.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("AGE-SECRET-KEY-"))
+        .map(|line| {
+            line.parse::<age::x25519::Identity>()
+                .map_err(|e| InvalidSopsFile::with_debug(path, "invalid age identity", &e))
+        })
+        .collect()
+}
+
+fn decrypt_age(enc: &str, identities: &[age::x25519::Identity]) -> Result<Vec<u8>, String> {
+    if identities.is_empty() {
+        return Err("no identities in SOPS_AGE_KEY or SOPS_AGE_KEY_FILE".to_string());
+    }
+    let armored = age::armor::ArmoredReader::new(enc.as_bytes());
+    let decryptor = match age::Decryptor::new(armored) {
+        Ok(age::Decryptor::Recipients(decryptor)) => decryptor,
+        Ok(_) => return Err("passphrase-encrypted data keys are not supported".to_string()),
+        Err(e) => return Err(format!("{:?}", ErrorChain(&e))),
+    };
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|i| i as &dyn age::Identity))
+        .map_err(|e| format!("{:?}", ErrorChain(&e)))?;
+    let mut key = Vec::new();
+    reader
+        .read_to_end(&mut key)
+        .map_err(|e| format!("{:?}", ErrorChain(&e)))?;
+    Ok(key)
+}
+
+async fn decrypt_kms(arn: &str, enc: &str) -> Result<Vec<u8>, String> {
+    // ARN format: arn:aws:kms:<region>:<account>:key/<id>.
+    let region = arn
+        .split(':')
+        .nth(3)
+        .filter(|region| !region.is_empty())
+        .ok_or_else(|| "invalid key ARN".to_string())?;
+    let ciphertext = STANDARD
+        .decode(enc)
+        .map_err(|e| format!("{:?}", ErrorChain(&e)))?;
+    let config = aws_config::defaults(BehaviorVersion::v2024_03_28())
+        .region(Region::new(region.to_string()))
+        .load()
+        .await;
+    let output = aws_sdk_kms::Client::new(&config)
+        .decrypt()
+        .key_id(arn)
+        .ciphertext_blob(Blob::new(ciphertext))
+        .send()
+        .await
+        .map_err(|e| format!("{:?}", ErrorChain(&e)))?;
+    output
+        .plaintext()
+        .map(|blob| blob.as_ref().to_vec())
+        .ok_or_else(|| "no plaintext returned".to_string())
+}
+
+// Values.
+// --------------------------------------------------
+
+#[derive(Debug, PartialEq, Eq)]
+struct EncryptedValue {
+    data: Vec<u8>,
+    iv: Vec<u8>,
+    tag: Vec<u8>,
+    value_type: String,
+}
+
+// Values are encrypted individually with AES-256-GCM, using the path of keys
+// leading to the value (ex. "database:password:") as additional data. Values
+// that are not encrypted (ex. keys with the _unencrypted suffix) are left
+// unchanged.
+fn decrypt_tree(
+    value: &mut Value,
+    data_key: &[u8],
+    key_path: &mut Vec<String>,
+    path: &str,
+) -> Result<(), ConfigError> {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                key_path.push(key.clone());
+                decrypt_tree(child, data_key, key_path, path)?;
+                key_path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for item in items.iter_mut() {
+                decrypt_tree(item, data_key, key_path, path)?;
+            }
+        }
+        Value::String(s) if s.starts_with("ENC[") => {
+            let key_name = key_path.join(".");
+            let aad = format!("{}:", key_path.join(":"));
+            *s = decrypt_value(s, data_key, &aad)
+                .map_err(|reason| FailedToDecryptSopsValue::with_debug(path, &key_name, &reason))?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn decrypt_value(encrypted: &str, data_key: &[u8], aad: &str) -> Result<String, String> {
+    let value = parse_encrypted_value(encrypted)
+        .ok_or_else(|| "malformed ENC[AES256_GCM,...] value".to_string())?;
+    let cipher = AesGcm::<Aes256, U32>::new_from_slice(data_key)
+        .map_err(|e| format!("invalid data key: {e}"))?;
+    let mut msg = value.data;
+    msg.extend_from_slice(&value.tag);
+    let plaintext = cipher
+        .decrypt(
+            Nonce::<U32>::from_slice(&value.iv),
+            Payload {
+                msg: &msg,
+                aad: aad.as_bytes(),
+            },
+        )
+        .map_err(|_| "authentication failed".to_string())?;
+    let plaintext = String::from_utf8(plaintext).map_err(|_| "not valid UTF-8".to_string())?;
+    Ok(match value.value_type.as_str() {
+        // SOPS stores booleans as "True" / "False".
+        "bool" => plaintext.to_lowercase(),
+        _ => plaintext,
+    })
+}
+
+fn parse_encrypted_value(encrypted: &str) -> Option<EncryptedValue> {
+    let inner = encrypted
+        .strip_prefix("ENC[AES256_GCM,")?
+        .strip_suffix(']')?;
+    let (mut data, mut iv, mut tag, mut value_type) = (None, None, None, None);
+    for field in inner.split(',') {
+        let (name, content) = field.split_once(':')?;
+        match name {
+            "data" => data = Some(STANDARD.decode(content).ok()?),
+            "iv" => iv = Some(STANDARD.decode(content).ok()?),
+            "tag" => tag = Some(STANDARD.decode(content).ok()?),
+            "type" => value_type = Some(content.to_string()),
+            _ => {}
+        }
+    }
+    Some(EncryptedValue {
+        data: data?,
+        iv: iv?,
+        tag: tag?,
+        value_type: value_type?,
+    })
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::{define_secret_key, define_secrets_config, Format, SecretsConfigEnum};
+
+    use super::{load_secrets_from_sops, parse_encrypted_value, EncryptedValue};
+
+    define_secret_key!(SOPS_TEST_KEY);
+
+    define_secrets_config!(
+        SopsConfig,
+        SopsTestKey => SOPS_TEST_KEY,
+    );
+
+    #[test]
+    fn test_parse_encrypted_value() {
+        assert_eq!(
+            parse_encrypted_value("ENC[AES256_GCM,data:aGk=,iv:AAEC,tag:AwQF,type:str]"),
+            Some(EncryptedValue {
+                data: b"hi".to_vec(),
+                iv: vec![0, 1, 2],
+                tag: vec![3, 4, 5],
+                value_type: "str".to_string(),
+            })
+        );
+        assert_eq!(parse_encrypted_value("plaintext"), None);
+        assert_eq!(
+            parse_encrypted_value("ENC[AES256_GCM,data:aGk=,iv:AAEC,type:str]"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_from_sops_without_metadata() {
+        let path = std::env::temp_dir().join("fractic_env_config_sops_no_metadata.json");
+        std::fs::write(&path, r#"{ "SOPS_TEST_KEY": "plaintext" }"#).unwrap();
+
+        assert!(load_secrets_from_sops::<SopsConfig>(&path, Format::Json)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,40 @@
+// Secret key specifications.
+// --------------------------------------------------
+
+// Like KeySpec for env variables, each key of a SecretsConfigEnum can declare
+// options inline in the define_secrets_config! macro:
+//
+// define_secrets_config!(
+//     SecretsConfig,
+//     OpenAIKey => OPENAI_KEY,
+//     OAuthClient => OAUTH_CLIENT { json = true },
+// );
+//
+// Secrets Manager values that are numbers or booleans are accepted for any key
+// and read as their JSON text (ex. 8080, true). Objects and arrays are only
+// accepted for keys declared { json = true }, and are then read as the
+// serialized JSON, ready to be deserialized by the caller.
+#[derive(Debug, Clone)]
+pub struct SecretKeySpec {
+    name: &'static str,
+    json: bool,
+}
+
+impl SecretKeySpec {
+    pub fn new(name: &'static str) -> Self {
+        Self { name, json: false }
+    }
+
+    pub fn json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.json
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::poll_fn;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::ConfigError;
+use crate::{EnvVariables, SecretsEnvConfig, SECRETS_ID, SECRETS_REGION};
+
+use super::errors::{InvalidTenantId, InvalidTenantTemplate};
+use super::fetcher::SecretsFetcher;
+use super::{load_secrets_with, SecretValues, SecretsConfigEnum};
+
+// Multi-tenant secrets.
+// --------------------------------------------------
+
+// For multi-tenant services, where each tenant's secrets are stored under their
+// own secret ID:
+//
+// let tenants = TenantSecrets::<TenantConfig, _>::new(region, "app/{tenant}/secrets", fetcher)?
+//     .capacity(256)
+//     .ttl(Duration::from_secs(3600));
+// let secrets = tenants.load_secrets_for_tenant(&tenant_id).await?;
+//
+// The secret ID is resolved by replacing {tenant} in the template (which is
+// rejected by new if it doesn't contain it, since every tenant would otherwise
+// share the same secret). Loaded secrets are cached per tenant, evicting the
+// least recently used tenant once the capacity (default 128) is reached.
+// Without a ttl, cached secrets are kept until then, so a tenant whose secret
+// is rotated should be evicted (ex. from the rotation handler, see
+// rotation.rs). Concurrent loads of a tenant that isn't cached share a single
+// fetch. Tenant IDs are restricted to alphanumeric characters, '-' and '_', so
+// that a tenant ID can never resolve to another tenant's secret ID.
+#[derive(Debug)]
+pub struct TenantSecrets<T: SecretsConfigEnum, F: SecretsFetcher> {
+    region: String,
+    template: String,
+    fetcher: F,
+    capacity: usize,
+    ttl: Option<Duration>,
+    // Most recently used first.
+    cache: Mutex<VecDeque<CachedTenant<T>>>,
+    loading: Mutex<HashMap<String, Arc<PendingLoad<T>>>>,
+}
+
+#[derive(Debug)]
+struct CachedTenant<T: SecretsConfigEnum> {
+    tenant_id: String,
+    secrets: Arc<SecretValues<T>>,
+    loaded_at: Instant,
+}
+
+type LoadResult<T> = Result<Arc<SecretValues<T>>, ConfigError>;
+
+// Load of a tenant in progress, whose result is shared with the concurrent
+// loads of the same tenant. Doesn't depend on a particular runtime, like the
+// rest of loading.
+#[derive(Debug)]
+struct PendingLoad<T: SecretsConfigEnum>(Mutex<PendingState<T>>);
+
+#[derive(Debug)]
+enum PendingState<T: SecretsConfigEnum> {
+    Loading(Vec<Waker>),
+    Done(LoadResult<T>),
+    // The leading load was dropped before completing.
+    Abandoned,
+}
+
+static TENANT_PLACEHOLDER: &str = "{tenant}";
+const DEFAULT_CAPACITY: usize = 128;
+
+impl<T: SecretsConfigEnum, F: SecretsFetcher> TenantSecrets<T, F> {
+    pub fn new(
+        region: impl Into<String>,
+        template: impl Into<String>,
+        fetcher: F,
+    ) -> Result<Self, ConfigError> {
+        let template = template.into();
+        if !template.contains(TENANT_PLACEHOLDER) {
+            return Err(InvalidTenantTemplate::new(&template));
+        }
+        Ok(Self {
+            region: region.into(),
+            template,
+            fetcher,
+            capacity: DEFAULT_CAPACITY,
+            ttl: None,
+            cache: Default::default(),
+            loading: Default::default(),
+        })
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    // Reload a tenant's secrets once they were cached for longer than this.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn secret_id_for(&self, tenant_id: &str) -> Result<String, ConfigError> {
+        let valid = !tenant_id.is_empty()
+            && tenant_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(InvalidTenantId::new(tenant_id));
+        }
+        Ok(self.template.replace(TENANT_PLACEHOLDER, tenant_id))
+    }
+
+    pub async fn load_secrets_for_tenant(
+        &self,
+        tenant_id: &str,
+    ) -> Result<Arc<SecretValues<T>>, ConfigError> {
+        let secret_id = self.secret_id_for(tenant_id)?;
+        if let Some(secrets) = self.cached(tenant_id) {
+            return Ok(secrets);
+        }
+
+        let leader = {
+            let mut loading = self.loading.lock().unwrap_or_else(PoisonError::into_inner);
+            match loading.get(tenant_id) {
+                Some(pending) => Err(pending.clone()),
+                None => {
+                    let pending = Arc::new(PendingLoad::default());
+                    loading.insert(tenant_id.to_string(), pending.clone());
+                    Ok(pending)
+                }
+            }
+        };
+        match leader {
+            Ok(pending) => {
+                let guard = PendingGuard {
+                    loading: &self.loading,
+                    tenant_id,
+                    pending,
+                };
+                let result = self.load(tenant_id, secret_id).await;
+                guard.pending.finish(PendingState::Done(result.clone()));
+                result
+            }
+            Err(pending) => match pending.wait().await {
+                Some(result) => result,
+                None => self.load(tenant_id, secret_id).await,
+            },
+        }
+    }
+
+    pub fn evict(&self, tenant_id: &str) {
+        self.cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|cached| cached.tenant_id != tenant_id);
+    }
+
+    async fn load(&self, tenant_id: &str, secret_id: String) -> LoadResult<T> {
+        let env = EnvVariables::<SecretsEnvConfig>::from(HashMap::from([
+            (SECRETS_REGION, self.region.clone()),
+            (SECRETS_ID, secret_id),
+        ]));
+        let secrets = Arc::new(load_secrets_with::<T>(env, &self.fetcher).await?);
+
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        cache.retain(|cached| cached.tenant_id != tenant_id);
+        cache.push_front(CachedTenant {
+            tenant_id: tenant_id.to_string(),
+            secrets: secrets.clone(),
+            loaded_at: Instant::now(),
+        });
+        cache.truncate(self.capacity);
+        Ok(secrets)
+    }
+
+    fn cached(&self, tenant_id: &str) -> Option<Arc<SecretValues<T>>> {
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        let index = cache
+            .iter()
+            .position(|cached| cached.tenant_id == tenant_id)?;
+        let entry = cache.remove(index)?;
+        if self.ttl.is_some_and(|ttl| entry.loaded_at.elapsed() >= ttl) {
+            return None;
+        }
+        let secrets = entry.secrets.clone();
+        cache.push_front(entry);
+        Some(secrets)
+    }
+}
+
+impl<T: SecretsConfigEnum> Default for PendingLoad<T> {
+    fn default() -> Self {
+        Self(Mutex::new(PendingState::Loading(Vec::new())))
+    }
+}
+
+impl<T: SecretsConfigEnum> PendingLoad<T> {
+    fn lock(&self) -> std::sync::MutexGuard<'_, PendingState<T>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // The leader's result, or None if it was abandoned.
+    async fn wait(&self) -> Option<LoadResult<T>> {
+        poll_fn(|cx| match &mut *self.lock() {
+            PendingState::Loading(wakers) => {
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+            PendingState::Done(result) => Poll::Ready(Some(result.clone())),
+            PendingState::Abandoned => Poll::Ready(None),
+        })
+        .await
+    }
+
+    fn finish(&self, state: PendingState<T>) {
+        let previous = std::mem::replace(&mut *self.lock(), state);
+        if let PendingState::Loading(wakers) = previous {
+            wakers.into_iter().for_each(Waker::wake);
+        }
+    }
+}
+
+// Removes the pending load once the leading load completes, or is dropped
+// before completing (in which case waiters fall back to loading themselves).
+struct PendingGuard<'a, T: SecretsConfigEnum> {
+    loading: &'a Mutex<HashMap<String, Arc<PendingLoad<T>>>>,
+    tenant_id: &'a str,
+    pending: Arc<PendingLoad<T>>,
+}
+
+impl<T: SecretsConfigEnum> Drop for PendingGuard<'_, T> {
+    fn drop(&mut self) {
+        self.loading
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(self.tenant_id);
+        if matches!(*self.pending.lock(), PendingState::Loading(_)) {
+            self.pending.finish(PendingState::Abandoned);
+        }
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{define_secret_key, define_secrets_config, FakeSecretsBackend, SecretsConfigEnum};
+
+    use super::TenantSecrets;
+
+    define_secret_key!(TENANT_API_KEY);
+
+    define_secrets_config!(
+        TenantConfig,
+        ApiKey => TENANT_API_KEY,
+    );
+
+    fn tenants() -> TenantSecrets<TenantConfig, FakeSecretsBackend> {
+        let backend = FakeSecretsBackend::new()
+            .with_secret("app/acme/secrets", r#"{"TENANT_API_KEY": "acme-key"}"#)
+            .with_secret("app/globex/secrets", r#"{"TENANT_API_KEY": "globex-key"}"#);
+        TenantSecrets::new("us-west-2", "app/{tenant}/secrets", backend).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_load_secrets_for_tenant() {
+        let tenants = tenants();
+        let acme = tenants.load_secrets_for_tenant("acme").await.unwrap();
+        assert_eq!(acme.get(&TenantConfig::ApiKey).unwrap(), "acme-key");
+        let globex = tenants.load_secrets_for_tenant("globex").await.unwrap();
+        assert_eq!(globex.get(&TenantConfig::ApiKey).unwrap(), "globex-key");
+
+        tenants.load_secrets_for_tenant("acme").await.unwrap();
+        assert_eq!(tenants.fetcher.fetch_count(), 2);
+        assert!(tenants.load_secrets_for_tenant("initech").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_loads_share_a_fetch() {
+        let tenants = tenants();
+        tenants.fetcher.set_latency(Duration::from_millis(50));
+        let (first, second) = tokio::join!(
+            tenants.load_secrets_for_tenant("acme"),
+            tenants.load_secrets_for_tenant("acme"),
+        );
+        assert_eq!(
+            first.unwrap().get(&TenantConfig::ApiKey).unwrap(),
+            "acme-key"
+        );
+        assert_eq!(
+            second.unwrap().get(&TenantConfig::ApiKey).unwrap(),
+            "acme-key"
+        );
+        assert_eq!(tenants.fetcher.fetch_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_cache_evicts_least_recently_used() {
+        let tenants = tenants().capacity(1);
+        tenants.load_secrets_for_tenant("acme").await.unwrap();
+        tenants.load_secrets_for_tenant("globex").await.unwrap();
+        tenants.load_secrets_for_tenant("acme").await.unwrap();
+        assert_eq!(tenants.fetcher.fetch_count(), 3);
+
+        let tenants = self::tenants().ttl(Duration::ZERO);
+        tenants.load_secrets_for_tenant("acme").await.unwrap();
+        tenants.load_secrets_for_tenant("acme").await.unwrap();
+        assert_eq!(tenants.fetcher.fetch_count(), 2);
+    }
+
+    #[test]
+    fn test_invalid_tenant_id() {
+        let tenants = tenants();
+        assert_eq!(tenants.secret_id_for("acme").unwrap(), "app/acme/secrets");
+        assert!(tenants.secret_id_for("../globex").is_err());
+        assert!(tenants.secret_id_for("").is_err());
+
+        let template = TenantSecrets::<TenantConfig, _>::new(
+            "us-west-2",
+            "app/shared/secrets",
+            FakeSecretsBackend::new(),
+        );
+        assert!(template.is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use super::cache::CachedSecretsFetcher;
+use super::errors::{
+    FailedToServeRotationWebhook, InvalidRotationNotification, RotationWebhookOutsideRuntime,
+};
+use super::fetcher::SecretsFetcher;
+use super::rotation::handle_rotation_notification;
+use crate::ConfigError;
+
+// Rotation webhook.
+// --------------------------------------------------
+
+// For services without an SQS consumer or Lambda trigger of their own, rotation
+// notifications (see rotation.rs) can be received over HTTP instead, ex. from
+// the rotation Lambda, or from an EventBridge API destination:
+//
+// let fetcher = Arc::new(CachedSecretsFetcher::new(
+//     AwsSecretsFetcher::new(region).await,
+//     Duration::from_secs(3600),
+// ));
+// let webhook = serve_rotation_webhook("10.0.0.12:9090", fetcher.clone())?;
+//
+// Every POST is handled as a notification, whatever its path, and answered with
+// the JSON list of the refreshed secret IDs (400 if the body isn't a
+// notification, 500 if refetching failed). SNS subscription confirmations are
+// not handled, so SNS topics should go through SQS or a Lambda instead. The
+// endpoint is unauthenticated (at worst, it triggers refetches), so it should
+// be bound to a private interface.
+//
+// Must be started from within a tokio runtime, whose tasks run the refreshes.
+// Connections are accepted by a thread of their own, which stops when the
+// RotationWebhook is dropped, and each is served on a separate thread, which
+// gives up on requests not received within 10 seconds. Available with the
+// "rotation-webhook" feature.
+#[derive(Debug)]
+pub struct RotationWebhook {
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+// Notifications are small JSON documents; anything larger is rejected unread.
+const MAX_BODY_LEN: usize = 256 * 1024;
+
+// Time allowed for receiving a whole request, however slowly it is sent.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn serve_rotation_webhook<F: SecretsFetcher + 'static>(
+    addr: &str,
+    cache: Arc<CachedSecretsFetcher<F>>,
+) -> Result<RotationWebhook, ConfigError> {
+    let runtime = tokio::runtime::Handle::try_current()
+        .map_err(|_| RotationWebhookOutsideRuntime::new(addr))?;
+    let (addr, listener) = TcpListener::bind(addr)
+        .and_then(|listener| Ok((listener.local_addr()?, listener)))
+        .map_err(|e| FailedToServeRotationWebhook::with_source(addr, e))?;
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let handle = std::thread::spawn({
+        let stopped = stopped.clone();
+        move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let runtime = runtime.clone();
+                    let cache = cache.clone();
+                    std::thread::spawn(move || respond(stream, &runtime, &cache));
+                }
+            }
+        }
+    });
+    Ok(RotationWebhook {
+        addr,
+        stopped,
+        handle: Some(handle),
+    })
+}
+
+impl RotationWebhook {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for RotationWebhook {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake up the accept loop, so it sees the flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn respond<F: SecretsFetcher + 'static>(
+    stream: TcpStream,
+    runtime: &tokio::runtime::Handle,
+    cache: &Arc<CachedSecretsFetcher<F>>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(DeadlineReader {
+        stream,
+        deadline: Instant::now() + REQUEST_TIMEOUT,
+    });
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let (status, body) = if !request_line.starts_with("POST ") {
+        ("405 Method Not Allowed", String::new())
+    } else if content_length > MAX_BODY_LEN {
+        ("413 Payload Too Large", String::new())
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        let body = String::from_utf8_lossy(&body).into_owned();
+        // Refreshed on the runtime, while this thread waits for the result.
+        let (sender, receiver) = mpsc::channel();
+        let cache = cache.clone();
+        runtime.spawn(async move {
+            let _ = sender.send(handle_rotation_notification(&cache, &body).await);
+        });
+        match receiver.recv() {
+            Ok(Ok(refreshed)) => {
+                let refreshed = refreshed.into_iter().map(Value::String).collect();
+                ("200 OK", Value::Array(refreshed).to_string())
+            }
+            Ok(Err(e)) if e.code() == Some(InvalidRotationNotification::CODE) => {
+                ("400 Bad Request", e.to_string())
+            }
+            Ok(Err(e)) => ("500 Internal Server Error", e.to_string()),
+            Err(_) => ("500 Internal Server Error", String::new()),
+        }
+    };
+    let mut stream = reader.into_inner().stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+// Reads from the stream until the deadline, rather than allowing a timeout for
+// each read.
+struct DeadlineReader {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use crate::{CachedSecretsFetcher, FakeSecretsBackend, SecretsFetcher};
+
+    use super::serve_rotation_webhook;
+
+    // Sent from the blocking pool, so that the runtime is free to run the
+    // refresh meanwhile.
+    async fn post(addr: SocketAddr, body: &'static str) -> String {
+        tokio::task::spawn_blocking(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(
+                stream,
+                "POST /rotated HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rotation_webhook() {
+        let backend = FakeSecretsBackend::new().with_secret("myapp/main", "v1");
+        let cache = Arc::new(CachedSecretsFetcher::new(
+            backend,
+            Duration::from_secs(3600),
+        ));
+        cache.fetch_secret_string("myapp/main").await.unwrap();
+        let webhook = serve_rotation_webhook("127.0.0.1:0", cache.clone()).unwrap();
+        // A client that never sends its request doesn't hold up the others.
+        let _idle = TcpStream::connect(webhook.local_addr()).unwrap();
+
+        cache.fetcher().set_secret("myapp/main", "v2");
+        let response = post(webhook.local_addr(), r#"{"SecretId": "myapp/main"}"#).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with(r#"["myapp/main"]"#));
+        assert_eq!(cache.fetch_secret_string("myapp/main").await.unwrap(), "v2");
+
+        let response = post(webhook.local_addr(), r#"{"Type": "Notification"}"#).await;
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request"));
+
+        let dropped_at = Instant::now();
+        drop(webhook);
+        assert!(dropped_at.elapsed() < Duration::from_secs(5));
+    }
+}
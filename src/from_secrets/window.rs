@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::panic::Location;
+
+use crate::from_env::values::decode_base64;
+use crate::ConfigError;
+use crate::Secret;
+
+use super::audit::record_access;
+use super::errors::InvalidSecretsCloneInto;
+use super::{SecretValues, SecretsConfigEnum};
+
+// Scoped windows.
+// --------------------------------------------------
+
+// Lends a narrowed view of a SecretValues for the duration of a closure,
+// without cloning the values, so handler code only ever sees the keys it
+// declares:
+//
+// let receipt = secrets.scoped::<StripeConfig, _>(|stripe| {
+//     charge_card(stripe.get(&StripeConfig::StripeKey)?, amount)
+// })?;
+//
+// As with clone_into, the child config must be a subset of the parent. The
+// view can't escape the closure, and reads through it are audited like reads
+// of the parent (see audit.rs).
+#[derive(Debug)]
+pub struct SecretsWindow<'a, T: SecretsConfigEnum>(
+    &'a HashMap<&'static str, String>,
+    PhantomData<T>,
+);
+
+impl<ParentConfig: SecretsConfigEnum> SecretValues<ParentConfig> {
+    pub fn scoped<ChildConfig: SecretsConfigEnum, R>(
+        &self,
+        f: impl FnOnce(&SecretsWindow<'_, ChildConfig>) -> R,
+    ) -> Result<R, ConfigError> {
+        for value in ChildConfig::value_list() {
+            self.get_raw(value.as_str())
+                .map_err(|_critical_error| InvalidSecretsCloneInto::new(value.as_str()))?;
+        }
+        Ok(f(&SecretsWindow(&self.0, PhantomData)))
+    }
+}
+
+impl<T: SecretsConfigEnum> SecretsWindow<'_, T> {
+    #[track_caller]
+    pub fn get(&self, key: &T) -> Result<&str, ConfigError> {
+        record_access(key.as_str(), Location::caller());
+        self.get_raw(key.as_str())
+    }
+    #[track_caller]
+    pub fn get_cloned(&self, key: &T) -> Result<String, ConfigError> {
+        self.get(key).map(str::to_string)
+    }
+    #[track_caller]
+    pub fn get_secret(&self, key: &T) -> Result<Secret<String>, ConfigError> {
+        self.get_cloned(key).map(Secret::new)
+    }
+    #[track_caller]
+    pub fn get_base64(&self, key: &T) -> Result<Vec<u8>, ConfigError> {
+        decode_base64(key.as_str(), self.get(key)?)
+    }
+    fn get_raw(&self, key: &str) -> Result<&str, ConfigError> {
+        self.0.get(key).map(String::as_str).ok_or(ConfigError::critical(&format!(
+            "Should be guaranteed any secret key of a SecretsWindow is present in its parent SecretValues, but {key} is missing."
+        )))
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{define_secret_key, define_secrets_config, SecretValues, SecretsConfigEnum};
+
+    define_secret_key!(WINDOW_TEST_OPENAI_KEY);
+    define_secret_key!(WINDOW_TEST_STRIPE_KEY);
+
+    define_secrets_config!(
+        WindowParentConfig,
+        OpenAIKey => WINDOW_TEST_OPENAI_KEY,
+        StripeKey => WINDOW_TEST_STRIPE_KEY,
+    );
+
+    define_secrets_config!(
+        WindowStripeConfig,
+        StripeKey => WINDOW_TEST_STRIPE_KEY,
+    );
+
+    #[test]
+    fn test_scoped() {
+        let parent: SecretValues<WindowParentConfig> = SecretValues::from(HashMap::from([
+            (WINDOW_TEST_OPENAI_KEY, String::from("sk-test")),
+            (WINDOW_TEST_STRIPE_KEY, String::from("sk_test")),
+        ]));
+        let stripe_key = parent
+            .scoped::<WindowStripeConfig, _>(|stripe| {
+                stripe.get_cloned(&WindowStripeConfig::StripeKey).unwrap()
+            })
+            .unwrap();
+        assert_eq!(stripe_key, "sk_test");
+
+        let child: SecretValues<WindowStripeConfig> = parent.clone_into().unwrap();
+        assert!(child.scoped::<WindowParentConfig, _>(|_| ()).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{Map, Value};
+
+#[cfg(feature = "aws")]
+use crate::{CachedSecretsFetcher, SecretsFetcher};
+use crate::{
+    ConfigSource, DynamicSchema, EnvConfigEnum, KeyGroup, KeySpec, MissingEnvVariableError,
+};
+
+// Health checks.
+// --------------------------------------------------
+
+// Summary of the config state that a service can expose from its health-check
+// endpoint (ex. /healthz):
+//
+// let health = ConfigHealth::new()
+//     .with_secrets_cache(&fetcher)
+//     .check_env::<EnvConfig>(&ProcessEnv::new());
+// let status = if health.is_healthy() { 200 } else { 503 };
+// let body = health.to_json().to_string();
+//
+// Reports when the secrets were last successfully refreshed and how stale the
+// cached copy is, along with any keys that would currently fail to load (ex.
+// since a variable was removed or changed to an invalid value).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigHealth {
+    last_secret_refresh: Option<SystemTime>,
+    secret_staleness: Option<Duration>,
+    secret_refresh_error: Option<String>,
+    failing_keys: Vec<FailingKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailingKey {
+    pub key: String,
+    pub reason: String,
+}
+
+impl ConfigHealth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(feature = "aws")]
+    pub fn with_secrets_cache<F: SecretsFetcher + 'static>(
+        mut self,
+        cache: &CachedSecretsFetcher<F>,
+    ) -> Self {
+        self.last_secret_refresh = cache.last_refresh();
+        self.secret_staleness = Some(cache.staleness());
+        self.secret_refresh_error = cache.last_refresh_error();
+        self
+    }
+
+    // Re-check each key of the config against the source, recording the keys
+    // which are missing or fail their constraints.
+    pub fn check_env<T: EnvConfigEnum>(self, source: &impl ConfigSource) -> Self {
+        let groups = T::groups();
+        let mut health = self.check_specs(
+            T::value_list().iter().map(EnvConfigEnum::spec),
+            source,
+            &groups,
+        );
+        for group in groups {
+            if let Err(e) = group.check(|key| matches!(source.get(key), Ok(Some(_)))) {
+                health.failing_keys.push(FailingKey {
+                    key: group.to_string(),
+                    reason: e.to_string(),
+                });
+            }
+        }
+        health
+    }
+
+    pub fn check_schema(self, schema: &DynamicSchema, source: &impl ConfigSource) -> Self {
+        self.check_specs(schema.specs().iter().cloned(), source, &[])
+    }
+
+    fn check_specs(
+        mut self,
+        specs: impl Iterator<Item = KeySpec>,
+        source: &impl ConfigSource,
+        groups: &[KeyGroup],
+    ) -> Self {
+        for spec in specs {
+            let grouped = groups.iter().any(|group| group.contains(spec.name()));
+            let result = spec.read(source).and_then(|value| match value {
+                Some(value) => spec.validate(&value),
+                None if grouped || spec.may_be_missing(source) => Ok(()),
+                None => Err(MissingEnvVariableError::new(spec.name(), "")),
+            });
+            if let Err(e) = result {
+                self.failing_keys.push(FailingKey {
+                    key: spec.name().to_string(),
+                    reason: e.to_string(),
+                });
+            }
+        }
+        self
+    }
+
+    pub fn last_secret_refresh(&self) -> Option<SystemTime> {
+        self.last_secret_refresh
+    }
+
+    pub fn secret_staleness(&self) -> Option<Duration> {
+        self.secret_staleness
+    }
+
+    // Set while the secrets cache is serving stale values after a failed
+    // refresh. Doesn't affect is_healthy, since values are still served (until
+    // the cache's max staleness is exceeded).
+    pub fn secret_refresh_error(&self) -> Option<&str> {
+        self.secret_refresh_error.as_deref()
+    }
+
+    pub fn failing_keys(&self) -> &[FailingKey] {
+        &self.failing_keys
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.failing_keys.is_empty()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut json = Map::new();
+        json.insert("healthy".to_string(), Value::from(self.is_healthy()));
+        json.insert(
+            "last_secret_refresh".to_string(),
+            Value::from(self.last_secret_refresh.map(|time| {
+                time.duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            })),
+        );
+        json.insert(
+            "secret_staleness_secs".to_string(),
+            Value::from(self.secret_staleness.map(|staleness| staleness.as_secs())),
+        );
+        json.insert(
+            "secret_refresh_error".to_string(),
+            Value::from(self.secret_refresh_error.clone()),
+        );
+        json.insert(
+            "failing_keys".to_string(),
+            Value::Array(
+                self.failing_keys
+                    .iter()
+                    .map(|failing| {
+                        let mut entry = Map::new();
+                        entry.insert("key".to_string(), Value::from(failing.key.as_str()));
+                        entry.insert("reason".to_string(), Value::from(failing.reason.as_str()));
+                        Value::Object(entry)
+                    })
+                    .collect(),
+            ),
+        );
+        Value::Object(json)
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::{define_env_config, define_env_variable, EnvConfigEnum, FileSource, Format};
+
+    use super::ConfigHealth;
+
+    define_env_variable!(HEALTH_REGION);
+    define_env_variable!(HEALTH_PORT);
+
+    define_env_config!(
+        HealthConfig,
+        Region => HEALTH_REGION,
+        Port => HEALTH_PORT { range = 1..=65535 },
+    );
+
+    #[test]
+    fn test_healthy_config() {
+        let source = FileSource::parse(
+            r#"{ "HEALTH_REGION": "us-west-2", "HEALTH_PORT": "8080" }"#,
+            Format::Json,
+        )
+        .unwrap();
+        let health = ConfigHealth::new().check_env::<HealthConfig>(&source);
+
+        assert!(health.is_healthy());
+        assert_eq!(health.to_json().get("healthy"), Some(&Value::Bool(true)));
+    }
+
+    #[test]
+    fn test_failing_keys() {
+        let source = FileSource::parse(r#"{ "HEALTH_PORT": "0" }"#, Format::Json).unwrap();
+        let health = ConfigHealth::new().check_env::<HealthConfig>(&source);
+
+        assert!(!health.is_healthy());
+        let keys = health
+            .failing_keys()
+            .iter()
+            .map(|failing| failing.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["HEALTH_REGION", "HEALTH_PORT"]);
+    }
+
+    #[cfg(feature = "aws")]
+    #[tokio::test]
+    async fn test_secrets_cache_health() {
+        use std::time::Duration;
+
+        use crate::{CachedSecretsFetcher, FakeSecretsBackend, SecretsFetcher};
+
+        let cache = CachedSecretsFetcher::new(
+            FakeSecretsBackend::new().with_secret("test-secret", "v1"),
+            Duration::from_secs(60),
+        );
+        assert_eq!(
+            ConfigHealth::new()
+                .with_secrets_cache(&cache)
+                .last_secret_refresh(),
+            None
+        );
+
+        cache.fetch_secret_string("test-secret").await.unwrap();
+        let health = ConfigHealth::new().with_secrets_cache(&cache);
+        assert!(health.last_secret_refresh().is_some());
+        assert_eq!(health.secret_staleness(), Some(Duration::ZERO));
+        assert_eq!(health.secret_refresh_error(), None);
+    }
+}
@@ -1,7 +1,89 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+#[doc(hidden)]
+pub extern crate alloc as __alloc;
+
+#[cfg(all(feature = "std", any(test, feature = "testing")))]
+mod chaos;
+#[cfg(feature = "std")]
+mod connection;
+#[cfg(feature = "std")]
 mod constants;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+mod error_chain;
+#[cfg(feature = "std")]
+mod error_code;
+#[cfg(feature = "figment")]
+mod figment_adapter;
+#[cfg(feature = "std")]
 mod from_env;
+#[cfg(feature = "k8s")]
+mod from_k8s;
+#[cfg(feature = "std")]
 mod from_secrets;
+#[cfg(feature = "std")]
+mod health;
+#[cfg(feature = "aws")]
+mod load;
+#[cfg(feature = "std")]
+mod localize;
+mod named;
+pub mod prelude;
+#[cfg(feature = "aws")]
+mod prewarm;
+mod provenance;
+#[cfg(feature = "rds-auth")]
+mod rds_auth;
+#[cfg(all(unix, feature = "std"))]
+mod share;
+#[cfg(feature = "sts")]
+mod sts;
+#[cfg(feature = "std")]
+mod summary;
 
+#[cfg(all(feature = "std", any(test, feature = "testing")))]
+pub use chaos::{ChaosGuard, ChaosScope};
+#[cfg(feature = "std")]
+pub use connection::{
+    DbConnection, DbConnectionKeys, DbEnvConfig, DbSecretsConfig, DefaultDb, DB_HOST, DB_NAME,
+    DB_PASSWORD, DB_PORT, DB_USER,
+};
+#[cfg(feature = "std")]
 pub use constants::*;
+#[cfg(feature = "std")]
+pub use error::ConfigError;
+#[cfg(feature = "std")]
+pub use error_code::{ErrorCode, ERROR_CODES};
+#[cfg(feature = "std")]
 pub use from_env::*;
+#[cfg(feature = "k8s")]
+pub use from_k8s::*;
+#[cfg(feature = "std")]
 pub use from_secrets::*;
+#[cfg(feature = "std")]
+pub use health::{ConfigHealth, FailingKey};
+#[cfg(feature = "aws")]
+pub use load::{load_concurrently, ConcurrentSources, LoadReport, SourceTiming};
+#[cfg(feature = "std")]
+pub use localize::{clear_message_catalog, localize, set_message_catalog, MessageCatalog};
+pub use named::{MissingNamedValue, NamedKey, NamedValues};
+#[cfg(feature = "aws")]
+pub use prewarm::{
+    get_prewarmed, on_reinit_after_fork, prewarm, prewarm_with, reinit_after_fork,
+    reinit_after_fork_with, store_prewarmed,
+};
+pub use provenance::{Plain, Secret};
+#[cfg(feature = "rds-auth")]
+pub use rds_auth::{DefaultRdsAuth, RdsAuthEnvConfig, RdsAuthKeys, RdsAuthTokens, DB_REGION};
+#[cfg(all(unix, feature = "std"))]
+pub use share::{connect_config, serve_config, serve_config_for_uid, ConfigServer};
+#[cfg(feature = "sts")]
+pub use sts::{
+    AssumeRoleEnvConfig, AssumedRole, AssumedRoleCredentials, ASSUME_ROLE_ARN,
+    ASSUME_ROLE_SESSION_NAME, AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY, AWS_SESSION_TOKEN,
+};
+#[cfg(feature = "std")]
+pub use summary::{CacheSummary, StartupSummary};
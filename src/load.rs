@@ -0,0 +1,211 @@
+use std::future::Future;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::ConfigError;
+use crate::StartupBudgetExceeded;
+
+// Concurrent loading.
+// --------------------------------------------------
+
+// When a service loads from several sources, they can be loaded concurrently
+// within a total time budget, instead of one after the other:
+//
+// let ((env, secrets), report) = load_concurrently(
+//     Duration::from_secs(5),
+//     (
+//         ("env", async { load_env::<EnvConfig>() }),
+//         ("secrets", load_secrets::<SecretsConfig>(secrets_env)),
+//     ),
+// )
+// .await?;
+//
+// Sources are given as (name, future) pairs, up to 4 at once. The first error
+// fails the whole load, and exceeding the budget fails with the names of the
+// sources still pending. On success, the LoadReport has the time taken by each
+// source. Note that synchronous sources (ex. load_env) don't yield, so only
+// asynchronous sources (ex. Secrets Manager) actually overlap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadReport {
+    total: Duration,
+    sources: Vec<SourceTiming>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceTiming {
+    pub name: &'static str,
+    pub elapsed: Duration,
+}
+
+impl LoadReport {
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    // In order of completion.
+    pub fn sources(&self) -> &[SourceTiming] {
+        &self.sources
+    }
+
+    pub fn elapsed(&self, name: &str) -> Option<Duration> {
+        self.sources
+            .iter()
+            .find(|timing| timing.name == name)
+            .map(|timing| timing.elapsed)
+    }
+}
+
+// Implemented for tuples of up to 4 (name, future) pairs.
+pub trait ConcurrentSources {
+    type Output;
+    fn names(&self) -> Vec<&'static str>;
+    fn load_all(
+        self,
+        timings: &Mutex<Vec<SourceTiming>>,
+    ) -> impl Future<Output = Result<Self::Output, ConfigError>>;
+}
+
+async fn timed<T>(
+    name: &'static str,
+    source: impl Future<Output = Result<T, ConfigError>>,
+    timings: &Mutex<Vec<SourceTiming>>,
+) -> Result<T, ConfigError> {
+    let start = Instant::now();
+    let result = source.await;
+    timings
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(SourceTiming {
+            name,
+            elapsed: start.elapsed(),
+        });
+    result
+}
+
+macro_rules! impl_concurrent_sources {
+    ($(($T:ident, $F:ident, $name:ident, $source:ident)),+) => {
+        impl<$($T, $F: Future<Output = Result<$T, ConfigError>>),+> ConcurrentSources
+            for ($((&'static str, $F),)+)
+        {
+            type Output = ($($T,)+);
+
+            fn names(&self) -> Vec<&'static str> {
+                let ($(($name, _),)+) = self;
+                vec![$(*$name),+]
+            }
+
+            async fn load_all(
+                self,
+                timings: &Mutex<Vec<SourceTiming>>,
+            ) -> Result<Self::Output, ConfigError> {
+                let ($(($name, $source),)+) = self;
+                tokio::try_join!($(timed($name, $source, timings)),+)
+            }
+        }
+    };
+}
+
+impl_concurrent_sources!((T1, F1, n1, s1));
+impl_concurrent_sources!((T1, F1, n1, s1), (T2, F2, n2, s2));
+impl_concurrent_sources!((T1, F1, n1, s1), (T2, F2, n2, s2), (T3, F3, n3, s3));
+impl_concurrent_sources!(
+    (T1, F1, n1, s1),
+    (T2, F2, n2, s2),
+    (T3, F3, n3, s3),
+    (T4, F4, n4, s4)
+);
+
+pub async fn load_concurrently<S: ConcurrentSources>(
+    budget: Duration,
+    sources: S,
+) -> Result<(S::Output, LoadReport), ConfigError> {
+    let names = sources.names();
+    let timings = Mutex::new(Vec::new());
+    let start = Instant::now();
+    let result = tokio::time::timeout(budget, sources.load_all(&timings)).await;
+    let timings = timings.into_inner().unwrap_or_else(PoisonError::into_inner);
+    match result {
+        Ok(output) => Ok((
+            output?,
+            LoadReport {
+                total: start.elapsed(),
+                sources: timings,
+            },
+        )),
+        Err(_) => {
+            let pending = names
+                .into_iter()
+                .filter(|name| !timings.iter().any(|timing| timing.name == *name))
+                .collect::<Vec<_>>();
+            Err(StartupBudgetExceeded::new(
+                &format!("{budget:?}"),
+                &pending.join(", "),
+            ))
+        }
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::ConfigError;
+
+    use crate::MissingEnvVariableError;
+
+    use super::load_concurrently;
+
+    async fn slow<T>(value: T, delay: Duration) -> Result<T, ConfigError> {
+        tokio::time::sleep(delay).await;
+        Ok(value)
+    }
+
+    #[tokio::test]
+    async fn test_load_concurrently() {
+        let ((a, b), report) = load_concurrently(
+            Duration::from_secs(5),
+            (
+                ("a", slow("a", Duration::from_millis(100))),
+                ("b", slow(2, Duration::from_millis(100))),
+            ),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!((a, b), ("a", 2));
+        assert_eq!(report.sources().len(), 2);
+        assert!(report.elapsed("a").unwrap() >= Duration::from_millis(100));
+        assert!(report.total() < Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_load_concurrently_error() {
+        let result = load_concurrently(
+            Duration::from_secs(5),
+            (
+                ("a", slow("a", Duration::ZERO)),
+                ("b", async {
+                    Err::<(), _>(MissingEnvVariableError::new("B", ""))
+                }),
+            ),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_concurrently_budget_exceeded() {
+        let result = load_concurrently(
+            Duration::from_millis(50),
+            (
+                ("fast", slow((), Duration::ZERO)),
+                ("slow", slow((), Duration::from_secs(5))),
+            ),
+        )
+        .await;
+        assert!(result.unwrap_err().to_string().contains("slow"));
+    }
+}
@@ -0,0 +1,95 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::ConfigError;
+use crate::ErrorCode;
+
+// Localized messages.
+// --------------------------------------------------
+
+// Error messages are in English. Operator-facing tools built on this crate can
+// render them in another language by installing a message catalog, which
+// translates message templates by error code (see error_code.rs):
+//
+// set_message_catalog(HashMap::from([
+//     ("ENV002", "Variable d'environnement manquante : '{missing_var}'."),
+//     ("ENV019", "'{var}' ne respecte pas la contrainte : {constraint}."),
+// ]));
+//
+// eprintln!("{}", localize(&error));
+//
+// Translated templates use the same {placeholders} as the English ones (listed
+// in ERROR_CODES), which are filled in with the fields of the original error.
+// Errors the catalog doesn't translate, or without a code, are rendered as-is
+// in English, which also remains the default when no catalog is installed.
+pub trait MessageCatalog: Send + Sync {
+    fn template(&self, code: &str) -> Option<Cow<'static, str>>;
+}
+
+impl MessageCatalog for HashMap<&'static str, &'static str> {
+    fn template(&self, code: &str) -> Option<Cow<'static, str>> {
+        self.get(code).map(|template| Cow::Borrowed(*template))
+    }
+}
+
+static CATALOG: RwLock<Option<Arc<dyn MessageCatalog>>> = RwLock::new(None);
+
+pub fn set_message_catalog(catalog: impl MessageCatalog + 'static) {
+    *CATALOG.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(catalog));
+}
+
+pub fn clear_message_catalog() {
+    *CATALOG.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+pub fn localize(error: &ConfigError) -> String {
+    let catalog = CATALOG
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    catalog
+        .and_then(|catalog| translate(error, &*catalog))
+        .unwrap_or_else(|| error.to_string())
+}
+
+fn translate(error: &ConfigError, catalog: &dyn MessageCatalog) -> Option<String> {
+    let code = error.error_code()?;
+    let translated = catalog.template(code)?;
+    Some(
+        error
+            .fields()
+            .iter()
+            .fold(translated.into_owned(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), value)
+            }),
+    )
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{EnvVariableFailedConstraint, MissingEnvVariableError};
+
+    use super::translate;
+
+    #[test]
+    fn test_translate() {
+        let catalog = HashMap::from([(
+            "ENV019",
+            "'{var}' ne respecte pas la contrainte : {constraint}.",
+        )]);
+        let error = EnvVariableFailedConstraint::new("PORT", "range 1..=65535");
+        assert_eq!(
+            translate(&error, &catalog).unwrap(),
+            "'PORT' ne respecte pas la contrainte : range 1..=65535."
+        );
+
+        let error = MissingEnvVariableError::new("REGION", "");
+        assert!(translate(&error, &catalog).is_none());
+    }
+}
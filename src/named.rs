@@ -0,0 +1,132 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+// Named values.
+// --------------------------------------------------
+
+// The enum-keyed container at the core of EnvVariables and SecretValues,
+// without any of the loading machinery. Only depends on core and alloc, so it
+// is available without the "std" feature (ex. in firmware or embedded tools,
+// with default-features = false):
+//
+// define_named_config!(
+//     BoardConfig,
+//     BaudRate => "BAUD_RATE",
+//     DeviceName => "DEVICE_NAME",
+// );
+//
+// let config = NamedValues::<BoardConfig>::from_pairs(read_flash_pairs())?;
+// let baud = config.get(&BoardConfig::BaudRate);
+//
+// As with EnvVariables, constructing the container checks all keys are
+// present, so get can't fail. Unknown names are ignored.
+pub trait NamedKey: fmt::Debug + PartialEq + Eq + Clone {
+    fn as_str(&self) -> &'static str;
+    fn value_list() -> Vec<Self>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedValues<K: NamedKey>(BTreeMap<&'static str, String>, PhantomData<K>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingNamedValue {
+    pub key: &'static str,
+}
+
+impl fmt::Display for MissingNamedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Missing value for '{}'.", self.key)
+    }
+}
+
+impl<K: NamedKey> NamedValues<K> {
+    // If a name appears more than once, the last value wins.
+    pub fn from_pairs(
+        pairs: impl IntoIterator<Item = (impl AsRef<str>, impl Into<String>)>,
+    ) -> Result<Self, MissingNamedValue> {
+        let keys = K::value_list();
+        let mut map = BTreeMap::new();
+        for (name, value) in pairs {
+            if let Some(key) = keys.iter().find(|key| key.as_str() == name.as_ref()) {
+                map.insert(key.as_str(), value.into());
+            }
+        }
+        match keys.iter().find(|key| !map.contains_key(key.as_str())) {
+            Some(key) => Err(MissingNamedValue { key: key.as_str() }),
+            None => Ok(Self(map, PhantomData)),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> &str {
+        // Guaranteed by from_pairs.
+        &self.0[key.as_str()]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &str)> + '_ {
+        self.0.iter().map(|(name, value)| (*name, value.as_str()))
+    }
+}
+
+#[macro_export]
+macro_rules! define_named_config {
+    ($T:ident, $($k:ident => $v:expr),* $(,)?) => {
+        #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+        pub enum $T {
+            $($k),*
+        }
+
+        impl $crate::NamedKey for $T {
+            fn as_str(&self) -> &'static str {
+                match *self {
+                    $($T::$k => $v),*
+                }
+            }
+
+            fn value_list() -> $crate::__alloc::vec::Vec<Self> {
+                $crate::__alloc::vec![$($T::$k),*]
+            }
+        }
+    };
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::{MissingNamedValue, NamedValues};
+
+    define_named_config!(
+        BoardConfig,
+        BaudRate => "BAUD_RATE",
+        DeviceName => "DEVICE_NAME",
+    );
+
+    #[test]
+    fn test_named_values() {
+        let config = NamedValues::<BoardConfig>::from_pairs([
+            ("BAUD_RATE", "9600"),
+            ("DEVICE_NAME", "sensor-1"),
+            ("UNRELATED", "ignored"),
+            ("BAUD_RATE", "115200"),
+        ])
+        .unwrap();
+        assert_eq!(config.get(&BoardConfig::BaudRate), "115200");
+        assert_eq!(
+            config.iter().collect::<Vec<_>>(),
+            vec![("BAUD_RATE", "115200"), ("DEVICE_NAME", "sensor-1")]
+        );
+    }
+
+    #[test]
+    fn test_named_values_missing() {
+        let result = NamedValues::<BoardConfig>::from_pairs([("BAUD_RATE", "9600")]);
+        assert_eq!(result, Err(MissingNamedValue { key: "DEVICE_NAME" }));
+    }
+}
@@ -0,0 +1,69 @@
+// Prelude.
+// --------------------------------------------------
+
+// Everything most services need to declare and load their configs, with paths
+// that stay stable as the crate's modules are reorganized:
+//
+// use fractic_env_config::prelude::*;
+//
+// define_env_variable!(REGION);
+// define_env_config!(EnvConfig, Region => REGION);
+//
+// fn main() -> Result<(), ConfigError> {
+//     let env = load_env::<EnvConfig>()?;
+//     ...
+// }
+//
+// Includes the config traits (which the define_* macros expect in scope), the
+// macros, the value containers, the load functions, and the errors commonly
+// matched on. Everything else stays available from the crate root.
+
+pub use crate::{define_named_config, NamedKey, NamedValues, Plain, Secret};
+
+#[cfg(feature = "std")]
+pub use crate::{
+    define_env_config, define_env_values, define_env_variable, define_secret_key,
+    define_secrets_config, load_env, load_env_with, load_from_source, load_secrets_from_source,
+    load_secrets_with, requires_env, window, ConfigError, ConfigSource, ContainsConfig,
+    EnvConfigEnum, EnvVariables, ErrorCode, FromEnvValue, LoadOptions, RequiresEnv, SecretValues,
+    SecretsConfigEnum, SecretsEnvKeys, SecretsFetcher,
+};
+
+// Errors.
+#[cfg(feature = "std")]
+pub use crate::{
+    EnvConfigValidationFailed, EnvVariableFailedConstraint, InvalidConfigWindow,
+    InvalidEnvCloneInto, InvalidEnvVariableType, InvalidSecretsCloneInto, MissingEnvVariableError,
+    MissingEnvVariables, MissingSecretKey, MissingSecretValue, SecretNotFound, SecretsAccessDenied,
+};
+
+#[cfg(feature = "aws")]
+pub use crate::{load_secrets, AwsSecretsFetcher, CachedSecretsFetcher};
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    define_env_variable!(PRELUDE_REGION);
+    define_secret_key!(PRELUDE_API_KEY);
+
+    define_env_config!(PreludeEnvConfig, Region => PRELUDE_REGION);
+    define_secrets_config!(PreludeSecretsConfig, ApiKey => PRELUDE_API_KEY);
+
+    #[test]
+    fn test_prelude() -> Result<(), ConfigError> {
+        let source = HashMap::from([(String::from("PRELUDE_REGION"), String::from("us-west-2"))]);
+        let env = load_from_source::<PreludeEnvConfig>(&source)?;
+        assert_eq!(env.get(&PreludeEnvConfig::Region)?, "us-west-2");
+
+        let source = HashMap::from([(String::from("PRELUDE_API_KEY"), String::from("abc123"))]);
+        let secrets = load_secrets_from_source::<PreludeSecretsConfig>(&source)?;
+        assert_eq!(secrets.get(&PreludeSecretsConfig::ApiKey)?, "abc123");
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+use std::time::Duration;
+
+use crate::ConfigError;
+use crate::{
+    load_concurrently, load_env, load_secrets_with, AwsSecretsFetcher, EnvConfigEnum, EnvVariables,
+    LoadReport, NotPrewarmed, SecretValues, SecretsConfigEnum, SecretsEnvKeys, SecretsFetcher,
+};
+
+// Pre-warming.
+// --------------------------------------------------
+
+// For Lambda (especially with provisioned concurrency), the config can be
+// loaded during the init phase (ex. from an extension, or before starting the
+// runtime loop), so that the handler itself doesn't pay for it:
+//
+// prewarm::<EnvConfig, SecretsConfig, SecretsEnvConfig>(Duration::from_secs(5)).await?;
+//
+// // In the handler:
+// let env = get_prewarmed::<EnvVariables<EnvConfig>>()?;
+// let secrets = get_prewarmed::<SecretValues<SecretsConfig>>()?;
+//
+// Env variables and secrets are loaded concurrently, within the given budget.
+// The secrets are located by the variables of the given SecretsEnvKeys
+// (SecretsEnvConfig for SECRETS_REGION and SECRETS_ID, see config.rs).
+// The prewarmed values are kept for the lifetime of the process, and accessing
+// them is only a map lookup and an Arc clone. Other values can be stored with
+// store_prewarmed.
+fn prewarmed() -> &'static RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>> {
+    static PREWARMED: OnceLock<RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>> =
+        OnceLock::new();
+    PREWARMED.get_or_init(Default::default)
+}
+
+pub async fn prewarm<E, S, K>(budget: Duration) -> Result<LoadReport, ConfigError>
+where
+    E: EnvConfigEnum + 'static,
+    S: SecretsConfigEnum + 'static,
+    K: SecretsEnvKeys,
+{
+    let secrets_env = load_env::<K>()?;
+    let fetcher = AwsSecretsFetcher::new(secrets_env.get(&K::region())?).await;
+    prewarm_from::<E, S, K>(budget, secrets_env, &fetcher).await
+}
+
+pub async fn prewarm_with<E, S, K>(
+    budget: Duration,
+    fetcher: &impl SecretsFetcher,
+) -> Result<LoadReport, ConfigError>
+where
+    E: EnvConfigEnum + 'static,
+    S: SecretsConfigEnum + 'static,
+    K: SecretsEnvKeys,
+{
+    prewarm_from::<E, S, K>(budget, load_env::<K>()?, fetcher).await
+}
+
+async fn prewarm_from<E, S, K>(
+    budget: Duration,
+    secrets_env: EnvVariables<K>,
+    fetcher: &impl SecretsFetcher,
+) -> Result<LoadReport, ConfigError>
+where
+    E: EnvConfigEnum + 'static,
+    S: SecretsConfigEnum + 'static,
+    K: SecretsEnvKeys,
+{
+    let ((env, secrets), report) = load_concurrently(
+        budget,
+        (
+            ("env", async { load_env::<E>() }),
+            ("secrets", load_secrets_with::<S>(secrets_env, fetcher)),
+        ),
+    )
+    .await?;
+    store_prewarmed::<EnvVariables<E>>(env);
+    store_prewarmed::<SecretValues<S>>(secrets);
+    Ok(report)
+}
+
+// Forking.
+// --------------------------------------------------
+
+// SDK clients (and their connection pools, credential caches and background
+// tasks) don't survive a fork, and neither does the tokio runtime. A process
+// that forks or daemonizes after loading its config should re-resolve it in
+// the child, from a new runtime:
+//
+// on_reinit_after_fork(|| DB_POOL.reset());
+//
+// // In the child, after fork():
+// let rt = tokio::runtime::Runtime::new()?;
+// rt.block_on(reinit_after_fork::<EnvConfig, SecretsConfig, SecretsEnvConfig>(
+//     Duration::from_secs(5),
+// ))?;
+//
+// Registered hooks are run first, so that handles held elsewhere (ex. clients
+// built from the config) can be dropped. The environment is then loaded and
+// validated again, and the secrets fetched with a newly created client, and
+// both replace the prewarmed values. Other values stored with store_prewarmed
+// are left as-is, and should be replaced by a hook if they hold such handles.
+static FORK_HOOKS: RwLock<Vec<fn()>> = RwLock::new(Vec::new());
+
+pub fn on_reinit_after_fork(hook: fn()) {
+    FORK_HOOKS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(hook);
+}
+
+pub async fn reinit_after_fork<E, S, K>(budget: Duration) -> Result<LoadReport, ConfigError>
+where
+    E: EnvConfigEnum + 'static,
+    S: SecretsConfigEnum + 'static,
+    K: SecretsEnvKeys,
+{
+    run_fork_hooks();
+    prewarm::<E, S, K>(budget).await
+}
+
+pub async fn reinit_after_fork_with<E, S, K>(
+    budget: Duration,
+    fetcher: &impl SecretsFetcher,
+) -> Result<LoadReport, ConfigError>
+where
+    E: EnvConfigEnum + 'static,
+    S: SecretsConfigEnum + 'static,
+    K: SecretsEnvKeys,
+{
+    run_fork_hooks();
+    prewarm_with::<E, S, K>(budget, fetcher).await
+}
+
+fn run_fork_hooks() {
+    let hooks = FORK_HOOKS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    for hook in hooks {
+        hook();
+    }
+}
+
+pub fn store_prewarmed<T: Any + Send + Sync>(value: T) {
+    prewarmed()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(TypeId::of::<T>(), Arc::new(value));
+}
+
+pub fn get_prewarmed<T: Any + Send + Sync>() -> Result<Arc<T>, ConfigError> {
+    prewarmed()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&TypeId::of::<T>())
+        .cloned()
+        .and_then(|value| value.downcast::<T>().ok())
+        .ok_or_else(|| NotPrewarmed::new(std::any::type_name::<T>()))
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use crate::{
+        define_env_config, define_env_variable, define_secret_key, define_secrets_config,
+        EnvConfigEnum, EnvOverrideScope, EnvVariables, FakeSecretsBackend, SecretValues,
+        SecretsConfigEnum, SecretsEnvKeys,
+    };
+
+    use super::{
+        get_prewarmed, on_reinit_after_fork, prewarm_with, reinit_after_fork_with, store_prewarmed,
+    };
+
+    define_env_variable!(PREWARM_REGION);
+    define_env_variable!(PREWARM_SECRETS_REGION);
+    define_env_variable!(PREWARM_SECRETS_ID);
+    define_secret_key!(PREWARM_KEY);
+
+    define_env_variable!(FORK_REGION);
+    define_env_variable!(FORK_SECRETS_REGION);
+    define_env_variable!(FORK_SECRETS_ID);
+
+    define_env_config!(
+        PrewarmEnvConfig,
+        Region => PREWARM_REGION,
+    );
+    define_env_config!(
+        ForkEnvConfig,
+        Region => FORK_REGION,
+    );
+
+    // Each test locates its secrets by variables of its own, so that they
+    // don't override each other's SECRETS_REGION and SECRETS_ID.
+    define_env_config!(
+        PrewarmSecretsEnv,
+        Region => PREWARM_SECRETS_REGION,
+        Id => PREWARM_SECRETS_ID,
+    );
+    define_env_config!(
+        ForkSecretsEnv,
+        Region => FORK_SECRETS_REGION,
+        Id => FORK_SECRETS_ID,
+    );
+
+    impl SecretsEnvKeys for PrewarmSecretsEnv {
+        fn region() -> Self {
+            Self::Region
+        }
+        fn id() -> Self {
+            Self::Id
+        }
+    }
+
+    impl SecretsEnvKeys for ForkSecretsEnv {
+        fn region() -> Self {
+            Self::Region
+        }
+        fn id() -> Self {
+            Self::Id
+        }
+    }
+    define_secrets_config!(
+        PrewarmSecretsConfig,
+        Key => PREWARM_KEY,
+    );
+
+    #[tokio::test]
+    async fn test_prewarm() {
+        let _guard = EnvOverrideScope::new()
+            .set(PREWARM_REGION, "us-west-2")
+            .set(PREWARM_SECRETS_REGION, "us-west-2")
+            .set(PREWARM_SECRETS_ID, "prewarm-secret")
+            .apply();
+        let backend =
+            FakeSecretsBackend::new().with_secret("prewarm-secret", r#"{"PREWARM_KEY": "abc123"}"#);
+
+        assert!(get_prewarmed::<EnvVariables<PrewarmEnvConfig>>().is_err());
+        let report = prewarm_with::<PrewarmEnvConfig, PrewarmSecretsConfig, PrewarmSecretsEnv>(
+            Duration::from_secs(5),
+            &backend,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.sources().len(), 2);
+
+        let env = get_prewarmed::<EnvVariables<PrewarmEnvConfig>>().unwrap();
+        assert_eq!(env.get(&PrewarmEnvConfig::Region).unwrap(), "us-west-2");
+        let secrets = get_prewarmed::<SecretValues<PrewarmSecretsConfig>>().unwrap();
+        assert_eq!(secrets.get(&PrewarmSecretsConfig::Key).unwrap(), "abc123");
+    }
+
+    #[test]
+    fn test_store_prewarmed() {
+        struct Custom(u32);
+        store_prewarmed(Custom(7));
+        assert_eq!(get_prewarmed::<Custom>().unwrap().0, 7);
+    }
+
+    #[tokio::test]
+    async fn test_reinit_after_fork() {
+        static HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+        on_reinit_after_fork(|| {
+            HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+        });
+        let _guard = EnvOverrideScope::new()
+            .set(FORK_REGION, "eu-west-1")
+            .set(FORK_SECRETS_REGION, "us-west-2")
+            .set(FORK_SECRETS_ID, "fork-secret")
+            .apply();
+        let backend =
+            FakeSecretsBackend::new().with_secret("fork-secret", r#"{"PREWARM_KEY": "abc123"}"#);
+
+        reinit_after_fork_with::<ForkEnvConfig, PrewarmSecretsConfig, ForkSecretsEnv>(
+            Duration::from_secs(5),
+            &backend,
+        )
+        .await
+        .unwrap();
+        assert!(HOOK_CALLS.load(Ordering::SeqCst) >= 1);
+        let env = get_prewarmed::<EnvVariables<ForkEnvConfig>>().unwrap();
+        assert_eq!(env.get(&ForkEnvConfig::Region).unwrap(), "eu-west-1");
+    }
+}
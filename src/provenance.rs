@@ -0,0 +1,101 @@
+use core::fmt;
+use core::ops::Deref;
+
+// Value provenance.
+// --------------------------------------------------
+
+// Typed wrappers distinguishing secret values from plain config values, so
+// downstream signatures can require a secret-typed value:
+//
+// fn connect(password: Secret<String>) { ... }
+//
+// connect(secrets.get_secret(&SecretsConfig::DbPassword)?);
+//
+// Secret intentionally implements neither Display nor Debug, so it can't be
+// accidentally logged or formatted. The value has to be explicitly exposed.
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_exposed(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+// Plain (non-sensitive) values can be used and formatted freely.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Plain<T>(T);
+
+impl<T> Plain<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Plain<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Plain<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> From<T> for Plain<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+
+    use super::{Plain, Secret};
+
+    #[test]
+    fn test_secret_expose() {
+        let secret = Secret::new(String::from("abc123"));
+        assert_eq!(secret.expose(), "abc123");
+        assert_eq!(secret.clone().into_exposed(), "abc123");
+    }
+
+    #[test]
+    fn test_plain_display_and_deref() {
+        let plain = Plain::new(String::from("us-west-2"));
+        assert_eq!(plain.to_string(), "us-west-2");
+        assert_eq!(plain.len(), 9);
+        assert_eq!(plain.into_inner(), "us-west-2");
+    }
+}
@@ -0,0 +1,298 @@
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use aws_config::{BehaviorVersion, Region, SdkConfig};
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_credential_types::Credentials;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::connection::percent_encode;
+use crate::ConfigError;
+use crate::{
+    define_env_config, define_env_variable, EnvConfigEnum, EnvVariables,
+    FailedToGenerateRdsAuthToken, Secret, DB_HOST, DB_PORT, DB_USER,
+};
+
+define_env_variable!(DB_REGION);
+
+define_env_config!(
+    RdsAuthEnvConfig,
+    DbRegion => DB_REGION,
+    DbHost => DB_HOST,
+    DbPort => DB_PORT { range = 1..=65535 },
+    DbUser => DB_USER,
+);
+
+// RDS IAM authentication.
+// --------------------------------------------------
+
+// With IAM database authentication enabled, RDS accepts short-lived auth
+// tokens (signed with the service's own AWS credentials) in place of a
+// password, so no database password has to be stored in Secrets Manager at
+// all. With the conventional keys (DB_REGION, DB_HOST, DB_PORT and DB_USER):
+//
+// let tokens = RdsAuthTokens::load::<DefaultRdsAuth>(&window!(env => RdsAuthEnvConfig)).await?;
+//
+// // For each new connection:
+// let options = PgConnectOptions::new()
+//     .host(tokens.host())
+//     .port(tokens.port())
+//     .username(tokens.user())
+//     .password(tokens.token().await?.expose());
+//
+// Tokens are valid for 15 minutes, and only needed to open a connection (not to
+// keep it open). They are generated on demand, and reused for 10 minutes. Other
+// key sets are declared by implementing RdsAuthKeys for a marker type. Available
+// with the "rds-auth" feature.
+pub trait RdsAuthKeys {
+    type Env: EnvConfigEnum;
+
+    fn region() -> Self::Env;
+    fn host() -> Self::Env;
+    fn port() -> Self::Env;
+    fn user() -> Self::Env;
+}
+
+pub struct DefaultRdsAuth;
+
+impl RdsAuthKeys for DefaultRdsAuth {
+    type Env = RdsAuthEnvConfig;
+
+    fn region() -> RdsAuthEnvConfig {
+        RdsAuthEnvConfig::DbRegion
+    }
+    fn host() -> RdsAuthEnvConfig {
+        RdsAuthEnvConfig::DbHost
+    }
+    fn port() -> RdsAuthEnvConfig {
+        RdsAuthEnvConfig::DbPort
+    }
+    fn user() -> RdsAuthEnvConfig {
+        RdsAuthEnvConfig::DbUser
+    }
+}
+
+const TOKEN_LIFETIME: Duration = Duration::from_secs(15 * 60);
+const TOKEN_REUSE: Duration = Duration::from_secs(10 * 60);
+
+pub struct RdsAuthTokens {
+    region: String,
+    host: String,
+    port: u16,
+    user: String,
+    credentials: Option<SharedCredentialsProvider>,
+    cached: Mutex<Option<(Secret<String>, Instant)>>,
+}
+
+impl RdsAuthTokens {
+    pub async fn load<K: RdsAuthKeys>(env: &EnvVariables<K::Env>) -> Result<Self, ConfigError> {
+        let sdk_config = aws_config::defaults(BehaviorVersion::v2024_03_28())
+            .region(Region::new(env.get(&K::region())?.to_string()))
+            .load()
+            .await;
+        Self::from_sdk_config::<K>(env, &sdk_config)
+    }
+
+    // Signs with the credentials of the SdkConfig. The region is still read
+    // from the config, since it's the region of the database.
+    pub fn from_sdk_config<K: RdsAuthKeys>(
+        env: &EnvVariables<K::Env>,
+        sdk_config: &SdkConfig,
+    ) -> Result<Self, ConfigError> {
+        Ok(Self {
+            region: env.get(&K::region())?.to_string(),
+            host: env.get(&K::host())?.to_string(),
+            port: env.get_parsed(&K::port())?,
+            user: env.get(&K::user())?.to_string(),
+            credentials: sdk_config.credentials_provider(),
+            cached: Mutex::new(None),
+        })
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    pub async fn token(&self) -> Result<Secret<String>, ConfigError> {
+        if let Some((token, generated_at)) = &*self.lock_cached() {
+            if generated_at.elapsed() < TOKEN_REUSE {
+                return Ok(token.clone());
+            }
+        }
+        let token_error = |reason: &str| FailedToGenerateRdsAuthToken::new(&self.host, reason);
+        let credentials = self
+            .credentials
+            .as_ref()
+            .ok_or_else(|| token_error("no AWS credentials provider is configured"))?
+            .provide_credentials()
+            .await
+            .map_err(|e| {
+                FailedToGenerateRdsAuthToken::with_source(
+                    &self.host,
+                    "failed to resolve AWS credentials",
+                    e,
+                )
+            })?;
+        let token = Secret::new(presign(
+            &self.host,
+            self.port,
+            &self.user,
+            &self.region,
+            &credentials,
+            SystemTime::now(),
+        ));
+        *self.lock_cached() = Some((token.clone(), Instant::now()));
+        Ok(token)
+    }
+
+    fn lock_cached(&self) -> std::sync::MutexGuard<'_, Option<(Secret<String>, Instant)>> {
+        self.cached.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for RdsAuthTokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RdsAuthTokens")
+            .field("region", &self.region)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .finish_non_exhaustive()
+    }
+}
+
+// SigV4 query-string presigning of the rds-db:connect action, as done by the
+// AWS CLI's `rds generate-db-auth-token`.
+fn presign(
+    host: &str,
+    port: u16,
+    user: &str,
+    region: &str,
+    credentials: &Credentials,
+    now: SystemTime,
+) -> String {
+    let (date, timestamp) = amz_date(now);
+    let scope = format!("{date}/{region}/rds-db/aws4_request");
+    let mut params = vec![
+        ("Action", String::from("connect")),
+        ("DBUser", user.to_string()),
+        ("X-Amz-Algorithm", String::from("AWS4-HMAC-SHA256")),
+        (
+            "X-Amz-Credential",
+            format!("{}/{scope}", credentials.access_key_id()),
+        ),
+        ("X-Amz-Date", timestamp.clone()),
+        ("X-Amz-Expires", TOKEN_LIFETIME.as_secs().to_string()),
+        ("X-Amz-SignedHeaders", String::from("host")),
+    ];
+    if let Some(session_token) = credentials.session_token() {
+        params.push(("X-Amz-Security-Token", session_token.to_string()));
+    }
+    params.sort();
+    let query = params
+        .iter()
+        .map(|(name, value)| format!("{name}={}", percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let canonical_request = format!(
+        "GET\n/\n{query}\nhost:{host}:{port}\n\nhost\n{:x}",
+        Sha256::digest(b"")
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{timestamp}\n{scope}\n{:x}",
+        Sha256::digest(canonical_request.as_bytes())
+    );
+    let signing_key = [date.as_str(), region, "rds-db", "aws4_request"]
+        .iter()
+        .fold(
+            format!("AWS4{}", credentials.secret_access_key()).into_bytes(),
+            |key, part| hmac_sha256(&key, part),
+        );
+    let signature = hmac_sha256(&signing_key, &string_to_sign)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    format!("{host}:{port}/?{query}&X-Amz-Signature={signature}")
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+// Returns the (YYYYMMDD, YYYYMMDDTHHMMSSZ) forms of the time, in UTC.
+fn amz_date(time: SystemTime) -> (String, String) {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since the epoch (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let (era, doe) = (z / 146_097, z % 146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    let date = format!("{year:04}{month:02}{day:02}");
+    let timestamp = format!(
+        "{date}T{:02}{:02}{:02}Z",
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60
+    );
+    (date, timestamp)
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use aws_credential_types::Credentials;
+
+    use super::{amz_date, presign};
+
+    #[test]
+    fn test_presign() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_709_294_405);
+        assert_eq!(
+            amz_date(now),
+            (String::from("20240301"), String::from("20240301T120005Z"))
+        );
+
+        let credentials =
+            Credentials::new("AKIDEXAMPLE", "secret", Some("a/b+c".into()), None, "test");
+        let token = presign(
+            "db.internal",
+            5432,
+            "app user",
+            "us-west-2",
+            &credentials,
+            now,
+        );
+        assert!(token.starts_with(
+            "db.internal:5432/?Action=connect&DBUser=app%20user&X-Amz-Algorithm=AWS4-HMAC-SHA256\
+             &X-Amz-Credential=AKIDEXAMPLE%2F20240301%2Fus-west-2%2Frds-db%2Faws4_request\
+             &X-Amz-Date=20240301T120005Z&X-Amz-Expires=900&X-Amz-Security-Token=a%2Fb%2Bc\
+             &X-Amz-SignedHeaders=host&X-Amz-Signature="
+        ));
+        assert!(!token.contains("secret"));
+    }
+}
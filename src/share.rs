@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::fs::DirBuilder;
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use serde_json::{Map, Value};
+
+use crate::from_env::config::may_be_absent;
+use crate::from_env::metadata::LoadMetadata;
+use crate::ConfigError;
+use crate::{
+    load_secrets_from_source, EnvConfigEnum, EnvVariables, FailedToReceiveSharedConfig,
+    FailedToServeConfig, InvalidSharedConfig, MissingEnvVariableError, SecretValues,
+    SecretsConfigEnum, SocketPathInUse,
+};
+
+// Config sharing.
+// --------------------------------------------------
+
+// A supervisor forking N workers can load the config (and fetch the secrets)
+// once, and hand it to each worker over a Unix domain socket, instead of every
+// worker fetching the same secrets:
+//
+// // Supervisor:
+// let env = load_env::<EnvConfig>()?;
+// let secrets = load_secrets::<SecretsConfig>(load_env::<SecretsEnvConfig>()?).await?;
+// let server = serve_config("/run/my-service/config.sock", &env, &secrets)?;
+// spawn_workers();
+//
+// // Worker:
+// let (env, secrets) =
+//     connect_config::<EnvConfig, SecretsConfig>("/run/my-service/config.sock")?;
+//
+// Each connection is sent the whole config as a single JSON document, and
+// workers may use any subset of the supervisor's configs. The socket file is
+// created with owner-only permissions (and only moved into place once it has
+// them), and removed when the ConfigServer is dropped. On top of that, the
+// server checks the credentials of each connecting process, and only answers
+// processes running as the same user (or as the user passed to
+// serve_config_for_uid, for workers that drop privileges):
+//
+// let server = serve_config_for_uid("/run/my-service/config.sock", &env, &secrets, worker_uid)?;
+//
+// The server keeps its own copy of the values for as long as it runs (zeroed
+// when it stops), so it should be dropped once all workers have started.
+#[derive(Debug)]
+pub struct ConfigServer {
+    path: PathBuf,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+pub fn serve_config<E: EnvConfigEnum, S: SecretsConfigEnum>(
+    path: impl AsRef<Path>,
+    env: &EnvVariables<E>,
+    secrets: &SecretValues<S>,
+) -> Result<ConfigServer, ConfigError> {
+    // SAFETY: geteuid has no preconditions, and can't fail.
+    serve_config_for_uid(path, env, secrets, unsafe { libc::geteuid() })
+}
+
+pub fn serve_config_for_uid<E: EnvConfigEnum, S: SecretsConfigEnum>(
+    path: impl AsRef<Path>,
+    env: &EnvVariables<E>,
+    secrets: &SecretValues<S>,
+    allowed_uid: u32,
+) -> Result<ConfigServer, ConfigError> {
+    let path = path.as_ref().to_path_buf();
+    let path_str = path.display().to_string();
+
+    let section = |values: Vec<(&str, &str)>| {
+        let values = values
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), Value::from(value)))
+            .collect::<Map<_, _>>();
+        Value::Object(values)
+    };
+    let mut payload = Map::new();
+    payload.insert(
+        "env".to_string(),
+        section(env.0.iter().map(|(k, v)| (*k, &**v)).collect()),
+    );
+    payload.insert(
+        "secrets".to_string(),
+        section(secrets.0.iter().map(|(k, v)| (*k, v.as_str())).collect()),
+    );
+    let payload = Payload(Value::Object(payload).to_string());
+
+    let listener = bind_private(&path, &path_str)?;
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let handle = std::thread::spawn({
+        let stopped = stopped.clone();
+        move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(mut stream) = stream {
+                    if peer_uid(&stream) == Some(allowed_uid) {
+                        let _ = stream.write_all(payload.0.as_bytes());
+                    }
+                }
+            }
+        }
+    });
+    Ok(ConfigServer {
+        path,
+        stopped,
+        handle: Some(handle),
+    })
+}
+
+// Binds the socket in a directory only this user can enter, and links it to
+// the final path once it has owner-only permissions, so that no other user can
+// connect in between (which they could with a chmod after binding in place).
+// Unlike a rename, linking never replaces what is already at the path: a
+// socket left behind by a previous run is removed first, but anything else is
+// left alone.
+fn bind_private(path: &Path, path_str: &str) -> Result<UnixListener, ConfigError> {
+    let serve_error = |e: std::io::Error| FailedToServeConfig::with_source(path_str, e);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let staging = path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(format!(".{name}.{}.tmp", std::process::id()));
+    // Left behind by a previous run that had the same pid.
+    if std::fs::symlink_metadata(&staging).is_ok() {
+        std::fs::remove_dir_all(&staging).map_err(serve_error)?;
+    }
+    DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .map_err(serve_error)?;
+    let staged = staging.join("config.sock");
+    let bound = UnixListener::bind(&staged)
+        .and_then(|listener| {
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+            Ok(listener)
+        })
+        .map_err(serve_error)
+        .and_then(|listener| {
+            link_new(&staged, path, path_str)?;
+            Ok(listener)
+        });
+    let _ = std::fs::remove_file(&staged);
+    let _ = std::fs::remove_dir(&staging);
+    bound
+}
+
+fn link_new(staged: &Path, path: &Path, path_str: &str) -> Result<(), ConfigError> {
+    let serve_error = |e: std::io::Error| FailedToServeConfig::with_source(path_str, e);
+    match std::fs::hard_link(staged, path) {
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        linked => return linked.map_err(serve_error),
+    }
+    let is_socket = std::fs::symlink_metadata(path)
+        .map(|metadata| metadata.file_type().is_socket())
+        .unwrap_or(false);
+    if !is_socket {
+        return Err(SocketPathInUse::new(path_str));
+    }
+    std::fs::remove_file(path).map_err(serve_error)?;
+    // Fails (rather than replacing it) if anything was put at the path since.
+    std::fs::hard_link(staged, path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => SocketPathInUse::new(path_str),
+        _ => serve_error(e),
+    })
+}
+
+// The serialized config, secrets included, zeroed once the server stops.
+struct Payload(String);
+
+impl Drop for Payload {
+    fn drop(&mut self) {
+        #[cfg(feature = "hardened")]
+        zeroize::Zeroize::zeroize(&mut self.0);
+        #[cfg(not(feature = "hardened"))]
+        {
+            let mut bytes = std::mem::take(&mut self.0).into_bytes();
+            bytes.fill(0);
+            std::hint::black_box(&bytes);
+        }
+    }
+}
+
+// The effective user ID of the process on the other end, as recorded by the
+// kernel when it connected.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: cred and len describe a writable ucred, as SO_PEERCRED expects.
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    (result == 0).then_some(cred.uid)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    let (mut uid, mut gid) = (0, 0);
+    // SAFETY: uid and gid are writable, as getpeereid expects.
+    let result = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+    (result == 0).then_some(uid)
+}
+
+impl Drop for ConfigServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake up the accept loop, so it sees the flag.
+        let _ = UnixStream::connect(&self.path);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+pub fn connect_config<E: EnvConfigEnum, S: SecretsConfigEnum>(
+    path: impl AsRef<Path>,
+) -> Result<(EnvVariables<E>, SecretValues<S>), ConfigError> {
+    let path_str = path.as_ref().display().to_string();
+    let mut payload = String::new();
+    UnixStream::connect(path.as_ref())
+        .and_then(|mut stream| stream.read_to_string(&mut payload))
+        .map_err(|e| FailedToReceiveSharedConfig::with_source(&path_str, e))?;
+    let mut payload = serde_json::from_str::<HashMap<String, HashMap<String, String>>>(&payload)
+        .map_err(|e| InvalidSharedConfig::with_source(&path_str, "not a map of sections", e))?;
+    let mut section = |name: &str| {
+        payload
+            .remove(name)
+            .ok_or_else(|| InvalidSharedConfig::new(&path_str, &format!("missing '{name}'")))
+    };
+    let (env, secrets) = (section("env")?, section("secrets")?);
+
+    // The supervisor already validated the values, so only check that the
+    // worker's configs are covered by it.
+    let mut env_map = HashMap::with_capacity(E::COUNT);
+    for key in E::value_list() {
+        match env.get(key.as_str()) {
+            Some(value) => {
+                env_map.insert(key.as_str(), value.clone());
+            }
+            None if may_be_absent(key) => {}
+            None => return Err(MissingEnvVariableError::new(key.as_str(), "")),
+        }
+    }
+    let metadata = LoadMetadata::new(format!("shared:{path_str}"));
+    Ok((
+        EnvVariables::from(env_map).with_metadata(metadata.clone()),
+        load_secrets_from_source(&secrets)?.with_metadata(metadata),
+    ))
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        define_env_config, define_env_variable, define_secret_key, define_secrets_config,
+        EnvConfigEnum, EnvVariables, SecretValues, SecretsConfigEnum,
+    };
+
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::{connect_config, serve_config, serve_config_for_uid};
+
+    define_env_variable!(SHARE_REGION);
+    define_env_variable!(SHARE_BUCKET);
+    define_secret_key!(SHARE_API_KEY);
+
+    define_env_config!(
+        ShareEnvConfig,
+        Region => SHARE_REGION,
+        Bucket => SHARE_BUCKET,
+    );
+    define_env_config!(
+        WorkerEnvConfig,
+        Region => SHARE_REGION,
+    );
+    define_secrets_config!(
+        ShareSecretsConfig,
+        ApiKey => SHARE_API_KEY,
+    );
+
+    #[test]
+    fn test_serve_and_connect_config() {
+        let path = std::env::temp_dir().join(format!(
+            "fractic_env_config_share_{}.sock",
+            std::process::id()
+        ));
+        let env = EnvVariables::<WorkerEnvConfig>::from(HashMap::from([(
+            SHARE_REGION,
+            String::from("us-west-2"),
+        )]));
+        let secrets = SecretValues::<ShareSecretsConfig>::from(HashMap::from([(
+            SHARE_API_KEY,
+            String::from("abc123"),
+        )]));
+        let server = serve_config(&path, &env, &secrets).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let (env, secrets) = connect_config::<WorkerEnvConfig, ShareSecretsConfig>(&path).unwrap();
+        assert_eq!(env.get(&WorkerEnvConfig::Region).unwrap(), "us-west-2");
+        assert_eq!(secrets.get(&ShareSecretsConfig::ApiKey).unwrap(), "abc123");
+        assert!(connect_config::<ShareEnvConfig, ShareSecretsConfig>(&path).is_err());
+
+        drop(server);
+        assert!(!path.exists());
+        assert!(connect_config::<WorkerEnvConfig, ShareSecretsConfig>(&path).is_err());
+    }
+
+    #[test]
+    fn test_serve_config_checks_peer_and_path() {
+        let dir =
+            std::env::temp_dir().join(format!("fractic_env_config_share_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let env = EnvVariables::<WorkerEnvConfig>::from(HashMap::from([(
+            SHARE_REGION,
+            String::from("us-west-2"),
+        )]));
+        let secrets = SecretValues::<ShareSecretsConfig>::from(HashMap::from([(
+            SHARE_API_KEY,
+            String::from("abc123"),
+        )]));
+
+        // Connections from other users get nothing.
+        let path = dir.join("other_user.sock");
+        // SAFETY: geteuid has no preconditions.
+        let other_uid = unsafe { libc::geteuid() }.wrapping_add(1);
+        let server = serve_config_for_uid(&path, &env, &secrets, other_uid).unwrap();
+        assert!(connect_config::<WorkerEnvConfig, ShareSecretsConfig>(&path).is_err());
+        drop(server);
+
+        // A file that isn't a socket is never replaced.
+        let path = dir.join("not_a_socket");
+        std::fs::write(&path, "keep me").unwrap();
+        assert!(serve_config(&path, &env, &secrets).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+
+        // Sockets and staging directories left behind by a crashed run are
+        // replaced.
+        let path = dir.join("stale.sock");
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        std::fs::create_dir(dir.join(format!(".stale.sock.{}.tmp", std::process::id()))).unwrap();
+        let server = serve_config(&path, &env, &secrets).unwrap();
+        assert!(connect_config::<WorkerEnvConfig, ShareSecretsConfig>(&path).is_ok());
+        drop(server);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aws_config::{BehaviorVersion, SdkConfig};
+use aws_sdk_sts::Client;
+
+use crate::ConfigError;
+use crate::{
+    define_env_config, define_env_variable, define_secret_key, define_secrets_config,
+    EnvConfigEnum, EnvVariables, FailedToAssumeRole, SecretValues, SecretsConfigEnum,
+};
+
+define_env_variable!(ASSUME_ROLE_ARN);
+define_env_variable!(ASSUME_ROLE_SESSION_NAME);
+define_secret_key!(AWS_ACCESS_KEY_ID);
+define_secret_key!(AWS_SECRET_ACCESS_KEY);
+define_secret_key!(AWS_SESSION_TOKEN);
+
+define_env_config!(
+    AssumeRoleEnvConfig,
+    RoleArn => ASSUME_ROLE_ARN { matches = "^arn:aws[a-z-]*:iam::[0-9]+:role/.+$" },
+    SessionName => ASSUME_ROLE_SESSION_NAME { default = "fractic-env-config" },
+);
+
+define_secrets_config!(
+    AssumedRoleCredentials,
+    AccessKeyId => AWS_ACCESS_KEY_ID,
+    SecretAccessKey => AWS_SECRET_ACCESS_KEY,
+    SessionToken => AWS_SESSION_TOKEN,
+);
+
+// Assumed role credentials.
+// --------------------------------------------------
+
+// Services acting on behalf of another account (or with narrower permissions
+// than their own) can declare the role to assume in their config
+// (ASSUME_ROLE_ARN, and optionally ASSUME_ROLE_SESSION_NAME), and read the
+// temporary credentials like any other secrets:
+//
+// let role = AssumedRole::load(&window!(env => AssumeRoleEnvConfig)).await?;
+//
+// let credentials = role.credentials().await?;
+// let client = ThirdPartyClient::new(
+//     credentials.get(&AssumedRoleCredentials::AccessKeyId)?,
+//     credentials.get(&AssumedRoleCredentials::SecretAccessKey)?,
+//     credentials.get(&AssumedRoleCredentials::SessionToken)?,
+// );
+//
+// The role is assumed with the service's own credentials on first use, and
+// again whenever the credentials are within 5 minutes of expiring, so
+// credentials() can be called before each use rather than holding on to the
+// values. Available with the "sts" feature.
+pub struct AssumedRole {
+    client: Client,
+    role_arn: String,
+    session_name: String,
+    cached: Mutex<Option<(SecretValues<AssumedRoleCredentials>, SystemTime)>>,
+}
+
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+impl AssumedRole {
+    pub async fn load(env: &EnvVariables<AssumeRoleEnvConfig>) -> Result<Self, ConfigError> {
+        let sdk_config = aws_config::load_defaults(BehaviorVersion::v2024_03_28()).await;
+        Self::from_sdk_config(env, &sdk_config)
+    }
+
+    pub fn from_sdk_config(
+        env: &EnvVariables<AssumeRoleEnvConfig>,
+        sdk_config: &SdkConfig,
+    ) -> Result<Self, ConfigError> {
+        Ok(Self {
+            client: Client::new(sdk_config),
+            role_arn: env.get(&AssumeRoleEnvConfig::RoleArn)?.to_string(),
+            session_name: env.get(&AssumeRoleEnvConfig::SessionName)?.to_string(),
+            cached: Mutex::new(None),
+        })
+    }
+
+    pub fn role_arn(&self) -> &str {
+        &self.role_arn
+    }
+
+    pub async fn credentials(&self) -> Result<SecretValues<AssumedRoleCredentials>, ConfigError> {
+        if let Some(credentials) = self.cached_credentials(SystemTime::now()) {
+            return Ok(credentials);
+        }
+        let output = self
+            .client
+            .assume_role()
+            .role_arn(&self.role_arn)
+            .role_session_name(&self.session_name)
+            .send()
+            .await
+            .map_err(|e| {
+                FailedToAssumeRole::with_source(&self.role_arn, "AssumeRole request failed", e)
+            })?;
+        let assumed = output.credentials().ok_or_else(|| {
+            FailedToAssumeRole::new(&self.role_arn, "no credentials in the response")
+        })?;
+        let credentials = SecretValues::from(HashMap::from([
+            (AWS_ACCESS_KEY_ID, assumed.access_key_id().to_string()),
+            (
+                AWS_SECRET_ACCESS_KEY,
+                assumed.secret_access_key().to_string(),
+            ),
+            (AWS_SESSION_TOKEN, assumed.session_token().to_string()),
+        ]));
+        let expiry = UNIX_EPOCH + Duration::from_secs(assumed.expiration().secs().max(0) as u64);
+        *self.lock_cached() = Some((credentials.clone(), expiry));
+        Ok(credentials)
+    }
+
+    fn cached_credentials(&self, now: SystemTime) -> Option<SecretValues<AssumedRoleCredentials>> {
+        self.lock_cached()
+            .as_ref()
+            .filter(|(_, expiry)| now + REFRESH_MARGIN < *expiry)
+            .map(|(credentials, _)| credentials.clone())
+    }
+
+    fn lock_cached(
+        &self,
+    ) -> std::sync::MutexGuard<'_, Option<(SecretValues<AssumedRoleCredentials>, SystemTime)>> {
+        self.cached.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for AssumedRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssumedRole")
+            .field("role_arn", &self.role_arn)
+            .field("session_name", &self.session_name)
+            .finish_non_exhaustive()
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{Duration, SystemTime};
+
+    use aws_config::SdkConfig;
+
+    use crate::{load_from_source, SecretValues};
+
+    use super::{
+        AssumeRoleEnvConfig, AssumedRole, AssumedRoleCredentials, AWS_ACCESS_KEY_ID,
+        AWS_SECRET_ACCESS_KEY, AWS_SESSION_TOKEN,
+    };
+
+    #[test]
+    fn test_cached_credentials() {
+        let source = HashMap::from([(
+            String::from("ASSUME_ROLE_ARN"),
+            String::from("arn:aws:iam::123456789012:role/reader"),
+        )]);
+        let env = load_from_source::<AssumeRoleEnvConfig>(&source).unwrap();
+        let sdk_config = SdkConfig::builder().build();
+        let role = AssumedRole::from_sdk_config(&env, &sdk_config).unwrap();
+        assert_eq!(role.session_name, "fractic-env-config");
+
+        let now = SystemTime::now();
+        assert!(role.cached_credentials(now).is_none());
+        *role.lock_cached() = Some((
+            SecretValues::<AssumedRoleCredentials>::from(HashMap::from([
+                (AWS_ACCESS_KEY_ID, String::from("ASIA...")),
+                (AWS_SECRET_ACCESS_KEY, String::from("secret")),
+                (AWS_SESSION_TOKEN, String::from("token")),
+            ])),
+            now + Duration::from_secs(3600),
+        ));
+        assert!(role.cached_credentials(now).is_some());
+        assert!(role
+            .cached_credentials(now + Duration::from_secs(3400))
+            .is_none());
+
+        let source = HashMap::from([(String::from("ASSUME_ROLE_ARN"), String::from("reader"))]);
+        assert!(load_from_source::<AssumeRoleEnvConfig>(&source).is_err());
+    }
+}
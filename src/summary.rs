@@ -0,0 +1,261 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde_json::{Map, Value};
+
+use crate::from_env::snapshot::hash_value;
+#[cfg(feature = "aws")]
+use crate::{CachedSecretsFetcher, LoadReport, SecretsFetcher};
+use crate::{EnvConfigEnum, EnvVariables, SecretValues, SecretsConfigEnum};
+
+// Startup summary.
+// --------------------------------------------------
+
+// A single structured "config loaded" event, to log once at startup so every
+// deploy's configuration can be confirmed (and searched for) from the logs:
+//
+// let ((env, secrets), report) = load_concurrently(budget, sources).await?;
+// let summary = StartupSummary::new()
+//     .with_env(&env)
+//     .with_secrets(&secrets)
+//     .with_load_report(&report)
+//     .with_secrets_cache(&fetcher);
+// println!("{summary}");
+//
+// Prints one line of JSON:
+//
+// {"event":"config_loaded","env_keys":12,"secret_keys":3,"sources":["env","secrets"],
+//  "total_ms":412,"durations_ms":{"env":0,"secrets":411},"cache":{...},"fingerprint":"sha256:..."}
+//
+// The fingerprint is a hash over every loaded key and (hashed) value, so two
+// deploys with the same fingerprint ran with the same configuration, without
+// the values themselves ever being logged. Sources loaded without a LoadReport
+// can be named with source.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StartupSummary {
+    env_keys: usize,
+    secret_keys: usize,
+    sources: Vec<String>,
+    total: Option<Duration>,
+    durations: Vec<(String, Duration)>,
+    cache: Option<CacheSummary>,
+    value_hashes: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheSummary {
+    pub cached_secrets: usize,
+    pub coalesced_fetches: u64,
+    pub failed_refreshes: u64,
+    pub staleness: Duration,
+}
+
+impl StartupSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_env<T: EnvConfigEnum>(mut self, env: &EnvVariables<T>) -> Self {
+        self.env_keys += env.0.len();
+        self.value_hashes.extend(
+            env.0
+                .iter()
+                .map(|(key, value)| (format!("env:{key}"), hash_value(value))),
+        );
+        self
+    }
+
+    pub fn with_secrets<S: SecretsConfigEnum>(mut self, secrets: &SecretValues<S>) -> Self {
+        self.secret_keys += secrets.0.len();
+        self.value_hashes.extend(
+            secrets
+                .0
+                .iter()
+                .map(|(key, value)| (format!("secret:{key}"), hash_value(value))),
+        );
+        self
+    }
+
+    #[cfg(feature = "aws")]
+    pub fn with_load_report(mut self, report: &LoadReport) -> Self {
+        self.total = Some(self.total.unwrap_or_default() + report.total());
+        for timing in report.sources() {
+            self = self.source(timing.name);
+            self.durations
+                .push((timing.name.to_string(), timing.elapsed));
+        }
+        self
+    }
+
+    #[cfg(feature = "aws")]
+    pub fn with_secrets_cache<F: SecretsFetcher + 'static>(
+        mut self,
+        cache: &CachedSecretsFetcher<F>,
+    ) -> Self {
+        self.cache = Some(CacheSummary {
+            cached_secrets: cache.cached_secret_ids().len(),
+            coalesced_fetches: cache.coalesced_fetches(),
+            failed_refreshes: cache.failed_refreshes(),
+            staleness: cache.staleness(),
+        });
+        self
+    }
+
+    pub fn source(mut self, name: &str) -> Self {
+        if !self.sources.iter().any(|source| source == name) {
+            self.sources.push(name.to_string());
+        }
+        self
+    }
+
+    pub fn env_keys(&self) -> usize {
+        self.env_keys
+    }
+
+    pub fn secret_keys(&self) -> usize {
+        self.secret_keys
+    }
+
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    pub fn cache(&self) -> Option<&CacheSummary> {
+        self.cache.as_ref()
+    }
+
+    pub fn fingerprint(&self) -> String {
+        let mut hashes = self.value_hashes.clone();
+        hashes.sort();
+        let hashes = hashes
+            .iter()
+            .map(|(key, hash)| format!("{key}={hash}\n"))
+            .collect::<String>();
+        hash_value(&hashes)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let millis = |duration: &Duration| Value::from(duration.as_millis() as u64);
+        let mut json = Map::new();
+        json.insert("event".to_string(), Value::from("config_loaded"));
+        json.insert("env_keys".to_string(), Value::from(self.env_keys));
+        json.insert("secret_keys".to_string(), Value::from(self.secret_keys));
+        json.insert(
+            "sources".to_string(),
+            Value::Array(
+                self.sources
+                    .iter()
+                    .map(|s| Value::from(s.as_str()))
+                    .collect(),
+            ),
+        );
+        json.insert(
+            "total_ms".to_string(),
+            self.total.as_ref().map(millis).unwrap_or(Value::Null),
+        );
+        json.insert(
+            "durations_ms".to_string(),
+            Value::Object(
+                self.durations
+                    .iter()
+                    .map(|(name, duration)| (name.clone(), millis(duration)))
+                    .collect(),
+            ),
+        );
+        json.insert(
+            "cache".to_string(),
+            self.cache.as_ref().map_or(Value::Null, |cache| {
+                let mut json = Map::new();
+                json.insert(
+                    "cached_secrets".to_string(),
+                    Value::from(cache.cached_secrets),
+                );
+                json.insert(
+                    "coalesced_fetches".to_string(),
+                    Value::from(cache.coalesced_fetches),
+                );
+                json.insert(
+                    "failed_refreshes".to_string(),
+                    Value::from(cache.failed_refreshes),
+                );
+                json.insert(
+                    "staleness_secs".to_string(),
+                    Value::from(cache.staleness.as_secs()),
+                );
+                Value::Object(json)
+            }),
+        );
+        json.insert("fingerprint".to_string(), Value::from(self.fingerprint()));
+        Value::Object(json)
+    }
+}
+
+impl fmt::Display for StartupSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_json())
+    }
+}
+
+// Tests.
+// --------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        define_env_config, define_env_variable, define_secret_key, define_secrets_config,
+        EnvConfigEnum, EnvVariables, SecretValues, SecretsConfigEnum,
+    };
+
+    use super::StartupSummary;
+
+    define_env_variable!(SUMMARY_REGION);
+    define_secret_key!(SUMMARY_API_KEY);
+
+    define_env_config!(
+        SummaryEnvConfig,
+        Region => SUMMARY_REGION,
+    );
+    define_secrets_config!(
+        SummarySecretsConfig,
+        ApiKey => SUMMARY_API_KEY,
+    );
+
+    fn summary(api_key: &str) -> StartupSummary {
+        let env = EnvVariables::<SummaryEnvConfig>::from(HashMap::from([(
+            SUMMARY_REGION,
+            String::from("us-west-2"),
+        )]));
+        let secrets = SecretValues::<SummarySecretsConfig>::from(HashMap::from([(
+            SUMMARY_API_KEY,
+            String::from(api_key),
+        )]));
+        StartupSummary::new()
+            .with_env(&env)
+            .with_secrets(&secrets)
+            .source("process-env")
+            .source("secrets-manager")
+    }
+
+    #[test]
+    fn test_startup_summary() {
+        let loaded = summary("abc123");
+        assert_eq!((loaded.env_keys(), loaded.secret_keys()), (1, 1));
+        assert_eq!(loaded.sources(), ["process-env", "secrets-manager"]);
+        assert_eq!(loaded.fingerprint(), summary("abc123").fingerprint());
+        assert_ne!(loaded.fingerprint(), summary("def456").fingerprint());
+
+        let line = loaded.to_string();
+        assert!(!line.contains('\n'));
+        assert!(!line.contains("abc123"));
+        let json = loaded.to_json();
+        assert_eq!(json.get("event").unwrap().as_str(), Some("config_loaded"));
+        assert!(json
+            .get("fingerprint")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .starts_with("sha256:"));
+    }
+}